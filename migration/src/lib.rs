@@ -13,6 +13,28 @@ mod m20240531_140339_create_reviews_table;
 mod m20240604_185157_make_email_unique;
 mod m20240607_123717_add_user_role;
 mod m20240612_180337_add_version_protocol;
+mod m20261019_090000_create_pubsub_tables;
+mod m20261019_100000_add_auth_token_rotation;
+mod m20261019_110000_add_email_verification;
+mod m20261019_120000_create_login_attempts_table;
+mod m20261019_130000_create_totp_tables;
+mod m20261019_140000_add_minecraft_account;
+mod m20261019_150000_create_user_roles_table;
+mod m20261019_160000_add_category_hierarchy;
+mod m20261019_170000_add_catalog_archival;
+mod m20261019_180000_add_version_release_info;
+mod m20261019_190000_add_version_ordinal;
+mod m20261019_200000_fix_server_categories;
+mod m20261019_210000_create_tags_tables;
+mod m20261019_220000_create_server_images_table;
+mod m20261019_230000_add_players_graph_online;
+mod m20261019_233000_create_players_rollup_tables;
+mod m20261020_000000_add_auth_session_start;
+mod m20261020_010000_create_login_throttle_locks;
+mod m20261020_020000_create_oauth_nonces_table;
+mod m20261020_030000_hash_auth_tokens;
+mod m20261020_040000_add_pending_email;
+mod m20261020_050000_add_servers_info_port;
 
 pub struct Migrator;
 
@@ -33,6 +55,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20240604_185157_make_email_unique::Migration),
             Box::new(m20240607_123717_add_user_role::Migration),
             Box::new(m20240612_180337_add_version_protocol::Migration),
+            Box::new(m20261019_090000_create_pubsub_tables::Migration),
+            Box::new(m20261019_100000_add_auth_token_rotation::Migration),
+            Box::new(m20261019_110000_add_email_verification::Migration),
+            Box::new(m20261019_120000_create_login_attempts_table::Migration),
+            Box::new(m20261019_130000_create_totp_tables::Migration),
+            Box::new(m20261019_140000_add_minecraft_account::Migration),
+            Box::new(m20261019_150000_create_user_roles_table::Migration),
+            Box::new(m20261019_160000_add_category_hierarchy::Migration),
+            Box::new(m20261019_170000_add_catalog_archival::Migration),
+            Box::new(m20261019_180000_add_version_release_info::Migration),
+            Box::new(m20261019_190000_add_version_ordinal::Migration),
+            Box::new(m20261019_200000_fix_server_categories::Migration),
+            Box::new(m20261019_210000_create_tags_tables::Migration),
+            Box::new(m20261019_220000_create_server_images_table::Migration),
+            Box::new(m20261019_230000_add_players_graph_online::Migration),
+            Box::new(m20261019_233000_create_players_rollup_tables::Migration),
+            Box::new(m20261020_000000_add_auth_session_start::Migration),
+            Box::new(m20261020_010000_create_login_throttle_locks::Migration),
+            Box::new(m20261020_020000_create_oauth_nonces_table::Migration),
+            Box::new(m20261020_030000_hash_auth_tokens::Migration),
+            Box::new(m20261020_040000_add_pending_email::Migration),
+            Box::new(m20261020_050000_add_servers_info_port::Migration),
         ]
     }
 }
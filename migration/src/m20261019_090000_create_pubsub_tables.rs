@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PubsubMessages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PubsubMessages::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PubsubMessages::Instance).string().not_null())
+                    .col(ColumnDef::new(PubsubMessages::Payload).text().not_null())
+                    .col(
+                        ColumnDef::new(PubsubMessages::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_PubsubMessages_CreatedAt")
+                    .table(PubsubMessages::Table)
+                    .col(PubsubMessages::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LeaderLease::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LeaderLease::Name)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LeaderLease::Holder).string().not_null())
+                    .col(
+                        ColumnDef::new(LeaderLease::ExpiresAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LeaderLease::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PubsubMessages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PubsubMessages {
+    Table,
+    Id,
+    Instance,
+    Payload,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum LeaderLease {
+    Table,
+    Name,
+    Holder,
+    ExpiresAt,
+}
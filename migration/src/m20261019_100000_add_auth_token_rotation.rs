@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .add_column(
+                        ColumnDef::new(Auth::Family)
+                            .string()
+                            .not_null()
+                            .default("")
+                            .extra("AFTER user_id"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Auth::RevokedAt)
+                            .date_time()
+                            .null()
+                            .extra("AFTER expires_at"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Auth::ReplacedBy)
+                            .integer()
+                            .null()
+                            .extra("AFTER revoked_at"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing tokens each become their own session
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE auth SET family = CONCAT('legacy-', id) WHERE family = ''")
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_Auth_Token")
+                    .table(Auth::Table)
+                    .col(Auth::Token)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_Auth_Family")
+                    .table(Auth::Table)
+                    .col(Auth::Family)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Auth::Table)
+                    .name("IDX_Auth_Family")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Auth::Table)
+                    .name("IDX_Auth_Token")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .drop_column(Auth::Family)
+                    .drop_column(Auth::RevokedAt)
+                    .drop_column(Auth::ReplacedBy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Auth {
+    Table,
+    Token,
+    Family,
+    RevokedAt,
+    ReplacedBy,
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_134809_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("email_verified_at"))
+                            .date_time()
+                            .null()
+                            .extra("AFTER email"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Accounts created before verification existed keep working
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE users SET email_verified_at = NOW()")
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserTokens::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserTokens::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(UserTokens::Purpose)
+                            .enumeration(
+                                Alias::new("purpose"),
+                                vec![Alias::new("VerifyEmail"), Alias::new("ResetPassword")],
+                            )
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserTokens::Jti)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserTokens::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .col(ColumnDef::new(UserTokens::ExpiresAt).date_time().not_null())
+                    .col(ColumnDef::new(UserTokens::UsedAt).date_time().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_UserTokens_Users")
+                            .from(UserTokens::Table, UserTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserTokens::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Alias::new("email_verified_at"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserTokens {
+    Table,
+    Id,
+    UserId,
+    Purpose,
+    Jti,
+    CreatedAt,
+    ExpiresAt,
+    UsedAt,
+}
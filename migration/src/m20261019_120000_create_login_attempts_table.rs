@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginAttempts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginAttempts::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginAttempts::Email).string().not_null())
+                    .col(ColumnDef::new(LoginAttempts::UserId).integer().null())
+                    .col(ColumnDef::new(LoginAttempts::Ip).string_len(45).null())
+                    .col(
+                        ColumnDef::new(LoginAttempts::Outcome)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LoginAttempts::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_LoginAttempts_Email_CreatedAt")
+                    .table(LoginAttempts::Table)
+                    .col(LoginAttempts::Email)
+                    .col(LoginAttempts::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_LoginAttempts_Ip_CreatedAt")
+                    .table(LoginAttempts::Table)
+                    .col(LoginAttempts::Ip)
+                    .col(LoginAttempts::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginAttempts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LoginAttempts {
+    Table,
+    Id,
+    Email,
+    UserId,
+    Ip,
+    Outcome,
+    CreatedAt,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_134809_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserTotp::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserTotp::UserId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserTotp::Secret).string().not_null())
+                    .col(ColumnDef::new(UserTotp::EnabledAt).date_time().null())
+                    .col(ColumnDef::new(UserTotp::LastUsedStep).big_integer().null())
+                    .col(
+                        ColumnDef::new(UserTotp::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_UserTotp_Users")
+                            .from(UserTotp::Table, UserTotp::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecoveryCodes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecoveryCodes::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RecoveryCodes::CodeHash)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RecoveryCodes::UsedAt).date_time().null())
+                    .col(
+                        ColumnDef::new(RecoveryCodes::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_RecoveryCodes_Users")
+                            .from(RecoveryCodes::Table, RecoveryCodes::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCodes::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserTotp::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserTotp {
+    Table,
+    UserId,
+    Secret,
+    EnabledAt,
+    LastUsedStep,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RecoveryCodes {
+    Table,
+    Id,
+    UserId,
+    CodeHash,
+    UsedAt,
+    CreatedAt,
+}
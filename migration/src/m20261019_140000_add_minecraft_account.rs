@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_134809_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("minecraft_uuid"))
+                            .string_len(36)
+                            .null()
+                            .unique_key()
+                            .extra("AFTER username"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Alias::new("minecraft_name"))
+                            .string_len(16)
+                            .null()
+                            .extra("AFTER minecraft_uuid"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Alias::new("minecraft_linked_at"))
+                            .date_time()
+                            .null()
+                            .extra("AFTER minecraft_name"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Alias::new("minecraft_uuid"))
+                    .drop_column(Alias::new("minecraft_name"))
+                    .drop_column(Alias::new("minecraft_linked_at"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_134809_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRoles::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserRoles::UserId).integer().not_null())
+                    .col(ColumnDef::new(UserRoles::Role).string_len(32).not_null())
+                    .col(ColumnDef::new(UserRoles::GrantedBy).integer().null())
+                    .col(
+                        ColumnDef::new(UserRoles::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .primary_key(Index::create().col(UserRoles::UserId).col(UserRoles::Role))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_UserRoles_Users")
+                            .from(UserRoles::Table, UserRoles::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_UserRoles_GrantedBy")
+                            .from(UserRoles::Table, UserRoles::GrantedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Admins keep their role, users without any role are plain users
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO user_roles (user_id, role) SELECT id, 'Admin' FROM users WHERE role = 'Admin'",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Alias::new("role"))
+                    .to_owned(),
+            )
+            .await?;
+
+        for table in ["servers", "reviews"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .add_column(ColumnDef::new(Alias::new("hidden_at")).date_time().null())
+                        .add_column(ColumnDef::new(Alias::new("hidden_by")).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in ["servers", "reviews"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Alias::new("hidden_at"))
+                        .drop_column(Alias::new("hidden_by"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("role"))
+                            .enumeration(
+                                Alias::new("role"),
+                                vec![Alias::new("Admin"), Alias::new("User")],
+                            )
+                            .default("User")
+                            .not_null()
+                            .extra("AFTER password"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Other roles have no equivalent and are lost
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE users SET role = 'Admin' WHERE id IN (SELECT user_id FROM user_roles WHERE role = 'Admin')",
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserRoles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserRoles {
+    Table,
+    UserId,
+    Role,
+    GrantedBy,
+    CreatedAt,
+}
@@ -0,0 +1,138 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use crate::m20240531_140207_create_categories_table::Categories;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const MAX_SLUG_ROUNDS: usize = 3;
+
+async fn duplicate_slugs(db: &SchemaManagerConnection<'_>) -> Result<i64, DbErr> {
+    let row = db
+        .query_one(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT COUNT(*) AS duplicates FROM (SELECT slug FROM categories GROUP BY slug HAVING COUNT(*) > 1) d",
+        ))
+        .await?;
+
+    match row {
+        Some(row) => row.try_get("", "duplicates"),
+        None => Ok(0),
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Categories::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("parent_id"))
+                            .integer()
+                            .null()
+                            .extra("AFTER id"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Alias::new("slug"))
+                            .string_len(64)
+                            .null()
+                            .extra("AFTER name"),
+                    )
+                    .add_column(ColumnDef::new(Alias::new("description")).text().null())
+                    .add_column(ColumnDef::new(Alias::new("icon")).string_len(255).null())
+                    .add_column(ColumnDef::new(Alias::new("colour")).string_len(7).null())
+                    .add_column(
+                        ColumnDef::new(Alias::new("sort_order"))
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("FK_Categories_Parent")
+                            .from_tbl(Categories::Table)
+                            .from_col(Alias::new("parent_id"))
+                            .to_tbl(Categories::Table)
+                            .to_col(Categories::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing categories get a slug from their name, cut to fit the column
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE categories SET slug = TRIM(BOTH '-' FROM LEFT(TRIM(BOTH '-' FROM REGEXP_REPLACE(LOWER(TRIM(name)), '[^a-z0-9]+', '-')), 64))",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE categories SET slug = CONCAT('category-', id) WHERE slug = ''",
+        )
+        .await?;
+
+        // The oldest of a duplicate keeps the slug, the others get their id appended, cut so
+        // it still fits. An appended id can meet an existing slug, so this is checked again.
+        for _ in 0..MAX_SLUG_ROUNDS {
+            if duplicate_slugs(db).await? == 0 {
+                break;
+            }
+
+            db.execute_unprepared(
+                "UPDATE categories c
+                JOIN (SELECT slug, MIN(id) AS keep_id FROM categories GROUP BY slug HAVING COUNT(*) > 1) d
+                    ON c.slug = d.slug AND c.id <> d.keep_id
+                SET c.slug = CONCAT(TRIM(BOTH '-' FROM LEFT(c.slug, 63 - LENGTH(c.id))), '-', c.id)",
+            )
+            .await?;
+        }
+
+        let duplicates = duplicate_slugs(db).await?;
+        if duplicates > 0 {
+            return Err(DbErr::Migration(format!(
+                "{duplicates} category slugs are still shared, rename those categories and run again"
+            )));
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Categories::Table)
+                    .modify_column(
+                        ColumnDef::new(Alias::new("slug"))
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Categories::Table)
+                    .drop_foreign_key(Alias::new("FK_Categories_Parent"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Categories::Table)
+                    .drop_column(Alias::new("parent_id"))
+                    .drop_column(Alias::new("slug"))
+                    .drop_column(Alias::new("description"))
+                    .drop_column(Alias::new("icon"))
+                    .drop_column(Alias::new("colour"))
+                    .drop_column(Alias::new("sort_order"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_140157_create_versions_table::Versions;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Versions::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("release_type"))
+                            .string_len(16)
+                            .null()
+                            .extra("AFTER protocol"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Alias::new("released_at"))
+                            .date_time()
+                            .null()
+                            .extra("AFTER release_type"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Versions::Table)
+                    .drop_column(Alias::new("release_type"))
+                    .drop_column(Alias::new("released_at"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_140157_create_versions_table::Versions;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Versions::Table)
+                    .add_column(
+                        ColumnDef::new(Alias::new("ordinal"))
+                            .integer()
+                            .not_null()
+                            .default(0)
+                            .extra("AFTER protocol"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Protocol numbers order the releases added by hand well enough to start from
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE versions v JOIN (SELECT id, ROW_NUMBER() OVER (ORDER BY protocol, id) AS n FROM versions) r ON v.id = r.id SET v.ordinal = r.n",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_Versions_Ordinal")
+                    .table(Versions::Table)
+                    .col(Alias::new("ordinal"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("IDX_Versions_Ordinal")
+                    .table(Versions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Versions::Table)
+                    .drop_column(Alias::new("ordinal"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,138 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20240531_140153_create_servers_table::Servers,
+    m20240531_140207_create_categories_table::Categories,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+async fn drop_foreign_keys(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    for name in [
+        "FK_ServerCategories_Servers",
+        "FK_ServerCategories_Categories",
+    ] {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(name)
+                    .table(ServerCategories::Table)
+                    .to_owned(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The old table has no key to dedupe by, so rows are copied into a new one
+        drop_foreign_keys(manager).await?;
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(ServerCategories::Table, Alias::new("server_categories_old"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ServerCategories::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ServerCategories::ServerId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ServerCategories::CategoryId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ServerCategories::IsPrimary)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ServerCategories::ServerId)
+                            .col(ServerCategories::CategoryId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ServerCategories_Servers")
+                            .from(ServerCategories::Table, ServerCategories::ServerId)
+                            .to(Servers::Table, Servers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ServerCategories_Categories")
+                            .from(ServerCategories::Table, ServerCategories::CategoryId)
+                            .to(Categories::Table, Categories::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The rows always held servers.id, whatever the old key pointed at
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO server_categories (server_id, category_id) SELECT DISTINCT o.server_id, o.category_id FROM server_categories_old o JOIN servers s ON s.id = o.server_id",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE server_categories sc JOIN (SELECT server_id, MIN(category_id) AS category_id FROM server_categories GROUP BY server_id) p ON p.server_id = sc.server_id AND p.category_id = sc.category_id SET sc.is_primary = TRUE",
+        )
+        .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(Alias::new("server_categories_old"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_foreign_keys(manager).await?;
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE server_categories DROP PRIMARY KEY")
+            .await?;
+
+        // The key to servers_info never matched the stored ids and is not restored
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServerCategories::Table)
+                    .drop_column(ServerCategories::IsPrimary)
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("FK_ServerCategories_Categories")
+                            .from_tbl(ServerCategories::Table)
+                            .from_col(ServerCategories::CategoryId)
+                            .to_tbl(Categories::Table)
+                            .to_col(Categories::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ServerCategories {
+    Table,
+    ServerId,
+    CategoryId,
+    IsPrimary,
+}
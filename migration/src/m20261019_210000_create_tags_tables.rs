@@ -0,0 +1,158 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20240531_134809_create_users_table::Users, m20240531_140153_create_servers_table::Servers,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ATTRIBUTES: [(&str, u32); 5] = [
+    ("language", 8),
+    ("country", 2),
+    ("website", 255),
+    ("discord_invite", 255),
+    ("store_url", 255),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tags::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tags::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Tags::Name)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Tags::Slug)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Tags::ApprovedAt).date_time().null())
+                    .col(ColumnDef::new(Tags::CreatedBy).integer().null())
+                    .col(
+                        ColumnDef::new(Tags::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_Tags_Users")
+                            .from(Tags::Table, Tags::CreatedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ServerTags::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ServerTags::ServerId).integer().not_null())
+                    .col(ColumnDef::new(ServerTags::TagId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ServerTags::ServerId)
+                            .col(ServerTags::TagId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ServerTags_Servers")
+                            .from(ServerTags::Table, ServerTags::ServerId)
+                            .to(Servers::Table, Servers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ServerTags_Tags")
+                            .from(ServerTags::Table, ServerTags::TagId)
+                            .to(Tags::Table, Tags::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Features players asked to filter by most, owners can suggest more
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO tags (name, slug, approved_at) VALUES ('Cracked', 'cracked', NOW()), ('Premium only', 'premium-only', NOW()), ('Whitelist', 'whitelist', NOW()), ('Cross-play', 'cross-play', NOW())",
+            )
+            .await?;
+
+        for (column, len) in ATTRIBUTES.into_iter().rev() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Servers::Table)
+                        .add_column(
+                            ColumnDef::new(Alias::new(column))
+                                .string_len(len)
+                                .null()
+                                .extra("AFTER description"),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (column, _) in ATTRIBUTES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Servers::Table)
+                        .drop_column(Alias::new(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(ServerTags::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tags::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tags {
+    Table,
+    Id,
+    Name,
+    Slug,
+    ApprovedAt,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ServerTags {
+    Table,
+    ServerId,
+    TagId,
+}
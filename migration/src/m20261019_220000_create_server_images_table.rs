@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_140153_create_servers_table::Servers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ServerImages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ServerImages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ServerImages::ServerId).integer().not_null())
+                    .col(ColumnDef::new(ServerImages::Kind).string_len(16).not_null())
+                    .col(ColumnDef::new(ServerImages::Url).string_len(512).not_null())
+                    .col(
+                        ColumnDef::new(ServerImages::StorageKey)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ServerImages::ThumbnailUrl)
+                            .string_len(512)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ServerImages::ThumbnailKey)
+                            .string_len(255)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ServerImages::ContentType)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ServerImages::Width).integer().not_null())
+                    .col(ColumnDef::new(ServerImages::Height).integer().not_null())
+                    .col(ColumnDef::new(ServerImages::Size).integer().not_null())
+                    .col(
+                        ColumnDef::new(ServerImages::CreatedAt)
+                            .date_time()
+                            .extra("DEFAULT CURRENT_TIMESTAMP"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_ServerImages_Servers")
+                            .from(ServerImages::Table, ServerImages::ServerId)
+                            .to(Servers::Table, Servers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_ServerImages_Server_Kind")
+                    .table(ServerImages::Table)
+                    .col(ServerImages::ServerId)
+                    .col(ServerImages::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ServerImages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ServerImages {
+    Table,
+    Id,
+    ServerId,
+    Kind,
+    Url,
+    StorageKey,
+    ThumbnailUrl,
+    ThumbnailKey,
+    ContentType,
+    Width,
+    Height,
+    Size,
+    CreatedAt,
+}
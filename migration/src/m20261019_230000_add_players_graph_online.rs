@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20240531_140153_create_servers_table::Servers,
+    m20240531_140213_create_servers_info_table::ServersInfo,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Samples are written with the id of the server, not of its info row
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("FK_PlayersGraph_Servers")
+                    .table(PlayersGraph::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE pg FROM players_graph pg
+                LEFT JOIN servers s ON s.id = pg.server_id
+                WHERE s.id IS NULL",
+            )
+            .await?;
+
+        // Failed pings were stored as 0 players, older samples count as online
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayersGraph::Table)
+                    .add_column(
+                        ColumnDef::new(PlayersGraph::Online)
+                            .boolean()
+                            .not_null()
+                            .default(true)
+                            .extra("AFTER players_online"),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("FK_PlayersGraph_Servers")
+                            .from_tbl(PlayersGraph::Table)
+                            .from_col(PlayersGraph::ServerId)
+                            .to_tbl(Servers::Table)
+                            .to_col(Servers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("FK_PlayersGraph_Servers")
+                    .table(PlayersGraph::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlayersGraph::Table)
+                    .drop_column(PlayersGraph::Online)
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("FK_PlayersGraph_Servers")
+                            .from_tbl(PlayersGraph::Table)
+                            .from_col(PlayersGraph::ServerId)
+                            .to_tbl(ServersInfo::Table)
+                            .to_col(ServersInfo::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayersGraph {
+    Table,
+    ServerId,
+    Online,
+}
@@ -0,0 +1,124 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_140153_create_servers_table::Servers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ROLLUP_TABLES: [(&str, &str); 2] = [
+    ("players_graph_hourly", "PlayersGraphHourly"),
+    ("players_graph_daily", "PlayersGraphDaily"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Retention deletes by date, history reads by server and date
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_PlayersGraph_Server_Date")
+                    .table(PlayersGraph::Table)
+                    .col(PlayersGraph::ServerId)
+                    .col(PlayersGraph::Date)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_PlayersGraph_Date")
+                    .table(PlayersGraph::Table)
+                    .col(PlayersGraph::Date)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Both rollups have the same shape, a row per server and hour or day
+        for (table, name) in ROLLUP_TABLES {
+            let table = Alias::new(table);
+            manager
+                .create_table(
+                    Table::create()
+                        .table(table.clone())
+                        .if_not_exists()
+                        .col(ColumnDef::new(Rollup::ServerId).integer().not_null())
+                        .col(ColumnDef::new(Rollup::Date).date_time().not_null())
+                        .col(ColumnDef::new(Rollup::Samples).integer().not_null())
+                        .col(ColumnDef::new(Rollup::Online).integer().not_null())
+                        .col(ColumnDef::new(Rollup::MinPlayers).integer().null())
+                        .col(ColumnDef::new(Rollup::MaxPlayers).integer().null())
+                        .col(
+                            ColumnDef::new(Rollup::TotalPlayers)
+                                .big_integer()
+                                .not_null(),
+                        )
+                        .primary_key(Index::create().col(Rollup::ServerId).col(Rollup::Date))
+                        .foreign_key(
+                            ForeignKey::create()
+                                .name(format!("FK_{name}_Servers"))
+                                .from(table.clone(), Rollup::ServerId)
+                                .to(Servers::Table, Servers::Id)
+                                .on_delete(ForeignKeyAction::Cascade),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("IDX_{name}_Date"))
+                        .table(table)
+                        .col(Rollup::Date)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, _) in ROLLUP_TABLES {
+            manager
+                .drop_table(Table::drop().table(Alias::new(table)).to_owned())
+                .await?;
+        }
+
+        for name in ["IDX_PlayersGraph_Date", "IDX_PlayersGraph_Server_Date"] {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(name)
+                        .table(PlayersGraph::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PlayersGraph {
+    Table,
+    ServerId,
+    Date,
+}
+
+#[derive(DeriveIden)]
+enum Rollup {
+    ServerId,
+    Date,
+    /// Pings in the hour or day
+    Samples,
+    /// Pings the server answered
+    Online,
+    /// Null when no ping was answered
+    MinPlayers,
+    MaxPlayers,
+    /// Players summed over answered pings, divided by `online` for the average
+    TotalPlayers,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .add_column(
+                        ColumnDef::new(Auth::SessionStartedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::current_timestamp())
+                            .extra("AFTER family"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Rotated tokens only know when they were issued, the earliest of a family is the closest
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE auth a
+                JOIN (
+                    SELECT family, MIN(created_at) AS started_at
+                    FROM auth
+                    WHERE created_at IS NOT NULL
+                    GROUP BY family
+                ) f ON f.family = a.family
+                SET a.session_started_at = f.started_at",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .drop_column(Auth::SessionStartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Auth {
+    Table,
+    SessionStartedAt,
+}
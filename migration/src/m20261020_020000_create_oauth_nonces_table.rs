@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthNonces::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthNonces::Nonce)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthNonces::ExpiresAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_OauthNonces_ExpiresAt")
+                    .table(OauthNonces::Table)
+                    .col(OauthNonces::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OauthNonces::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OauthNonces {
+    Table,
+    /// Nonce of a state that was already used
+    Nonce,
+    /// Kept until the state would have expired anyway
+    ExpiresAt,
+}
@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // RS256 refresh tokens do not fit in 255 characters, their hash always does
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .add_column(
+                        ColumnDef::new(Auth::TokenHash)
+                            .char_len(64)
+                            .null()
+                            .extra("AFTER token"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE auth SET token_hash = SHA2(token, 256)")
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Auth::Table)
+                    .name("IDX_Auth_Token")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .drop_column(Auth::Token)
+                    .modify_column(ColumnDef::new(Auth::TokenHash).char_len(64).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_Auth_TokenHash")
+                    .table(Auth::Table)
+                    .col(Auth::TokenHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Auth::Table)
+                    .name("IDX_Auth_TokenHash")
+                    .to_owned(),
+            )
+            .await?;
+
+        // Hashes can not be turned back into tokens, every session has to log in again
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM auth")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Auth::Table)
+                    .add_column(
+                        ColumnDef::new(Auth::Token)
+                            .string()
+                            .not_null()
+                            .extra("AFTER id"),
+                    )
+                    .drop_column(Auth::TokenHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("IDX_Auth_Token")
+                    .table(Auth::Table)
+                    .col(Auth::Token)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Auth {
+    Table,
+    Token,
+    /// Hex SHA-256 of the refresh token
+    TokenHash,
+}
@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Users::PendingEmail)
+                            .string()
+                            .null()
+                            .extra("AFTER email_verified_at"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserTokens::Table)
+                    .modify_column(&mut purpose(vec![
+                        Alias::new("VerifyEmail"),
+                        Alias::new("ResetPassword"),
+                        Alias::new("ChangeEmail"),
+                    ]))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM user_tokens WHERE purpose = 'ChangeEmail'")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserTokens::Table)
+                    .modify_column(&mut purpose(vec![
+                        Alias::new("VerifyEmail"),
+                        Alias::new("ResetPassword"),
+                    ]))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::PendingEmail)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn purpose(values: Vec<Alias>) -> ColumnDef {
+    ColumnDef::new(UserTokens::Purpose)
+        .enumeration(Alias::new("purpose"), values)
+        .not_null()
+        .to_owned()
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    /// Replaces `email` once the link mailed to it is opened
+    PendingEmail,
+}
+
+#[derive(DeriveIden)]
+enum UserTokens {
+    Table,
+    Purpose,
+}
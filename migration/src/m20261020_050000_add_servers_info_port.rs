@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240531_140213_create_servers_info_table::ServersInfo;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServersInfo::Table)
+                    .add_column(
+                        ColumnDef::new(Port::Port)
+                            .small_unsigned()
+                            .not_null()
+                            .default(25565)
+                            .extra("AFTER address"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Addresses saved before hosts were validated can still carry their port
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE servers_info
+                SET port = CAST(SUBSTRING_INDEX(address, ':', -1) AS UNSIGNED),
+                    address = SUBSTRING_INDEX(address, ':', 1)
+                WHERE address REGEXP '^[^:]+:[0-9]{1,5}$'
+                AND CAST(SUBSTRING_INDEX(address, ':', -1) AS UNSIGNED) BETWEEN 1 AND 65535",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE servers_info SET address = CONCAT(address, ':', port) WHERE port <> 25565",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ServersInfo::Table)
+                    .drop_column(Port::Port)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Port {
+    Port,
+}
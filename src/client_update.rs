@@ -1,17 +1,19 @@
 use crate::entities;
 use actix_web::http::StatusCode;
-use sea_orm::{DatabaseConnection, EntityTrait};
+use chrono::Duration;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 use serde::Serialize;
 use serde_json::json;
 use serde_json::Value;
 
-use crate::error::AppError;
+use crate::{error::AppError, player_stats};
 
 #[derive(Serialize, Debug)]
 pub enum UpdateEventType {
     PlayersGraph,
     Servers,
 
+    Shutdown,
     Error,
 }
 
@@ -64,4 +66,22 @@ macro_rules! s {
 }
 
 s!(servers, UpdateEventType::Servers);
-s!(players_graph, UpdateEventType::PlayersGraph);
+
+// Clients only draw recent samples, history comes from the players api
+const PLAYERS_GRAPH_WINDOW: Duration = Duration::hours(24);
+
+pub async fn players_graph(conn: &DatabaseConnection) -> Result<UpdateResponseBody, AppError> {
+    let since = player_stats::now() - PLAYERS_GRAPH_WINDOW;
+    let players_graph = entities::players_graph::Entity::find()
+        .filter(entities::players_graph::Column::Date.gte(since))
+        .order_by_asc(entities::players_graph::Column::Id)
+        .all(conn)
+        .await?;
+
+    Ok(UpdateResponseBody::new(
+        StatusCode::OK,
+        "Ok",
+        Some(json! {players_graph}),
+        UpdateEventType::PlayersGraph,
+    ))
+}
@@ -0,0 +1,27 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{error::AppError, permissions::Permission, supervisor::TaskRegistry, utils::AuthUser};
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/tasks",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Status of every background task", body = Vec<TaskStatus>, example = json!([{"name": "servers", "interval_secs": 300, "running": false, "run_count": 12, "error_count": 1, "restart_count": 0, "last_run": "2024-06-12T18:03:37", "last_duration_ms": 14, "last_error": "Database Error: Connection pool timed out"}])),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the view_system permission"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn list_tasks(
+    registry: web::Data<Arc<TaskRegistry>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ViewSystem)?;
+
+    Ok(HttpResponse::Ok().json(json! {registry.statuses()}))
+}
@@ -0,0 +1,39 @@
+use actix_web::web::{self, ServiceConfig};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::entities::sea_orm_active_enums::Role;
+
+pub mod list_tasks;
+pub mod sse_metrics;
+pub mod sync_versions;
+pub mod user_roles;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct RoleGrant {
+    role: Role,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserRoles {
+    user_id: i32,
+    roles: Vec<Role>,
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(web::resource("/admin/tasks").get(list_tasks::list_tasks))
+            .service(web::resource("/admin/sse").get(sse_metrics::sse_metrics))
+            .service(web::resource("/admin/versions/sync").post(sync_versions::sync_versions))
+            .service(
+                web::resource("/admin/users/{id}/roles")
+                    .get(user_roles::list_roles)
+                    .post(user_roles::grant_role),
+            )
+            .service(
+                web::resource("/admin/users/{id}/roles/{role}").delete(user_roles::revoke_role),
+            );
+    }
+}
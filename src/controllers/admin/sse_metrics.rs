@@ -0,0 +1,27 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{error::AppError, permissions::Permission, sender::Broadcaster, utils::AuthUser};
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/sse",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Server-sent events metrics of this instance", body = BroadcasterMetrics, example = json!({"connected_clients": 42, "total_connections": 1337, "rejected_connections": 3, "messages_sent": 90210, "dropped_messages": 12, "slow_disconnects": 0})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the view_system permission"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn sse_metrics(
+    broadcaster: web::Data<Arc<Broadcaster>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ViewSystem)?;
+
+    Ok(HttpResponse::Ok().json(json! {broadcaster.metrics()}))
+}
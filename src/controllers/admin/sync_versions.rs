@@ -0,0 +1,36 @@
+use actix_web::{web, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::{
+    error::AppError, events::EventBus, permissions::Permission, utils::AuthUser,
+    version_sync::VersionSync,
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/versions/sync",
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Versions imported from the configured manifest", body = SyncReport, example = json!({"created": 3, "updated": 1, "unchanged": 712, "skipped": 41})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 502, description = "Manifest or protocol source could not be fetched"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn sync_versions(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    sync: web::Data<Arc<VersionSync>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let report = sync.run(&db, &events).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
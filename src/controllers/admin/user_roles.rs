@@ -0,0 +1,155 @@
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    web, HttpResponse, Responder,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::Role, user_roles, users},
+    error::AppError,
+    permissions::{self, Permission},
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{RoleGrant, UserRoles};
+
+async fn find_user(db: &DatabaseConnection, id: i32) -> Result<users::Model, AppError> {
+    users::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(ErrorNotFound("No such user exists").into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/roles",
+    tag = "Admin",
+    params(
+        ("id" = i32, Path, description = "Id of the user"),
+    ),
+    responses(
+        (status = 200, description = "Roles of the user", body = UserRoles),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_roles permission"),
+        (status = 404, description = "User does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn list_roles(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<i32>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageRoles)?;
+
+    let target = find_user(&db, path.into_inner()).await?;
+    let roles = permissions::find_roles(db.get_ref().as_ref(), target.id).await?;
+
+    Ok(HttpResponse::Ok().json(UserRoles {
+        user_id: target.id,
+        roles,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/roles",
+    tag = "Admin",
+    params(
+        ("id" = i32, Path, description = "Id of the user"),
+    ),
+    request_body(content = RoleGrant, description = "Role to grant", content_type = "application/json", example = json!({"role": "Moderator"})),
+    responses(
+        (status = 204, description = "Role granted, it applies once the user refreshes their token"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_roles permission"),
+        (status = 404, description = "User does not exist"),
+        (status = 409, description = "User already has the role"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn grant_role(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<i32>,
+    data: ValidJson<RoleGrant>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageRoles)?;
+
+    let target = find_user(&db, path.into_inner()).await?;
+
+    // The primary key turns a second grant into a 409
+    user_roles::ActiveModel {
+        user_id: Set(target.id),
+        role: Set(data.role),
+        granted_by: Set(Some(user.id)),
+        created_at: Set(Some(Utc::now().naive_utc())),
+    }
+    .insert(db.get_ref().as_ref())
+    .await?;
+
+    log::info!(
+        "User {} granted {:?} to user {}",
+        user.id,
+        data.role,
+        target.id
+    );
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}/roles/{role}",
+    tag = "Admin",
+    params(
+        ("id" = i32, Path, description = "Id of the user"),
+        ("role" = Role, Path, description = "Role to revoke"),
+    ),
+    responses(
+        (status = 204, description = "Role revoked, it stops applying once the user refreshes their token"),
+        (status = 400, description = "Admins can not revoke their own admin role"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_roles permission"),
+        (status = 404, description = "User does not have the role"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn revoke_role(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<(i32, Role)>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageRoles)?;
+
+    let (user_id, role) = path.into_inner();
+
+    // Keeps the last admin from locking everyone out by accident
+    if user_id == user.id && role == Role::Admin {
+        return Err(ErrorBadRequest("You can not revoke your own admin role").into());
+    }
+
+    let res = user_roles::Entity::delete_by_id((user_id, role))
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    if res.rows_affected == 0 {
+        return Err(ErrorNotFound("User does not have this role").into());
+    }
+
+    log::info!("User {} revoked {role:?} from user {user_id}", user.id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
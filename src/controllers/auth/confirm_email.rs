@@ -0,0 +1,62 @@
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::Purpose, users},
+    error::AppError,
+    utils::consume_user_token,
+    validation::ValidJson,
+    Config,
+};
+
+use super::VerifyEmailRequest;
+
+#[utoipa::path(
+    post,
+    path = "/auth/confirm-email",
+    tag = "Auth",
+    request_body(content = VerifyEmailRequest, description = "Token from the mail sent to the new email", content_type = "application/json"),
+    responses(
+        (status = 204, description = "New email confirmed, it replaces the previous one"),
+        (status = 400, description = "Invalid, expired or already used token"),
+        (status = 409, description = "Email was registered by another account in the meantime"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn confirm_email(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    data: ValidJson<VerifyEmailRequest>,
+) -> Result<impl Responder, AppError> {
+    let txn = db.begin().await?;
+
+    let user_id = consume_user_token(
+        &txn,
+        &data.token,
+        Purpose::ChangeEmail,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    let user = users::Entity::find_by_id(user_id).one(&txn).await?;
+    let Some((user, email)) = user.and_then(|v| v.pending_email.clone().map(|email| (v, email)))
+    else {
+        return Err(ErrorBadRequest("Invalid or expired token").into());
+    };
+
+    let mut model = user.into_active_model();
+    model.email = Set(email);
+    model.pending_email = Set(None);
+    model.email_verified_at = Set(Some(Utc::now().naive_utc()));
+    // The unique key answers 409 when the address was taken since the change was asked for
+    model.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
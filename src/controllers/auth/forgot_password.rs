@@ -0,0 +1,55 @@
+use actix_web::{web, HttpResponse, Responder};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+use crate::{entities::users, error::AppError, mailer::Mailer, validation::ValidJson, Config};
+
+use super::{send_password_reset_mail, ForgotPasswordRequest};
+
+#[utoipa::path(
+    post,
+    path = "/auth/forgot-password",
+    tag = "Auth",
+    request_body(content = ForgotPasswordRequest, description = "Email of the account", content_type = "application/json"),
+    responses(
+        (status = 202, description = "A reset mail is sent if the account exists"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+    )
+)]
+pub async fn forgot_password(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    mailer: web::Data<Arc<dyn Mailer>>,
+    data: ValidJson<ForgotPasswordRequest>,
+) -> Result<impl Responder, AppError> {
+    let db = Arc::clone(db.get_ref());
+    let config = config.into_inner();
+    let mailer = Arc::clone(mailer.get_ref());
+    let email = data.0.email;
+
+    // Answered before the lookup, so the response time does not tell which emails are registered
+    actix_web::rt::spawn(async move {
+        if let Err(e) = reset_if_registered(&db, &config, mailer, &email).await {
+            log::error!("Failed to start a password reset: {e}");
+        }
+    });
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+async fn reset_if_registered(
+    db: &DatabaseConnection,
+    config: &Config,
+    mailer: Arc<dyn Mailer>,
+    email: &str,
+) -> Result<(), AppError> {
+    let user = users::Entity::find()
+        .filter(users::Column::Email.eq(email))
+        .one(db)
+        .await?;
+
+    match user {
+        Some(user) => send_password_reset_mail(db, config, mailer, &user).await,
+        None => Ok(()),
+    }
+}
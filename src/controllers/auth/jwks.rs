@@ -0,0 +1,19 @@
+use actix_web::{http::header, web, HttpResponse, Responder};
+use std::sync::Arc;
+
+use crate::jwt::JwtKeys;
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Public keys access tokens are verified with, identified by kid", body = Object),
+    )
+)]
+pub async fn jwks(keys: web::Data<Arc<JwtKeys>>) -> impl Responder {
+    // Short enough for verifiers to pick up a new key soon after a rotation
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(keys.jwks())
+}
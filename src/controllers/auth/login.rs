@@ -1,18 +1,35 @@
-use actix_web::{
-    cookie::Cookie, error::ErrorUnauthorized, web, HttpRequest, HttpResponse, Responder,
+use actix_web::{error::ErrorUnauthorized, web, HttpRequest, Responder};
+use argon2::{
+    self,
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHasher,
 };
-use argon2::{self, Argon2, PasswordHash, PasswordVerifier};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use crate::{
-    entities::users,
+    entities::{sea_orm_active_enums::LoginOutcome, users},
     error::AppError,
-    utils::{create_access_token, create_refresh_token, validate_refresh_token},
+    jwt::JwtKeys,
+    login_throttle, totp,
+    utils::client_ip,
+    validation::ValidJson,
     Config,
 };
 
-use super::{LoginRequest, LoginResponse};
+use super::{mfa_challenge, start_session, too_many_attempts, verify_password, LoginRequest};
+
+// Checked against for unknown emails, so they take as long as a wrong password
+fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(b"dummy password", &salt)
+            .map(|v| v.to_string())
+            .unwrap_or_default()
+    })
+}
 
 #[utoipa::path(
     post,
@@ -20,7 +37,10 @@ use super::{LoginRequest, LoginResponse};
     tag = "Auth",
     request_body(content = LoginRequest, description = "Credentials data", content_type = "application/json"),
     responses(
-        (status = 200, description = "Successfully logged in", body = LoginResponse),
+        (status = 200, description = "Successfully logged in, or a LoginChallenge when the account has two-factor authentication", body = LoginResponse),
+        (status = 401, description = "Invalid credentials, the same for unknown emails"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 429, description = "Too many failed attempts, Retry-After tells when to try again"),
         (status = 500, description = "Database error"),
     ),
     security(
@@ -30,51 +50,48 @@ use super::{LoginRequest, LoginResponse};
 pub async fn login(
     db: web::Data<Arc<DatabaseConnection>>,
     config: web::Data<Config>,
-    data: web::Json<LoginRequest>,
+    keys: web::Data<Arc<JwtKeys>>,
+    data: ValidJson<LoginRequest>,
     req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
+    let email = login_throttle::normalize_email(&data.email);
+    let ip = client_ip(&req, config.trust_forwarded_for);
+
+    let attempt = login_throttle::start(&db, &config.login, &email, ip).await?;
+    if let Some(retry) = attempt.retry_after() {
+        attempt.finish(&db, None, LoginOutcome::Throttled).await?;
+
+        return Ok(too_many_attempts(retry));
+    }
+
     let user = users::Entity::find()
-        .filter(users::Column::Email.eq(data.email.clone()))
+        .filter(users::Column::Email.eq(email.clone()))
         .one(db.get_ref().as_ref())
         .await?;
 
-    if let Some(user) = user {
-        let argon2 = Argon2::default();
-        if argon2
-            .verify_password(
-                data.password.as_bytes(),
-                &PasswordHash::new(&user.password).unwrap(),
-            )
-            .is_ok()
-        {
-            let cookie = req.cookie("refresh_token").map(|v| v.value().to_owned());
-            let token = create_access_token(user.id, config.json_token.as_bytes());
-            let refresh_token = if cookie.is_some()
-                && validate_refresh_token(
-                    &db,
-                    cookie.clone().unwrap().as_str(),
-                    config.json_token.as_bytes(),
-                )
-                .await
-                .is_ok()
-            {
-                cookie.unwrap()
-            } else {
-                create_refresh_token(db.get_ref(), user.id, config.json_token.as_bytes()).await
-            };
-
-            let cookie = Cookie::build("refresh_token", refresh_token)
-                // TODO: Add valid domain url to unwrap
-                .domain(req.uri().host().unwrap_or(""))
-                .path("/")
-                .http_only(true)
-                .finish();
-
-            return Ok(HttpResponse::Ok()
-                .cookie(cookie)
-                .json(LoginResponse { jwt_token: token }));
+    let outcome = match &user {
+        Some(user) if verify_password(&data.password, &user.password) => {
+            match totp::find_enabled(db.get_ref().as_ref(), user.id).await? {
+                Some(_) => LoginOutcome::PasswordAccepted,
+                None => LoginOutcome::Success,
+            }
         }
-    }
+        Some(_) => LoginOutcome::BadPassword,
+        None => {
+            verify_password(&data.password, dummy_hash());
+            LoginOutcome::UnknownEmail
+        }
+    };
+    attempt
+        .finish(&db, user.as_ref().map(|v| v.id), outcome)
+        .await?;
+
+    let user = match (user, outcome) {
+        (Some(user), LoginOutcome::Success) => user,
+        // Tokens are only issued once the code is checked as well
+        (Some(user), LoginOutcome::PasswordAccepted) => return mfa_challenge(&config, &user),
+        _ => return Err(ErrorUnauthorized("Invalid Credentials").into()),
+    };
 
-    Err(ErrorUnauthorized("Invalid Credentials").into())
+    start_session(&db, &keys, &req, &user).await
 }
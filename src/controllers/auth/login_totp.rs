@@ -0,0 +1,73 @@
+use actix_web::{error::ErrorUnauthorized, web, HttpRequest, Responder};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::LoginOutcome, users},
+    error::AppError,
+    jwt::JwtKeys,
+    login_throttle, totp,
+    utils::{client_ip, validate_mfa_token},
+    validation::ValidJson,
+    Config,
+};
+
+use super::{start_session, too_many_attempts, TotpLoginRequest};
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/totp",
+    tag = "Auth",
+    request_body(content = TotpLoginRequest, description = "Token from the first login step and a TOTP or recovery code", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully logged in", body = LoginResponse),
+        (status = 401, description = "Invalid code or expired login"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 429, description = "Too many failed attempts, Retry-After tells when to try again"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn login_totp(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    keys: web::Data<Arc<JwtKeys>>,
+    data: ValidJson<TotpLoginRequest>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let user_id = validate_mfa_token(&data.mfa_token, config.json_token.as_bytes())?;
+    let user = users::Entity::find_by_id(user_id)
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or(ErrorUnauthorized("Login expired, start again"))?;
+
+    // Codes count towards the same limits as passwords
+    let email = login_throttle::normalize_email(&user.email);
+    let ip = client_ip(&req, config.trust_forwarded_for);
+
+    let attempt = login_throttle::start(&db, &config.login, &email, ip).await?;
+    if let Some(retry) = attempt.retry_after() {
+        attempt
+            .finish(&db, Some(user.id), LoginOutcome::Throttled)
+            .await?;
+        return Ok(too_many_attempts(retry));
+    }
+
+    let valid = match totp::find_enabled(db.get_ref().as_ref(), user.id).await? {
+        Some(model) => {
+            totp::verify_second_factor(db.get_ref().as_ref(), &model, &data.code).await?
+        }
+        None => false,
+    };
+
+    let outcome = match valid {
+        true => LoginOutcome::Success,
+        false => LoginOutcome::BadCode,
+    };
+    attempt.finish(&db, Some(user.id), outcome).await?;
+
+    if !valid {
+        return Err(ErrorUnauthorized("Invalid code").into());
+    }
+
+    start_session(&db, &keys, &req, &user).await
+}
@@ -0,0 +1,69 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    jwt::JwtKeys,
+    utils::{find_refresh_token, revoke_refresh_family, revoke_user_refresh_tokens},
+};
+
+use super::{removal_cookie, REFRESH_COOKIE};
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "Auth",
+    responses(
+        (status = 204, description = "Session ended, the refresh_token cookie is removed"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn logout(
+    db: web::Data<Arc<DatabaseConnection>>,
+    keys: web::Data<Arc<JwtKeys>>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    // Logging out without a valid session is not an error, there is nothing left to end
+    if let Some(cookie) = req.cookie(REFRESH_COOKIE) {
+        if let Ok(stored) = find_refresh_token(&db, cookie.value(), &keys).await {
+            revoke_refresh_family(&db, &stored.family).await?;
+        }
+    }
+
+    Ok(HttpResponse::NoContent()
+        .cookie(removal_cookie(&req))
+        .finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    tag = "Auth",
+    responses(
+        (status = 204, description = "Every session of the user ended"),
+        (status = 401, description = "Invalid refresh token provided"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn logout_all(
+    db: web::Data<Arc<DatabaseConnection>>,
+    keys: web::Data<Arc<JwtKeys>>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let token = req
+        .cookie(REFRESH_COOKIE)
+        .map(|v| v.value().to_owned())
+        .unwrap_or_default();
+    let stored = find_refresh_token(&db, &token, &keys).await?;
+
+    if stored.revoked_at.is_some() {
+        return Err(actix_web::error::ErrorUnauthorized("Invalid token").into());
+    }
+
+    revoke_user_refresh_tokens(&db, stored.user_id).await?;
+
+    Ok(HttpResponse::NoContent()
+        .cookie(removal_cookie(&req))
+        .finish())
+}
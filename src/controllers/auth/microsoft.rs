@@ -0,0 +1,225 @@
+use actix_web::{
+    error::{ErrorConflict, ErrorNotFound, ErrorServiceUnavailable, ErrorUnauthorized},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    entities::users,
+    error::AppError,
+    jwt::JwtKeys,
+    microsoft::MicrosoftClient,
+    totp,
+    utils::{consume_oauth_state, create_oauth_state, AuthUser},
+    Config,
+};
+
+use super::{mfa_challenge, oauth_state_cookie, start_session, OAUTH_STATE_COOKIE};
+
+#[derive(Serialize, ToSchema)]
+pub struct MicrosoftAuthorization {
+    /// Microsoft sign in page the user has to be sent to
+    authorize_url: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct MicrosoftCallback {
+    /// Authorization code from Microsoft
+    code: String,
+    /// State returned unchanged by Microsoft
+    state: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MinecraftAccount {
+    minecraft_uuid: String,
+    minecraft_name: String,
+}
+
+fn authorize(
+    client: &MicrosoftClient,
+    config: &Config,
+    req: &HttpRequest,
+    link_user: Option<i32>,
+) -> Result<HttpResponse, AppError> {
+    if !client.enabled() {
+        return Err(ErrorServiceUnavailable("Sign in with Microsoft is not configured").into());
+    }
+
+    let (state, nonce) = create_oauth_state(link_user, config.json_token.as_bytes())?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(oauth_state_cookie(req, nonce))
+        .json(MicrosoftAuthorization {
+            authorize_url: client.authorize_url(&state)?,
+        }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/microsoft",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Where to send the user to log in with the linked Minecraft account", body = MicrosoftAuthorization),
+        (status = 503, description = "Sign in with Microsoft is not configured"),
+    )
+)]
+pub async fn microsoft_login(
+    client: web::Data<Arc<MicrosoftClient>>,
+    config: web::Data<Config>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    authorize(&client, &config, &req, None)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/microsoft/link",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Where to send the user to link their Minecraft account", body = MicrosoftAuthorization),
+        (status = 401, description = "Invalid token"),
+        (status = 503, description = "Sign in with Microsoft is not configured"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn microsoft_link(
+    client: web::Data<Arc<MicrosoftClient>>,
+    config: web::Data<Config>,
+    user: AuthUser,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    authorize(&client, &config, &req, Some(user.id))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/microsoft/link",
+    tag = "Auth",
+    responses(
+        (status = 204, description = "Minecraft account unlinked"),
+        (status = 401, description = "Invalid token"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn microsoft_unlink(
+    db: web::Data<Arc<DatabaseConnection>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    users::Entity::update_many()
+        .col_expr(
+            users::Column::MinecraftUuid,
+            sea_orm::sea_query::Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            users::Column::MinecraftName,
+            sea_orm::sea_query::Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            users::Column::MinecraftLinkedAt,
+            sea_orm::sea_query::Expr::value(Option::<chrono::NaiveDateTime>::None),
+        )
+        .filter(users::Column::Id.eq(user.id))
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/microsoft/callback",
+    tag = "Auth",
+    params(MicrosoftCallback),
+    responses(
+        (status = 200, description = "Linked account when linking, otherwise logged in like `/auth/login`", body = LoginResponse),
+        (status = 400, description = "Expired or already used state, or the Microsoft account does not own Minecraft"),
+        (status = 404, description = "No user linked this Minecraft account"),
+        (status = 409, description = "Minecraft account already linked to another user"),
+        (status = 502, description = "Microsoft or Minecraft services failed"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn microsoft_callback(
+    db: web::Data<Arc<DatabaseConnection>>,
+    client: web::Data<Arc<MicrosoftClient>>,
+    config: web::Data<Config>,
+    keys: web::Data<Arc<JwtKeys>>,
+    query: web::Query<MicrosoftCallback>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let nonce = req.cookie(OAUTH_STATE_COOKIE);
+    let link_user = consume_oauth_state(
+        &db,
+        &query.state,
+        nonce.as_ref().map(|v| v.value()),
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    let profile = client.minecraft_profile(&query.code).await?;
+
+    let owner = users::Entity::find()
+        .filter(users::Column::MinecraftUuid.eq(profile.uuid.clone()))
+        .one(db.get_ref().as_ref())
+        .await?;
+
+    // The state is single use, the browser drops the nonce
+    let mut used = oauth_state_cookie(&req, String::new());
+    used.make_removal();
+
+    if let Some(user_id) = link_user {
+        if owner.as_ref().is_some_and(|v| v.id != user_id) {
+            return Err(ErrorConflict("This Minecraft account is linked to another user").into());
+        }
+
+        let user = users::Entity::find_by_id(user_id)
+            .one(db.get_ref().as_ref())
+            .await?
+            .ok_or(ErrorUnauthorized("Invalid token"))?;
+
+        let mut user = user.into_active_model();
+        user.minecraft_uuid = Set(Some(profile.uuid.clone()));
+        user.minecraft_name = Set(Some(profile.name.clone()));
+        user.minecraft_linked_at = Set(Some(Utc::now().naive_utc()));
+        user.update(db.get_ref().as_ref()).await?;
+
+        return Ok(HttpResponse::Ok().cookie(used).json(MinecraftAccount {
+            minecraft_uuid: profile.uuid,
+            minecraft_name: profile.name,
+        }));
+    }
+
+    let Some(user) = owner else {
+        return Err(ErrorNotFound("No user linked this Minecraft account").into());
+    };
+
+    // Names can change, the uuid can not
+    let user = if user.minecraft_name.as_deref() != Some(profile.name.as_str()) {
+        let mut user = user.into_active_model();
+        user.minecraft_name = Set(Some(profile.name));
+        user.update(db.get_ref().as_ref()).await?
+    } else {
+        user
+    };
+
+    let mut res = match totp::find_enabled(db.get_ref().as_ref(), user.id).await? {
+        Some(_) => mfa_challenge(&config, &user)?,
+        None => start_session(&db, &keys, &req, &user).await?,
+    };
+    res.add_cookie(&used).map_err(actix_web::Error::from)?;
+
+    Ok(res)
+}
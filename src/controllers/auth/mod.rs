@@ -1,27 +1,299 @@
-use actix_web::web::{self, ServiceConfig};
+use actix_web::{
+    cookie::Cookie,
+    http::header,
+    web::{self, ServiceConfig},
+    HttpRequest, HttpResponse,
+};
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::Duration;
+use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
 use utoipa::ToSchema;
+use validator::Validate;
 
+use crate::{
+    entities::{sea_orm_active_enums::Purpose, users},
+    error::AppError,
+    jwt::JwtKeys,
+    mailer::{dispatch, Mail, Mailer},
+    permissions,
+    utils::{
+        create_access_token, create_mfa_token, create_refresh_token, create_user_token,
+        find_refresh_token, revoke_refresh_family, revoke_user_tokens,
+    },
+    Config,
+};
+
+pub mod confirm_email;
+pub mod forgot_password;
+pub mod jwks;
 pub mod login;
+pub mod login_totp;
+pub mod logout;
+pub mod microsoft;
 pub mod refresh;
 pub mod register;
+pub mod reset_password;
+pub mod verify_email;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct RegisterRequest {
+    #[validate(email(message = "Must be a valid email address"), length(max = 255))]
     email: String,
+    #[validate(custom(function = "crate::validation::username"))]
     username: String,
+    #[validate(custom(function = "crate::validation::password"))]
     password: String,
 }
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct LoginRequest {
+    #[validate(length(min = 1, max = 255))]
     email: String,
+    #[validate(length(min = 1, max = 128))]
     password: String,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     jwt_token: String,
+    /// Staff without two-factor authentication, their permissions are withheld until it is enabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    totp_enrollment_required: bool,
+}
+
+/// Answer to a correct password when the account has two-factor authentication.
+#[derive(Serialize, ToSchema)]
+pub struct LoginChallenge {
+    mfa_required: bool,
+    /// Sent to `/auth/login/totp` together with the code
+    mfa_token: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TotpLoginRequest {
+    #[validate(length(min = 1))]
+    mfa_token: String,
+    /// Code from the authenticator app or a recovery code
+    #[validate(length(min = 6, max = 32))]
+    code: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1))]
+    token: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Must be a valid email address"))]
+    email: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1))]
+    token: String,
+    #[validate(custom(function = "crate::validation::password"))]
+    password: String,
+}
+
+pub(crate) fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn too_many_attempts(retry: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry.to_string()))
+        .json(json!({
+            "code": 429,
+            "message": "Too many failed attempts, try again later"
+        }))
+}
+
+/// Second step of a login, the access token waits for the TOTP or recovery code.
+fn mfa_challenge(config: &Config, user: &users::Model) -> Result<HttpResponse, AppError> {
+    let mfa_token = create_mfa_token(user.id, config.json_token.as_bytes())?;
+
+    Ok(HttpResponse::Ok().json(LoginChallenge {
+        mfa_required: true,
+        mfa_token,
+    }))
+}
+
+/// Issues the access token and a new refresh token session once every login step passed.
+async fn start_session(
+    db: &DatabaseConnection,
+    keys: &JwtKeys,
+    req: &HttpRequest,
+    user: &users::Model,
+) -> Result<HttpResponse, AppError> {
+    // Logging in again replaces the session the client had
+    if let Some(cookie) = req.cookie(REFRESH_COOKIE) {
+        if let Ok(stored) = find_refresh_token(db, cookie.value(), keys).await {
+            revoke_refresh_family(db, &stored.family).await?;
+        }
+    }
+
+    let grants = permissions::grants(db, user.id).await?;
+    let token = create_access_token(user.id, &grants, keys);
+    let refresh_token = create_refresh_token(db, user.id, keys).await?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(refresh_cookie(req, refresh_token))
+        .json(LoginResponse {
+            jwt_token: token,
+            totp_enrollment_required: grants.totp_enrollment_required,
+        }))
+}
+
+const VERIFY_EMAIL_LIFETIME: Duration = Duration::days(1);
+const RESET_PASSWORD_LIFETIME: Duration = Duration::hours(1);
+
+pub(crate) async fn send_verification_mail(
+    db: &DatabaseConnection,
+    config: &Config,
+    mailer: Arc<dyn Mailer>,
+    user: &users::Model,
+) -> Result<(), AppError> {
+    let token = create_user_token(
+        db,
+        user.id,
+        Purpose::VerifyEmail,
+        VERIFY_EMAIL_LIFETIME,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    let link = format!("{}/verify-email?token={token}", config.mail.app_url);
+    dispatch(
+        mailer,
+        Mail {
+            to: user.email.clone(),
+            subject: "Verify your email".to_owned(),
+            body: format!(
+                "Hi {},\n\nConfirm your email address by opening the link below:\n{link}\n\nThe link is valid for 24 hours.",
+                user.username
+            ),
+        },
+    );
+
+    Ok(())
+}
+
+/// Mails a confirmation link to the new address and tells the current one about the change.
+/// Links sent for an earlier change stop working.
+pub(crate) async fn send_email_change_mails(
+    db: &DatabaseConnection,
+    config: &Config,
+    mailer: Arc<dyn Mailer>,
+    user: &users::Model,
+    new_email: &str,
+) -> Result<(), AppError> {
+    revoke_user_tokens(db, user.id, Purpose::ChangeEmail).await?;
+
+    let token = create_user_token(
+        db,
+        user.id,
+        Purpose::ChangeEmail,
+        VERIFY_EMAIL_LIFETIME,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    let link = format!("{}/confirm-email?token={token}", config.mail.app_url);
+    dispatch(
+        Arc::clone(&mailer),
+        Mail {
+            to: new_email.to_owned(),
+            subject: "Confirm your new email".to_owned(),
+            body: format!(
+                "Hi {},\n\nConfirm that this is the new email of your account by opening the link below:\n{link}\n\nThe link is valid for 24 hours, until then your current email stays in use.",
+                user.username
+            ),
+        },
+    );
+    dispatch(
+        mailer,
+        Mail {
+            to: user.email.clone(),
+            subject: "Your email is being changed".to_owned(),
+            body: format!(
+                "Hi {},\n\nSomeone asked to change the email of your account to {new_email}. It only changes once the link sent there is opened.\n\nIf it was not you, change your password right away.",
+                user.username
+            ),
+        },
+    );
+
+    Ok(())
+}
+
+async fn send_password_reset_mail(
+    db: &DatabaseConnection,
+    config: &Config,
+    mailer: Arc<dyn Mailer>,
+    user: &users::Model,
+) -> Result<(), AppError> {
+    let token = create_user_token(
+        db,
+        user.id,
+        Purpose::ResetPassword,
+        RESET_PASSWORD_LIFETIME,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    let link = format!("{}/reset-password?token={token}", config.mail.app_url);
+    dispatch(
+        mailer,
+        Mail {
+            to: user.email.clone(),
+            subject: "Reset your password".to_owned(),
+            body: format!(
+                "Hi {},\n\nSomeone asked to reset the password of your account. If it was you, open the link below:\n{link}\n\nThe link is valid for one hour, ignore this mail if you did not ask for it.",
+                user.username
+            ),
+        },
+    );
+
+    Ok(())
+}
+
+pub(crate) const REFRESH_COOKIE: &str = "refresh_token";
+
+fn refresh_cookie(req: &HttpRequest, token: String) -> Cookie<'static> {
+    Cookie::build(REFRESH_COOKIE, token)
+        // TODO: Add valid domain url to unwrap
+        .domain(req.uri().host().unwrap_or("").to_owned())
+        .path("/")
+        .http_only(true)
+        .finish()
+}
+
+pub(crate) fn removal_cookie(req: &HttpRequest) -> Cookie<'static> {
+    let mut cookie = refresh_cookie(req, String::new());
+    cookie.make_removal();
+    cookie
+}
+
+const OAUTH_STATE_COOKIE: &str = "oauth_state";
+
+fn oauth_state_cookie(req: &HttpRequest, nonce: String) -> Cookie<'static> {
+    Cookie::build(OAUTH_STATE_COOKIE, nonce)
+        .domain(req.uri().host().unwrap_or("").to_owned())
+        .path("/")
+        .http_only(true)
+        .max_age(actix_web::cookie::time::Duration::minutes(10))
+        .finish()
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
@@ -29,6 +301,20 @@ pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
         config
             .service(web::resource("/register").post(register::register))
             .service(web::resource("/login").post(login::login))
-            .service(web::resource("/refresh").get(refresh::refresh));
+            .service(web::resource("/login/totp").post(login_totp::login_totp))
+            .service(web::resource("/refresh").get(refresh::refresh))
+            .service(web::resource("/logout").post(logout::logout))
+            .service(web::resource("/logout-all").post(logout::logout_all))
+            .service(web::resource("/verify-email").post(verify_email::verify_email))
+            .service(web::resource("/confirm-email").post(confirm_email::confirm_email))
+            .service(web::resource("/forgot-password").post(forgot_password::forgot_password))
+            .service(web::resource("/reset-password").post(reset_password::reset_password))
+            .service(web::resource("/microsoft").get(microsoft::microsoft_login))
+            .service(web::resource("/microsoft/callback").get(microsoft::microsoft_callback))
+            .service(
+                web::resource("/microsoft/link")
+                    .post(microsoft::microsoft_link)
+                    .delete(microsoft::microsoft_unlink),
+            );
     }
 }
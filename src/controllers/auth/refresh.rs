@@ -1,41 +1,46 @@
 use actix_web::{error::ErrorUnauthorized, web, HttpRequest, HttpResponse, Responder};
 use sea_orm::DatabaseConnection;
-use serde_json::json;
 use std::sync::Arc;
 
 use crate::{
     error::AppError,
-    utils::{create_access_token, validate_refresh_token},
-    Config,
+    jwt::JwtKeys,
+    permissions,
+    utils::{create_access_token, rotate_refresh_token},
 };
 
+use super::{refresh_cookie, LoginResponse, REFRESH_COOKIE};
+
 #[utoipa::path(
     get,
     path = "/auth/refresh",
     tag = "Auth",
     responses(
-        (status = 200, description = "JwtToken", body = LoginResponse),
-        (status = 401, description = "Invalid refresh token provided"),
+        (status = 200, description = "JwtToken, the refresh_token cookie is replaced", body = LoginResponse),
+        (status = 401, description = "Invalid or already used refresh token provided"),
         (status = 500, description = "Database error"),
     )
 )]
 pub async fn refresh(
     db: web::Data<Arc<DatabaseConnection>>,
-    config: web::Data<Config>,
+    keys: web::Data<Arc<JwtKeys>>,
     req: HttpRequest,
 ) -> Result<impl Responder, AppError> {
-    let cookie = match req.cookie("refresh_token") {
+    let cookie = match req.cookie(REFRESH_COOKIE) {
         Some(v) => v,
         None => return Err(ErrorUnauthorized("Missing refresh token").into()),
     };
 
-    let claims =
-        match validate_refresh_token(&db, cookie.value(), config.json_token.as_bytes()).await {
-            Ok(v) => v,
-            Err(_) => return Err(ErrorUnauthorized("Invalid token").into()),
-        };
+    let (user_id, refresh_token) = rotate_refresh_token(&db, cookie.value(), &keys).await?;
 
-    let token = create_access_token(claims.sub, config.json_token.as_bytes());
+    // Roles granted or revoked since the last token apply from here on
+    let grants = permissions::grants(db.get_ref().as_ref(), user_id).await?;
+    let token = create_access_token(user_id, &grants, &keys);
 
-    Ok(HttpResponse::Ok().json(json!({"jwt_token": token})))
+    Ok(HttpResponse::Ok()
+        .cookie(refresh_cookie(&req, refresh_token))
+        .json(LoginResponse {
+            jwt_token: token,
+            totp_enrollment_required: grants.totp_enrollment_required,
+        }))
 }
@@ -1,45 +1,64 @@
-use actix_web::{web, HttpResponse, Responder};
-use argon2::{
-    password_hash::{rand_core::OsRng, SaltString},
-    Argon2, PasswordHasher,
+use actix_web::{error::ErrorConflict, web, HttpResponse, Responder};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set,
 };
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
 use std::{ops::Deref, sync::Arc};
 
-use crate::{entities::users, error::AppError};
+use crate::{
+    entities::users, error::AppError, mailer::Mailer, utils::hash_password, validation::ValidJson,
+    Config,
+};
 
-use super::RegisterRequest;
+use super::{send_verification_mail, RegisterRequest};
 
-// TODO: Validate if user already exists
 #[utoipa::path(
     post,
     path = "/auth/register",
     tag = "Auth",
     request_body(content = RegisterRequest, description = "Credentials data", content_type = "application/json"),
     responses(
-        (status = 200, description = "Successfully registered"),
-        (status = 401, description = "Invalid refresh_token provided"),
+        (status = 201, description = "Successfully registered, a verification mail is sent"),
+        (status = 409, description = "Email or username already taken"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Database error"),
     )
 )]
 pub async fn register(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<RegisterRequest>,
+    config: web::Data<Config>,
+    mailer: web::Data<Arc<dyn Mailer>>,
+    data: ValidJson<RegisterRequest>,
 ) -> Result<impl Responder, AppError> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(data.password.as_bytes(), &salt)
-        .unwrap();
+    let existing = users::Entity::find()
+        .filter(
+            Condition::any()
+                .add(users::Column::Email.eq(data.email.clone()))
+                .add(users::Column::Username.eq(data.username.clone())),
+        )
+        .one(db.get_ref().deref())
+        .await?;
+
+    // A concurrent registration still ends as 409 through the unique keys
+    match existing {
+        Some(v) if v.email.eq_ignore_ascii_case(&data.email) => {
+            return Err(ErrorConflict("Email is already registered").into())
+        }
+        Some(_) => return Err(ErrorConflict("Username is already taken").into()),
+        None => {}
+    }
+
+    let password_hash = hash_password(&data.password)?;
 
     let new_user = users::ActiveModel {
         email: Set(data.email.clone()),
         username: Set(data.username.clone()),
-        password: Set(password_hash.to_string()),
+        password: Set(password_hash),
         ..Default::default()
     };
 
-    new_user.insert(db.get_ref().deref()).await?;
+    let user = new_user.insert(db.get_ref().deref()).await?;
+
+    send_verification_mail(&db, &config, mailer.get_ref().clone(), &user).await?;
 
     Ok(HttpResponse::Created().finish())
 }
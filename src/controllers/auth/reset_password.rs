@@ -0,0 +1,197 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::Purpose, users},
+    error::AppError,
+    utils::{consume_user_token, hash_password, revoke_user_refresh_tokens},
+    validation::ValidJson,
+    Config,
+};
+
+use super::ResetPasswordRequest;
+
+#[utoipa::path(
+    post,
+    path = "/auth/reset-password",
+    tag = "Auth",
+    request_body(content = ResetPasswordRequest, description = "Token from the reset mail and the new password", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Password changed, every session is logged out"),
+        (status = 400, description = "Invalid, expired or already used token"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn reset_password(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    data: ValidJson<ResetPasswordRequest>,
+) -> Result<impl Responder, AppError> {
+    let password_hash = hash_password(&data.password)?;
+
+    let txn = db.begin().await?;
+
+    let user_id = consume_user_token(
+        &txn,
+        &data.token,
+        Purpose::ResetPassword,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    users::Entity::update_many()
+        .col_expr(users::Column::Password, Expr::value(password_hash))
+        .filter(users::Column::Id.eq(user_id))
+        .exec(&txn)
+        .await?;
+
+    // The reset link reached the inbox, so the address is proven as well
+    users::Entity::update_many()
+        .col_expr(
+            users::Column::EmailVerifiedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::EmailVerifiedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    revoke_user_refresh_tokens(&db, user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, web::Data, App};
+    use sea_orm::{
+        ActiveModelTrait, ConnectOptions, ConnectionTrait, Database, DbBackend, Schema, Set,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        controllers::auth::{forgot_password::forgot_password, verify_password},
+        entities::{auth, user_tokens},
+        mailer::{memory::MemoryMailer, Mailer},
+    };
+
+    // An in-memory SQLite database stands in for MySQL
+    async fn stand_in() -> Arc<DatabaseConnection> {
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1);
+        let conn = Database::connect(options).await.unwrap();
+
+        let schema = Schema::new(DbBackend::Sqlite);
+        for table in [
+            schema.create_table_from_entity(users::Entity),
+            schema.create_table_from_entity(user_tokens::Entity),
+            schema.create_table_from_entity(auth::Entity),
+        ] {
+            conn.execute(conn.get_database_backend().build(&table))
+                .await
+                .unwrap();
+        }
+        Arc::new(conn)
+    }
+
+    fn config() -> Config {
+        serde_json::from_value(json!({
+            "addr": "127.0.0.1",
+            "port": 8080,
+            "threads": 1,
+            "database_table": "craftlist",
+            "database_url": "sqlite::memory:",
+            "log": 0,
+            "json_token": "test secret",
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn forgot_then_reset_changes_the_password_once() {
+        let db = stand_in().await;
+        let user = users::ActiveModel {
+            email: Set("steve@example.com".to_owned()),
+            username: Set("steve".to_owned()),
+            password: Set(hash_password("old password 1").unwrap()),
+            ..Default::default()
+        }
+        .insert(db.as_ref())
+        .await
+        .unwrap();
+
+        let mailer = MemoryMailer::create();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Arc::clone(&db)))
+                .app_data(Data::new(config()))
+                .app_data(Data::new(Arc::clone(&mailer) as Arc<dyn Mailer>))
+                .route("/forgot-password", web::post().to(forgot_password))
+                .route("/reset-password", web::post().to(reset_password)),
+        )
+        .await;
+
+        for email in ["nobody@example.com", "steve@example.com"] {
+            let req = test::TestRequest::post()
+                .uri("/forgot-password")
+                .set_json(json!({ "email": email }))
+                .to_request();
+            assert_eq!(
+                test::call_service(&app, req).await.status(),
+                StatusCode::ACCEPTED
+            );
+        }
+
+        // The mail goes out after the response
+        let mut sent = mailer.sent();
+        for _ in 0..100 {
+            if !sent.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            sent = mailer.sent();
+        }
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "steve@example.com");
+
+        let token = sent[0]
+            .body
+            .split("token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .unwrap()
+            .to_owned();
+
+        let reset = || {
+            test::TestRequest::post()
+                .uri("/reset-password")
+                .set_json(json!({ "token": token, "password": "new password 2" }))
+                .to_request()
+        };
+        assert_eq!(
+            test::call_service(&app, reset()).await.status(),
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            test::call_service(&app, reset()).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let user = users::Entity::find_by_id(user.id)
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(verify_password("new password 2", &user.password));
+        assert!(user.email_verified_at.is_some());
+    }
+}
@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::Purpose, users},
+    error::AppError,
+    utils::consume_user_token,
+    validation::ValidJson,
+    Config,
+};
+
+use super::VerifyEmailRequest;
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    tag = "Auth",
+    request_body(content = VerifyEmailRequest, description = "Token from the verification mail", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Email verified"),
+        (status = 400, description = "Invalid, expired or already used token"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    )
+)]
+pub async fn verify_email(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    data: ValidJson<VerifyEmailRequest>,
+) -> Result<impl Responder, AppError> {
+    let user_id = consume_user_token(
+        db.get_ref().as_ref(),
+        &data.token,
+        Purpose::VerifyEmail,
+        config.json_token.as_bytes(),
+    )
+    .await?;
+
+    users::Entity::update_many()
+        .col_expr(
+            users::Column::EmailVerifiedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::EmailVerifiedAt.is_null())
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,262 @@
+use actix_web::{web, Responder};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, Set,
+    TransactionTrait,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use validator::Validate;
+
+use crate::{
+    entities::categories,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+};
+
+use super::{
+    export, invalid_fields, parse, CategoryRecord, ExportQuery, ImportQuery, ImportReport,
+};
+
+fn to_record(category: &categories::Model, slugs: &HashMap<i32, String>) -> CategoryRecord {
+    CategoryRecord {
+        name: category.name.clone(),
+        slug: category.slug.clone(),
+        parent: category.parent_id.and_then(|id| slugs.get(&id).cloned()),
+        description: category.description.clone(),
+        icon: category.icon.clone(),
+        colour: category.colour.clone(),
+        sort_order: category.sort_order,
+        archived: category.archived_at.is_some(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/catalog/categories",
+    tag = "Admin",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Every category, archived ones included, as JSON or CSV", body = Vec<CategoryRecord>),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn export_categories(
+    db: web::Data<Arc<DatabaseConnection>>,
+    query: web::Query<ExportQuery>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let categories = categories::Entity::find()
+        .order_by_asc(categories::Column::Id)
+        .all(db.get_ref().as_ref())
+        .await?;
+    let slugs: HashMap<i32, String> = categories.iter().map(|v| (v.id, v.slug.clone())).collect();
+
+    // Parents come before their children, so the file can be imported again as is
+    let mut records = Vec::with_capacity(categories.len());
+    let mut written = HashSet::new();
+    while written.len() < categories.len() {
+        let before = written.len();
+        for category in &categories {
+            let ready = category.parent_id.is_none_or(|id| written.contains(&id));
+            if ready && !written.contains(&category.id) {
+                records.push(to_record(category, &slugs));
+                written.insert(category.id);
+            }
+        }
+        if written.len() == before {
+            break;
+        }
+    }
+
+    export(query.format, "categories", &records)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/catalog/categories",
+    tag = "Admin",
+    params(ImportQuery),
+    request_body(content = Vec<CategoryRecord>, description = "Categories as a JSON array or CSV with a header row", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Categories imported, or what would change for a dry run", body = ImportReport),
+        (status = 400, description = "Body can not be parsed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 409, description = "Conflicts kept anything from being imported", body = ImportReport),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn import_categories(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let records: Vec<CategoryRecord> = parse(query.format, &body)?;
+    let existing = categories::Entity::find()
+        .all(db.get_ref().as_ref())
+        .await?;
+    let slugs: HashMap<i32, String> = existing.iter().map(|v| (v.id, v.slug.clone())).collect();
+    let by_slug: HashMap<&str, &categories::Model> =
+        existing.iter().map(|v| (v.slug.as_str(), v)).collect();
+
+    let mut report = ImportReport {
+        dry_run: query.dry_run,
+        ..Default::default()
+    };
+
+    // Parent of every slug once the import is applied
+    let mut parents: HashMap<&str, Option<&str>> = existing
+        .iter()
+        .map(|v| {
+            let parent = v
+                .parent_id
+                .and_then(|id| slugs.get(&id))
+                .map(String::as_str);
+            (v.slug.as_str(), parent)
+        })
+        .collect();
+    // Name of every slug once the import is applied
+    let mut names: HashMap<String, &str> = existing
+        .iter()
+        .map(|v| (v.name.to_lowercase(), v.slug.as_str()))
+        .collect();
+    let mut seen = HashSet::new();
+
+    for record in &records {
+        if !seen.insert(record.slug.as_str()) {
+            report.conflict(&record.slug, "Slug is listed more than once");
+            continue;
+        }
+        if let Err(e) = record.validate() {
+            report.conflict(&record.slug, invalid_fields(&e));
+            continue;
+        }
+
+        parents.insert(&record.slug, record.parent.as_deref());
+        if let Some(old) = by_slug.get(record.slug.as_str()) {
+            names.remove(&old.name.to_lowercase());
+        }
+    }
+
+    for record in &records {
+        match names.get(&record.name.to_lowercase()) {
+            Some(slug) if *slug != record.slug => {
+                report.conflict(&record.slug, format!("Name is used by {slug}"))
+            }
+            _ => {
+                names.insert(record.name.to_lowercase(), &record.slug);
+            }
+        }
+
+        let Some(parent) = &record.parent else {
+            continue;
+        };
+        if !parents.contains_key(parent.as_str()) {
+            report.conflict(&record.slug, format!("Parent {parent} does not exist"));
+            continue;
+        }
+
+        let mut current = Some(parent.as_str());
+        let mut depth = 0;
+        while let Some(ancestor) = current {
+            if ancestor == record.slug || depth > parents.len() {
+                report.conflict(&record.slug, "Category would be placed under itself");
+                break;
+            }
+            current = parents.get(ancestor).copied().flatten();
+            depth += 1;
+        }
+    }
+
+    let conflicting: HashSet<String> = report.conflicts.iter().map(|v| v.key.clone()).collect();
+    for record in records.iter().filter(|v| !conflicting.contains(&v.slug)) {
+        match by_slug.get(record.slug.as_str()) {
+            Some(old) if to_record(old, &slugs) == *record => report.unchanged += 1,
+            Some(_) => report.updated.push(record.slug.clone()),
+            None => report.created.push(record.slug.clone()),
+        }
+    }
+
+    if query.dry_run || !report.conflicts.is_empty() {
+        return Ok(report.response());
+    }
+
+    let changed: HashSet<&str> = report
+        .created
+        .iter()
+        .chain(&report.updated)
+        .map(String::as_str)
+        .collect();
+    let mut ids: HashMap<String, i32> = existing.iter().map(|v| (v.slug.clone(), v.id)).collect();
+    let now = Utc::now().naive_utc();
+
+    let txn = db.begin().await?;
+
+    // Parents are linked once every row exists
+    for record in records.iter().filter(|v| changed.contains(v.slug.as_str())) {
+        let old = by_slug.get(record.slug.as_str());
+        let mut model = match old {
+            Some(old) => (*old).clone().into_active_model(),
+            None => categories::ActiveModel {
+                ..Default::default()
+            },
+        };
+        model.name = Set(record.name.clone());
+        model.slug = Set(record.slug.clone());
+        model.description = Set(record.description.clone());
+        model.icon = Set(record.icon.clone());
+        model.colour = Set(record.colour.clone());
+        model.sort_order = Set(record.sort_order);
+        model.archived_at = Set(match record.archived {
+            true => old.and_then(|v| v.archived_at).or(Some(now)),
+            false => None,
+        });
+
+        let model = match old {
+            Some(_) => model.update(&txn).await?,
+            None => {
+                model.parent_id = Set(None);
+                model.insert(&txn).await?
+            }
+        };
+        ids.insert(model.slug.clone(), model.id);
+    }
+
+    for record in records.iter().filter(|v| changed.contains(v.slug.as_str())) {
+        let mut model = categories::ActiveModel {
+            id: Set(ids[&record.slug]),
+            ..Default::default()
+        };
+        model.parent_id = Set(record.parent.as_ref().map(|v| ids[v]));
+        model.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+
+    for slug in &changed {
+        events
+            .publish(DomainEvent::new(Topic::Categories, ids[*slug]))
+            .await;
+    }
+
+    Ok(report.response())
+}
@@ -0,0 +1,167 @@
+use actix_web::{
+    error::ErrorBadRequest,
+    http::header,
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use chrono::NaiveDateTime;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationErrors};
+
+use crate::{entities::sea_orm_active_enums::ReleaseType, error::AppError};
+
+pub mod categories;
+pub mod versions;
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ImportQuery {
+    /// Format of the body
+    #[serde(default)]
+    format: Format,
+    /// Only report what would change
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Categories are matched by slug, parents are referenced by slug too.
+#[derive(Serialize, Deserialize, ToSchema, Validate, Clone, PartialEq)]
+pub struct CategoryRecord {
+    #[validate(length(max = 64), custom(function = "crate::validation::not_blank"))]
+    name: String,
+    #[validate(custom(function = "crate::validation::slug"))]
+    slug: String,
+    parent: Option<String>,
+    #[validate(length(max = 1024))]
+    description: Option<String>,
+    #[validate(length(max = 255))]
+    icon: Option<String>,
+    #[validate(custom(function = "crate::validation::colour"))]
+    colour: Option<String>,
+    #[serde(default)]
+    sort_order: i32,
+    #[serde(default)]
+    archived: bool,
+}
+
+/// Versions are matched by name.
+#[derive(Serialize, Deserialize, ToSchema, Validate, Clone, PartialEq)]
+pub struct VersionRecord {
+    #[validate(length(max = 32), custom(function = "crate::validation::not_blank"))]
+    name: String,
+    #[validate(range(min = 0))]
+    protocol: i32,
+    release_type: Option<ReleaseType>,
+    released_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportConflict {
+    /// Slug or name of the record
+    pub key: String,
+    reason: String,
+}
+
+/// Nothing is applied while there are conflicts.
+#[derive(Serialize, ToSchema, Default)]
+pub struct ImportReport {
+    dry_run: bool,
+    created: Vec<String>,
+    updated: Vec<String>,
+    unchanged: usize,
+    conflicts: Vec<ImportConflict>,
+}
+
+impl ImportReport {
+    fn conflict(&mut self, key: &str, reason: impl Into<String>) {
+        self.conflicts.push(ImportConflict {
+            key: key.to_owned(),
+            reason: reason.into(),
+        });
+    }
+
+    /// 409 when conflicts kept the import from being applied.
+    fn response(self) -> HttpResponse {
+        match self.conflicts.is_empty() || self.dry_run {
+            true => HttpResponse::Ok().json(self),
+            false => HttpResponse::Conflict().json(self),
+        }
+    }
+}
+
+fn invalid_fields(errors: &ValidationErrors) -> String {
+    let mut fields: Vec<&str> = errors.field_errors().keys().copied().collect();
+    fields.sort();
+    format!("Invalid fields: {}", fields.join(", "))
+}
+
+fn parse<T: DeserializeOwned>(format: Format, body: &[u8]) -> Result<Vec<T>, AppError> {
+    match format {
+        Format::Json => Ok(serde_json::from_slice(body)?),
+        Format::Csv => csv::Reader::from_reader(body)
+            .deserialize()
+            .collect::<Result<Vec<T>, _>>()
+            .map_err(|e| ErrorBadRequest(format!("Invalid CSV: {e}")).into()),
+    }
+}
+
+fn export<T: Serialize>(
+    format: Format,
+    name: &str,
+    records: &[T],
+) -> Result<HttpResponse, AppError> {
+    match format {
+        Format::Json => Ok(HttpResponse::Ok().json(records)),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in records {
+                writer
+                    .serialize(record)
+                    .map_err(|e| std::io::Error::other(e.to_string()))?;
+            }
+            let body = writer
+                .into_inner()
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv")
+                .insert_header((
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{name}.csv\""),
+                ))
+                .body(body))
+        }
+    }
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(
+                web::resource("/admin/catalog/categories")
+                    .get(categories::export_categories)
+                    .post(categories::import_categories),
+            )
+            .service(
+                web::resource("/admin/catalog/versions")
+                    .get(versions::export_versions)
+                    .post(versions::import_versions),
+            );
+    }
+}
@@ -0,0 +1,187 @@
+use actix_web::{web, Responder};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryOrder, Set,
+    TransactionTrait,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use validator::Validate;
+
+use crate::{
+    entities::versions,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    version_sync::{reorder, INVERTED_RANGES},
+};
+
+use super::{export, invalid_fields, parse, ExportQuery, ImportQuery, ImportReport, VersionRecord};
+
+fn to_record(version: &versions::Model) -> VersionRecord {
+    VersionRecord {
+        name: version.name.clone(),
+        protocol: version.protocol,
+        release_type: version.release_type,
+        released_at: version.released_at,
+        archived: version.archived_at.is_some(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/catalog/versions",
+    tag = "Admin",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Every version from the oldest, archived ones included, as JSON or CSV", body = Vec<VersionRecord>),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn export_versions(
+    db: web::Data<Arc<DatabaseConnection>>,
+    query: web::Query<ExportQuery>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let records: Vec<VersionRecord> = versions::Entity::find()
+        .order_by_asc(versions::Column::Ordinal)
+        .all(db.get_ref().as_ref())
+        .await?
+        .iter()
+        .map(to_record)
+        .collect();
+
+    export(query.format, "versions", &records)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/catalog/versions",
+    tag = "Admin",
+    params(ImportQuery),
+    request_body(content = Vec<VersionRecord>, description = "Versions from the oldest as a JSON array or CSV with a header row", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Versions imported, or what would change for a dry run", body = ImportReport),
+        (status = 400, description = "Body can not be parsed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 409, description = "Conflicts kept anything from being imported, including servers whose version range the release dates would invert", body = ImportReport),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn import_versions(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let records: Vec<VersionRecord> = parse(query.format, &body)?;
+    let existing = versions::Entity::find().all(db.get_ref().as_ref()).await?;
+    let by_name: HashMap<String, &versions::Model> = existing
+        .iter()
+        .map(|v| (v.name.to_lowercase(), v))
+        .collect();
+
+    let mut report = ImportReport {
+        dry_run: query.dry_run,
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+    let mut accepted = Vec::new();
+
+    for record in &records {
+        if !seen.insert(record.name.to_lowercase()) {
+            report.conflict(&record.name, "Name is listed more than once");
+            continue;
+        }
+        if let Err(e) = record.validate() {
+            report.conflict(&record.name, invalid_fields(&e));
+            continue;
+        }
+
+        match by_name.get(&record.name.to_lowercase()) {
+            Some(old) if to_record(old) == *record => report.unchanged += 1,
+            Some(old) => {
+                report.updated.push(record.name.clone());
+                accepted.push((record, Some(*old)));
+            }
+            None => {
+                report.created.push(record.name.clone());
+                accepted.push((record, None));
+            }
+        }
+    }
+
+    if query.dry_run || !report.conflicts.is_empty() {
+        return Ok(report.response());
+    }
+
+    let mut ordinal = existing.iter().map(|v| v.ordinal).max().unwrap_or(0);
+    let now = Utc::now().naive_utc();
+    let mut changed = Vec::new();
+
+    let txn = db.begin().await?;
+
+    // New versions are appended in the order of the file, release dates sort them after
+    for (record, old) in accepted {
+        let mut model = match old {
+            Some(old) => old.clone().into_active_model(),
+            None => {
+                ordinal += 1;
+                versions::ActiveModel {
+                    ordinal: Set(ordinal),
+                    ..Default::default()
+                }
+            }
+        };
+        model.name = Set(record.name.clone());
+        model.protocol = Set(record.protocol);
+        model.release_type = Set(record.release_type);
+        model.released_at = Set(record.released_at);
+        model.archived_at = Set(match record.archived {
+            true => old.and_then(|v| v.archived_at).or(Some(now)),
+            false => None,
+        });
+
+        let model = match old {
+            Some(_) => model.update(&txn).await?,
+            None => model.insert(&txn).await?,
+        };
+        changed.push(model.id);
+    }
+
+    if !changed.is_empty() {
+        let inverted = reorder(&txn).await?;
+        if !inverted.is_empty() {
+            txn.rollback().await?;
+            for server in inverted {
+                report.conflict(&server.name, INVERTED_RANGES);
+            }
+            return Ok(report.response());
+        }
+    }
+
+    txn.commit().await?;
+
+    for id in changed {
+        events.publish(DomainEvent::new(Topic::Versions, id)).await;
+    }
+
+    Ok(report.response())
+}
@@ -1,23 +1,31 @@
-use actix_web::{error::ErrorConflict, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait};
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::categories, error::AppError};
+use crate::{
+    entities::categories,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
 
-use super::Category;
+use super::{check_parent, check_unique, slugify, Category};
 
 #[utoipa::path(
     post,
     path = "/api/categories",
     tag = "Categories",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
-    request_body(content = Category, description = "Category Data", content_type = "application/json", example = json!({"name": "Vanilla"})),
+    request_body(content = Category, description = "Category Data", content_type = "application/json", example = json!({"name": "BedWars", "parent_id": 2, "icon": "minecraft:red_bed", "colour": "#ff5555"})),
     responses(
         (status = 201, description = "Created new category", body = None, example = json!({"message": "Success", "id": 3})),
-        (status = 409, description = "Category already exists", body = None, example = json!({"message": "Category already exists"})),
+        (status = 400, description = "Parent category does not exist or no slug can be derived from the name"),
+        (status = 409, description = "Category or slug already exists", body = None, example = json!({"message": "Category already exists"})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -26,26 +34,36 @@ use super::Category;
 )]
 pub async fn add_category(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<Category>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<Category>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
-    let categories: Vec<String> = categories::Entity::find()
-        .all(db.get_ref().as_ref())
-        .await?
-        .iter()
-        .map(|v| v.name.to_lowercase())
-        .collect();
-
-    if categories.contains(&data.name.to_lowercase()) {
-        return Err(ErrorConflict("Category already exists").into());
+    user.require(Permission::ManageCatalog)?;
+
+    let slug = data.slug.clone().unwrap_or_else(|| slugify(&data.name));
+    if crate::validation::slug(&slug).is_err() {
+        return Err(ErrorBadRequest("Name can not be turned into a slug, set one").into());
     }
 
+    check_unique(&db, None, &data.name, &slug).await?;
+    check_parent(&db, None, data.parent_id).await?;
+
     let model = categories::ActiveModel {
+        parent_id: Set(data.parent_id),
         name: Set(data.name.clone()),
+        slug: Set(slug),
+        description: Set(data.description.clone()),
+        icon: Set(data.icon.clone()),
+        colour: Set(data.colour.clone()),
+        sort_order: Set(data.sort_order),
         ..Default::default()
     };
 
     let model_i = model.insert(db.get_ref().as_ref()).await?;
     let last_id = model_i.id;
+    events
+        .publish(DomainEvent::new(Topic::Categories, last_id))
+        .await;
 
     Ok(HttpResponse::Created().json(json!({"message": "Success", "id": last_id})))
 }
@@ -1,25 +1,97 @@
 use actix_web::{web, HttpResponse, Responder};
-use sea_orm::{DatabaseConnection, EntityTrait};
-use serde_json::json;
-use std::sync::Arc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use crate::{entities::categories, error::AppError};
+use crate::{
+    entities::{categories, server_categories, servers},
+    error::AppError,
+};
+
+use super::CategoryNode;
+
+/// Children of `parent` with the servers found anywhere below them.
+fn build_tree(
+    parent: Option<i32>,
+    children: &HashMap<Option<i32>, Vec<categories::Model>>,
+    servers: &HashMap<i32, HashSet<i32>>,
+) -> (Vec<CategoryNode>, HashSet<i32>) {
+    let mut nodes = Vec::new();
+    let mut all = HashSet::new();
+
+    for category in children.get(&parent).into_iter().flatten() {
+        let (sub, mut found) = build_tree(Some(category.id), children, servers);
+        found.extend(servers.get(&category.id).into_iter().flatten());
+
+        nodes.push(CategoryNode {
+            id: category.id,
+            name: category.name.clone(),
+            slug: category.slug.clone(),
+            description: category.description.clone(),
+            icon: category.icon.clone(),
+            colour: category.colour.clone(),
+            sort_order: category.sort_order,
+            server_count: found.len(),
+            children: sub,
+        });
+        all.extend(found);
+    }
+
+    (nodes, all)
+}
 
 #[utoipa::path(
     get,
     path = "/api/categories",
     tag = "Categories",
     responses(
-        (status = 200, description = "List of categories", body = Vec<crate::entities::categories::Model>, example = json!([{"id": 1, "name": "Survival"}, {"id": 3, "name": "Vanilla"}])),
+        (status = 200, description = "Tree of categories", body = Vec<CategoryNode>, example = json!([{"id": 2, "name": "Minigames", "slug": "minigames", "description": null, "icon": "minecraft:diamond_sword", "colour": null, "sort_order": 0, "server_count": 5, "children": [{"id": 3, "name": "BedWars", "slug": "bedwars", "description": null, "icon": "minecraft:red_bed", "colour": "#ff5555", "sort_order": 0, "server_count": 3, "children": []}]}])),
         (status = 500, description = "Server error"),
     )
 )]
 pub async fn list_categories(
     db: web::Data<Arc<DatabaseConnection>>,
 ) -> Result<impl Responder, AppError> {
+    // Children of an archived category are left out with it
     let categories = categories::Entity::find()
+        .filter(categories::Column::ArchivedAt.is_null())
+        .order_by_asc(categories::Column::SortOrder)
+        .order_by_asc(categories::Column::Name)
         .all(db.get_ref().as_ref())
         .await?;
 
-    Ok(HttpResponse::Ok().json(json! {categories}))
+    let pairs: Vec<(i32, i32)> = server_categories::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            server_categories::Relation::Servers.def(),
+        )
+        .filter(servers::Column::HiddenAt.is_null())
+        .select_only()
+        .column(server_categories::Column::CategoryId)
+        .column(server_categories::Column::ServerId)
+        .into_tuple()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    let mut servers: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for (category_id, server_id) in pairs {
+        servers.entry(category_id).or_default().insert(server_id);
+    }
+
+    let mut children: HashMap<Option<i32>, Vec<categories::Model>> = HashMap::new();
+    for category in categories {
+        children
+            .entry(category.parent_id)
+            .or_default()
+            .push(category);
+    }
+
+    let (tree, _) = build_tree(None, &children, &servers);
+
+    Ok(HttpResponse::Ok().json(tree))
 }
@@ -1,52 +1,168 @@
-use actix_web::web::{self, ServiceConfig};
-use actix_web_lab::middleware::from_fn;
-use serde::Deserialize;
+use actix_web::{
+    error::{ErrorBadRequest, ErrorConflict},
+    web::{self, ServiceConfig},
+};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::utils::admin_auth_middleware;
+use crate::{entities::categories, error::AppError};
 
 pub mod add_category;
 pub mod list_categories;
 pub mod remove_category;
 pub mod update_category;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct Category {
+    #[validate(length(max = 64), custom(function = "crate::validation::not_blank"))]
     name: String,
+    /// Derived from the name when left out
+    #[validate(custom(function = "crate::validation::slug"))]
+    slug: Option<String>,
+    /// Top level category when left out
+    #[validate(range(min = 1))]
+    parent_id: Option<i32>,
+    #[validate(length(max = 1024))]
+    description: Option<String>,
+    #[validate(length(max = 255))]
+    icon: Option<String>,
+    #[validate(custom(function = "crate::validation::colour"))]
+    colour: Option<String>,
+    /// Lower comes first among siblings
+    #[serde(default)]
+    sort_order: i32,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateCategory {
+    #[validate(range(min = 1))]
     id: i32,
+    #[validate(length(max = 64), custom(function = "crate::validation::not_blank"))]
     name: String,
+    /// Kept when left out
+    #[validate(custom(function = "crate::validation::slug"))]
+    slug: Option<String>,
+    /// Top level category when left out
+    #[validate(range(min = 1))]
+    parent_id: Option<i32>,
+    #[validate(length(max = 1024))]
+    description: Option<String>,
+    #[validate(length(max = 255))]
+    icon: Option<String>,
+    #[validate(custom(function = "crate::validation::colour"))]
+    colour: Option<String>,
+    #[serde(default)]
+    sort_order: i32,
+    /// Archived categories are left out of listings and can not be picked for servers
+    #[serde(default)]
+    archived: bool,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct DeleteCategory {
+    #[validate(range(min = 1))]
     id: Option<i32>,
+    #[validate(length(max = 64))]
     name: String,
+    /// Servers and children of the category are moved to this one first
+    #[validate(range(min = 1))]
+    merge_into: Option<i32>,
+    /// Keep the row archived instead of deleting it, servers keep the category
+    #[serde(default)]
+    archive: bool,
+}
+
+/// Category with its children, ordered by `sort_order` then name.
+#[derive(Serialize, ToSchema)]
+pub struct CategoryNode {
+    id: i32,
+    name: String,
+    slug: String,
+    description: Option<String>,
+    icon: Option<String>,
+    colour: Option<String>,
+    sort_order: i32,
+    /// Visible servers in the category or any of its children, each counted once
+    server_count: usize,
+    children: Vec<CategoryNode>,
+}
+
+/// Lowercase words of the name joined by hyphens.
+pub(crate) fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Name and slug have to be unique, `id` is left out when updating.
+async fn check_unique(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    name: &str,
+    slug: &str,
+) -> Result<(), AppError> {
+    let mut query = categories::Entity::find().filter(
+        Condition::any()
+            .add(categories::Column::Name.eq(name))
+            .add(categories::Column::Slug.eq(slug)),
+    );
+    if let Some(id) = id {
+        query = query.filter(categories::Column::Id.ne(id));
+    }
+
+    match query.one(db).await? {
+        Some(v) if v.slug == slug => Err(ErrorConflict("Slug is already used").into()),
+        Some(_) => Err(ErrorConflict("Category already exists").into()),
+        None => Ok(()),
+    }
+}
+
+/// The parent has to exist and can not be the category itself or one of its children.
+async fn check_parent(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<(), AppError> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    let parents: HashMap<i32, Option<i32>> = categories::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|v| (v.id, v.parent_id))
+        .collect();
+
+    if !parents.contains_key(&parent_id) {
+        return Err(ErrorBadRequest("Parent category does not exist").into());
+    }
+
+    let mut current = Some(parent_id);
+    let mut depth = 0;
+    while let Some(ancestor) = current {
+        if Some(ancestor) == id || depth > parents.len() {
+            return Err(ErrorBadRequest("A category can not be placed under itself").into());
+        }
+        current = parents.get(&ancestor).copied().flatten();
+        depth += 1;
+    }
+
+    Ok(())
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
         config.service(
             web::resource("/categories")
-                .route(
-                    // NOTE: .wrap() must be in the end
-                    web::post()
-                        .to(add_category::add_category)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
-                .route(
-                    web::delete()
-                        .to(remove_category::remove_category)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
-                .route(
-                    web::put()
-                        .to(update_category::update_category)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
+                .post(add_category::add_category)
+                .delete(remove_category::remove_category)
+                .put(update_category::update_category)
                 .get(list_categories::list_categories),
         );
     }
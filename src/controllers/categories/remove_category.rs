@@ -1,26 +1,45 @@
-use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter};
+use actix_web::{
+    error::{ErrorBadRequest, ErrorConflict, ErrorNotFound},
+    web, HttpResponse, Responder,
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, JoinType, ModelTrait,
+    PaginatorTrait, QueryFilter, QuerySelect, RelationTrait, TransactionTrait,
+};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::categories, error::AppError};
+use crate::{
+    controllers::servers::{in_use, ServerRef},
+    entities::{categories, server_categories, servers},
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
 
-use super::DeleteCategory;
+use super::{check_parent, DeleteCategory};
 
 #[utoipa::path(
     delete,
     path = "/api/categories",
     tag = "Categories",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
     request_body(content = DeleteCategory, description = "Category Data", content_type = "application/json", examples(
         ("Full" = (value = json!({"id": 3, "name": "Vanilla"}))),
-        ("No Id" = (value = json!({"name": "Vanilla"})))
+        ("No Id" = (value = json!({"name": "Vanilla"}))),
+        ("Merge" = (value = json!({"id": 3, "name": "Vanilla", "merge_into": 1}))),
+        ("Archive" = (value = json!({"id": 3, "name": "Vanilla", "archive": true})))
     )),
     responses(
-        (status = 200, description = "Successfully deleted category", body = None, example = json!({"message": "Success"})),
+        (status = 200, description = "Successfully deleted or archived category", body = None, example = json!({"message": "Success"})),
+        (status = 400, description = "Category to merge into does not exist, is archived or is the category itself or one of its children"),
         (status = 404, description = "Category does not exist", body = None, example = json!({"message": "No such category exist"})),
+        (status = 409, description = "Servers or children still use the category, the servers are listed", body = None, example = json!({"code": 409, "message": "Servers still use the category", "servers": [{"id": 4, "name": "Hypixel"}]})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -29,20 +48,145 @@ use super::DeleteCategory;
 )]
 pub async fn remove_category(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<DeleteCategory>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<DeleteCategory>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
     let category = if let Some(id) = data.id {
         categories::Entity::find().filter(categories::Column::Id.eq(id))
     } else {
         categories::Entity::find().filter(categories::Column::Name.eq(&data.name))
     }
     .one(db.get_ref().as_ref())
-    .await?;
+    .await?
+    .ok_or(ErrorNotFound("No such category exist"))?;
+    let id = category.id;
+
+    if let Some(target) = data.merge_into {
+        let exists = categories::Entity::find_by_id(target)
+            .filter(categories::Column::ArchivedAt.is_null())
+            .count(db.get_ref().as_ref())
+            .await?;
+        if target == id || exists == 0 {
+            return Err(ErrorBadRequest("Category to merge into does not exist").into());
+        }
+        // Children move under the target, which can not be one of them
+        check_parent(&db, Some(id), Some(target)).await?;
+    }
+
+    let txn = db.begin().await?;
+
+    // Servers and children picking the category wait for this lock, so the checks below hold
+    categories::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let servers: Vec<ServerRef> = servers::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            servers::Relation::ServerCategories.def(),
+        )
+        .filter(server_categories::Column::CategoryId.eq(id))
+        .select_only()
+        .column(servers::Column::Id)
+        .column(servers::Column::Name)
+        .into_model()
+        .all(&txn)
+        .await?;
+
+    // Archiving keeps the children, which are hidden along with their parent
+    if data.merge_into.is_none() && !data.archive {
+        let children = categories::Entity::find()
+            .filter(categories::Column::ParentId.eq(id))
+            .count(&txn)
+            .await?;
+        if children > 0 {
+            txn.rollback().await?;
+            return Err(ErrorConflict("Move or remove the children of the category first").into());
+        }
+        if !servers.is_empty() {
+            txn.rollback().await?;
+            return Ok(in_use("Servers still use the category", servers));
+        }
+    }
+
+    if let Some(target) = data.merge_into {
+        let merged: Vec<i32> = server_categories::Entity::find()
+            .select_only()
+            .column(server_categories::Column::ServerId)
+            .filter(server_categories::Column::CategoryId.eq(target))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+
+        // Servers already in the target only lose the old link, keeping it primary if it was
+        let primary: Vec<i32> = server_categories::Entity::find()
+            .select_only()
+            .column(server_categories::Column::ServerId)
+            .filter(server_categories::Column::CategoryId.eq(id))
+            .filter(server_categories::Column::IsPrimary.eq(1))
+            .filter(server_categories::Column::ServerId.is_in(merged.clone()))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+
+        server_categories::Entity::update_many()
+            .col_expr(server_categories::Column::IsPrimary, Expr::value(1))
+            .filter(server_categories::Column::CategoryId.eq(target))
+            .filter(server_categories::Column::ServerId.is_in(primary))
+            .exec(&txn)
+            .await?;
+
+        server_categories::Entity::delete_many()
+            .filter(server_categories::Column::CategoryId.eq(id))
+            .filter(server_categories::Column::ServerId.is_in(merged))
+            .exec(&txn)
+            .await?;
+
+        server_categories::Entity::update_many()
+            .col_expr(server_categories::Column::CategoryId, Expr::value(target))
+            .filter(server_categories::Column::CategoryId.eq(id))
+            .exec(&txn)
+            .await?;
+
+        categories::Entity::update_many()
+            .col_expr(categories::Column::ParentId, Expr::value(target))
+            .filter(categories::Column::ParentId.eq(id))
+            .exec(&txn)
+            .await?;
+    }
+
+    if data.archive {
+        categories::Entity::update_many()
+            .col_expr(
+                categories::Column::ArchivedAt,
+                Expr::value(Utc::now().naive_utc()),
+            )
+            .filter(categories::Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+    } else {
+        category.delete(&txn).await?;
+    }
+
+    txn.commit().await?;
 
-    if let Some(category) = category {
-        category.delete(db.get_ref().as_ref()).await?;
-        return Ok(HttpResponse::Ok().json(json!({"message": "Success"})));
+    events
+        .publish(DomainEvent::new(Topic::Categories, id))
+        .await;
+    if let Some(target) = data.merge_into {
+        events
+            .publish(DomainEvent::new(Topic::Categories, target))
+            .await;
+        for server in &servers {
+            events
+                .publish(DomainEvent::new(Topic::Servers, server.id))
+                .await;
+        }
     }
 
-    Err(ErrorNotFound("No such category exist").into())
+    Ok(HttpResponse::Ok().json(json!({"message": "Success"})))
 }
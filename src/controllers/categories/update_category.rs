@@ -1,23 +1,33 @@
 use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::categories, error::AppError};
+use crate::{
+    entities::categories,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
 
-use super::UpdateCategory;
+use super::{check_parent, check_unique, UpdateCategory};
 
 #[utoipa::path(
     put,
     path = "/api/categories",
     tag = "Categories",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
-    request_body(content = UpdateCategory, description = "Category Data", content_type = "application/json", example = json!({"id": 3, "name": "Vanilla"})),
+    request_body(content = UpdateCategory, description = "Category Data", content_type = "application/json", example = json!({"id": 3, "name": "SkyWars", "slug": "skywars", "parent_id": 2, "sort_order": 1})),
     responses(
         (status = 200, description = "Successfully updated category", body = None, example = json!({"message": "Success"})),
+        (status = 400, description = "Parent category does not exist or is the category itself or one of its children"),
         (status = 404, description = "Category does not exist", body = None, example = json!({"message": "No such category exist"})),
+        (status = 409, description = "Name or slug already used by another category"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -26,17 +36,40 @@ use super::UpdateCategory;
 )]
 pub async fn update_category(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<UpdateCategory>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<UpdateCategory>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
     let category = categories::Entity::find()
         .filter(categories::Column::Id.eq(data.id))
         .one(db.get_ref().as_ref())
         .await?;
 
     if let Some(category) = category {
+        let slug = data.slug.clone().unwrap_or_else(|| category.slug.clone());
+        check_unique(&db, Some(data.id), &data.name, &slug).await?;
+        check_parent(&db, Some(data.id), data.parent_id).await?;
+
+        let archived_at = match data.archived {
+            true => category.archived_at.or(Some(Utc::now().naive_utc())),
+            false => None,
+        };
+
         let mut new_category: categories::ActiveModel = category.into();
+        new_category.parent_id = Set(data.parent_id);
         new_category.name = Set(data.name.clone());
+        new_category.slug = Set(slug);
+        new_category.description = Set(data.description.clone());
+        new_category.icon = Set(data.icon.clone());
+        new_category.colour = Set(data.colour.clone());
+        new_category.sort_order = Set(data.sort_order);
+        new_category.archived_at = Set(archived_at);
         new_category.update(db.get_ref().as_ref()).await?;
+        events
+            .publish(DomainEvent::new(Topic::Categories, data.id))
+            .await;
 
         return Ok(HttpResponse::Ok().json(json!({"message": "Success"})));
     }
@@ -1,17 +1,35 @@
 use actix_web::web::{self, ServiceConfig};
 
+pub mod admin;
 pub mod auth;
+pub mod catalog;
 pub mod categories;
+pub mod moderation;
 pub mod servers;
+pub mod stats;
+pub mod tags;
+pub mod totp;
+pub mod users;
 pub mod versions;
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
-        config.service(web::scope("/auth").configure(auth::configure()));
+        config.service(web::resource("/.well-known/jwks.json").get(auth::jwks::jwks));
+        config.service(
+            web::scope("/auth")
+                .configure(auth::configure())
+                .configure(totp::configure()),
+        );
         config.service(
             web::scope("/api")
+                .configure(admin::configure())
+                .configure(catalog::configure())
                 .configure(servers::configure())
                 .configure(categories::configure())
+                .configure(moderation::configure())
+                .configure(stats::configure())
+                .configure(tags::configure())
+                .configure(users::configure())
                 .configure(versions::configure()),
         );
     }
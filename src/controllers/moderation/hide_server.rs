@@ -0,0 +1,66 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+use crate::{
+    entities::servers,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::Visibility;
+
+#[utoipa::path(
+    put,
+    path = "/api/moderation/servers/{id}",
+    tag = "Moderation",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    request_body(content = Visibility, description = "Whether the server is hidden", content_type = "application/json", example = json!({"hidden": true})),
+    responses(
+        (status = 204, description = "Visibility changed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the moderate permission"),
+        (status = 404, description = "Server does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn hide_server(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    path: web::Path<i32>,
+    data: ValidJson<Visibility>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::Moderate)?;
+
+    let id = path.into_inner();
+    let (hidden_at, hidden_by) = match data.hidden {
+        true => (Some(Utc::now().naive_utc()), Some(user.id)),
+        false => (None, None),
+    };
+
+    let res = servers::Entity::update_many()
+        .col_expr(servers::Column::HiddenAt, Expr::value(hidden_at))
+        .col_expr(servers::Column::HiddenBy, Expr::value(hidden_by))
+        .filter(servers::Column::Id.eq(id))
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    if res.rows_affected == 0 {
+        return Err(ErrorNotFound("No such server exists").into());
+    }
+
+    log::info!("User {} set server {id} hidden: {}", user.id, data.hidden);
+    events.publish(DomainEvent::new(Topic::Servers, id)).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
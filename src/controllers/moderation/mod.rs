@@ -0,0 +1,21 @@
+use actix_web::web::{self, ServiceConfig};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+pub mod hide_review;
+pub mod hide_server;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct Visibility {
+    /// Hidden servers and reviews are left out of every listing
+    hidden: bool,
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(web::resource("/moderation/servers/{id}").put(hide_server::hide_server))
+            .service(web::resource("/moderation/reviews/{id}").put(hide_review::hide_review));
+    }
+}
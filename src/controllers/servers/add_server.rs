@@ -1,23 +1,24 @@
 use actix_web::{
-    error::{ErrorBadRequest, ErrorConflict},
-    web, HttpRequest, HttpResponse, Responder,
+    error::{ErrorBadRequest, ErrorConflict, ErrorForbidden, ErrorUnauthorized},
+    web, HttpResponse, Responder,
 };
-use craftping::ping;
 use migration::{Alias, Expr, SimpleExpr};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
-    QueryFilter, QuerySelect, QueryTrait,
+    QueryFilter, QuerySelect, QueryTrait, TransactionTrait,
 };
 use serde_json::json;
 use std::{collections::HashSet, sync::Arc};
 
 use crate::{
-    entities::{categories, server_categories, servers, servers_info, versions},
+    entities::{categories, server_categories, servers, servers_info, users, versions},
     error::AppError,
-    utils::RequestUtils,
+    events::{DomainEvent, EventBus, Topic},
+    utils::AuthUser,
+    validation::ValidJson,
 };
 
-use super::ServerData;
+use super::{ServerData, MAX_CATEGORIES};
 
 fn vect_difference(v1: &[String], v2: &[String]) -> Vec<String> {
     let s1: HashSet<String> = v1.iter().cloned().collect();
@@ -30,6 +31,7 @@ fn sub(version: &str) -> migration::SubQueryStatement {
         .select_only()
         .column(versions::Column::Id)
         .filter(versions::Column::Name.eq(version))
+        .filter(versions::Column::ArchivedAt.is_null())
         .into_query()
         .into_sub_query_statement()
 }
@@ -38,12 +40,12 @@ fn sub(version: &str) -> migration::SubQueryStatement {
     post,
     path = "/api/servers",
     tag = "Servers",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
     request_body(content = ServerData, description = "Server Data", content_type = "application/json"),
     responses(
         (status = 200, description = "Server object", body = Server),
+        (status = 400, description = "Unknown category or version, too many categories, tags or pending tag suggestions, an invalid tag, a primary category outside of them, or the minimum version is newer than the maximum"),
+        (status = 403, description = "Email address is not verified"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -52,12 +54,41 @@ fn sub(version: &str) -> migration::SubQueryStatement {
 )]
 pub async fn add_server(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<ServerData>,
-    req: HttpRequest,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<ServerData>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    let user_id = user.id;
+    let user = users::Entity::find_by_id(user_id)
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or(ErrorUnauthorized("Invalid token"))?;
+
+    if user.email_verified_at.is_none() {
+        return Err(ErrorForbidden("Verify your email before adding a server").into());
+    }
+
     // Validate Categories
+    let names: HashSet<String> = data.categories.iter().map(|v| v.to_lowercase()).collect();
+    if names.len() > MAX_CATEGORIES {
+        return Err(ErrorBadRequest(format!(
+            "A server can have at most {MAX_CATEGORIES} categories"
+        ))
+        .into());
+    }
+
+    let primary = data
+        .primary_category
+        .as_ref()
+        .unwrap_or(&data.categories[0])
+        .to_lowercase();
+    if !names.contains(&primary) {
+        return Err(ErrorBadRequest("Primary category must be one of the categories").into());
+    }
+
     let categories = categories::Entity::find()
         .filter(categories::Column::Name.is_in(data.categories.clone()))
+        .filter(categories::Column::ArchivedAt.is_null())
         .all(db.get_ref().as_ref())
         .await?;
 
@@ -75,6 +106,8 @@ pub async fn add_server(
         );
     }
 
+    data.attributes.check_tags()?;
+
     // Validate Versions
     let versions: (Option<i32>, Option<i32>) = versions::Entity::find()
         .select_only()
@@ -99,6 +132,20 @@ pub async fn add_server(
         return Err(ErrorBadRequest("Version like this does not exist").into());
     }
 
+    let ordinals: Vec<(i32, i32)> = versions::Entity::find()
+        .select_only()
+        .column(versions::Column::Id)
+        .column(versions::Column::Ordinal)
+        .filter(versions::Column::Id.is_in([versions.0.unwrap(), versions.1.unwrap()]))
+        .into_tuple()
+        .all(db.get_ref().as_ref())
+        .await?;
+    let ordinal = |id: Option<i32>| ordinals.iter().find(|v| Some(v.0) == id).map(|v| v.1);
+
+    if ordinal(versions.0) > ordinal(versions.1) {
+        return Err(ErrorBadRequest("Minimum version is newer than the maximum version").into());
+    }
+
     // Check if server already exists
     let servers: Vec<String> = servers::Entity::find()
         .all(db.get_ref().as_ref())
@@ -115,38 +162,47 @@ pub async fn add_server(
         return Err(ErrorBadRequest("You have reached limit of servers").into());
     }
 
-    let new_server = servers::ActiveModel {
+    let mut new_server = servers::ActiveModel {
         name: Set(data.name.clone()),
         description: Set(data.description.clone()),
         is_premium: Set(false as i8),
-        user_id: Set(req.get_user_id()?),
+        user_id: Set(user_id),
         ..Default::default()
     };
-    let server = new_server.insert(db.get_ref().as_ref()).await?;
+    data.attributes.apply(&mut new_server);
+
+    let txn = db.begin().await?;
+    let server = new_server.insert(&txn).await?;
 
     let new_server_info = servers_info::ActiveModel {
         address: Set(data.address.clone()),
+        port: Set(data.port),
         server_id: Set(server.id),
         min_version: Set(versions.0.unwrap()),
         max_version: Set(versions.1.unwrap()),
         ..Default::default()
     };
-    new_server_info.insert(db.get_ref().as_ref()).await?;
+    new_server_info.insert(&txn).await?;
 
     let new_categories: Vec<server_categories::ActiveModel> = categories
         .iter()
         .map(|v| server_categories::ActiveModel {
             server_id: Set(server.id),
             category_id: Set(v.id),
+            is_primary: Set((v.name.to_lowercase() == primary) as i8),
         })
         .collect();
 
     server_categories::Entity::insert_many(new_categories)
-        .exec(db.get_ref().as_ref())
+        .exec(&txn)
         .await?;
 
-    // let ping = ping(data.address.clone(), data.port).await;
-    // ping.
+    data.attributes.save_tags(&txn, server.id, user_id).await?;
+    txn.commit().await?;
+
+    events
+        .publish(DomainEvent::new(Topic::Servers, server.id))
+        .await;
 
     Ok(HttpResponse::Created().json(json!({"message": "Success"})))
 }
@@ -0,0 +1,168 @@
+use actix_multipart::Multipart;
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::ImageKind, server_images},
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    images::{self, BANNER},
+    storage::{self, Storage},
+    utils::{random_hex, AuthUser},
+};
+
+use super::{owned_server, read_file, ServerImage};
+
+#[utoipa::path(
+    put,
+    path = "/api/servers/{id}/banner",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    request_body(content = ImageUpload, description = "468x60 GIF, PNG or JPEG of at most 2 MiB, replacing the current banner", content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Banner stored", body = ServerImage),
+        (status = 400, description = "Missing file, or not an image of the allowed formats and size"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Server belongs to someone else"),
+        (status = 404, description = "Server does not exist"),
+        (status = 413, description = "File is too large"),
+        (status = 500, description = "Storage or database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn upload_banner(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    storage: web::Data<Arc<dyn Storage>>,
+    path: web::Path<i32>,
+    payload: Multipart,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let server = owned_server(&db, path.into_inner(), &user).await?;
+    let data = read_file(payload, BANNER.max_bytes).await?;
+    let info = images::inspect(&data, &BANNER)?;
+
+    let size = data.len() as i32;
+    let key = format!(
+        "servers/{}/banner-{}.{}",
+        server.id,
+        random_hex(8),
+        info.extension()
+    );
+    storage.put(key.clone(), data, info.content_type()).await?;
+
+    let old = server_images::Entity::find()
+        .filter(server_images::Column::ServerId.eq(server.id))
+        .filter(server_images::Column::Kind.eq(ImageKind::Banner))
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    let saved = async {
+        let txn = db.begin().await?;
+        server_images::Entity::delete_many()
+            .filter(server_images::Column::Id.is_in(old.iter().map(|v| v.id)))
+            .exec(&txn)
+            .await?;
+        let model = server_images::ActiveModel {
+            server_id: Set(server.id),
+            kind: Set(ImageKind::Banner),
+            url: Set(storage.url(&key)),
+            storage_key: Set(key.clone()),
+            content_type: Set(info.content_type().to_owned()),
+            width: Set(info.width as i32),
+            height: Set(info.height as i32),
+            size: Set(size),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok::<_, AppError>(model)
+    }
+    .await;
+
+    let model = match saved {
+        Ok(model) => model,
+        Err(e) => {
+            storage::discard(Arc::clone(storage.get_ref()), vec![key]);
+            return Err(e);
+        }
+    };
+
+    storage::discard(
+        Arc::clone(storage.get_ref()),
+        old.into_iter().map(|v| v.storage_key).collect(),
+    );
+    events
+        .publish(DomainEvent::new(Topic::Servers, server.id))
+        .await;
+
+    Ok(HttpResponse::Created().json(ServerImage {
+        id: model.id,
+        url: model.url,
+        thumbnail_url: None,
+        width: model.width,
+        height: model.height,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{id}/banner",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    responses(
+        (status = 204, description = "Banner removed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Server belongs to someone else"),
+        (status = 404, description = "Server or banner does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn remove_banner(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    storage: web::Data<Arc<dyn Storage>>,
+    path: web::Path<i32>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let server = owned_server(&db, path.into_inner(), &user).await?;
+
+    let banners = server_images::Entity::find()
+        .filter(server_images::Column::ServerId.eq(server.id))
+        .filter(server_images::Column::Kind.eq(ImageKind::Banner))
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    if banners.is_empty() {
+        return Err(ErrorNotFound("Server has no banner").into());
+    }
+
+    server_images::Entity::delete_many()
+        .filter(server_images::Column::Id.is_in(banners.iter().map(|v| v.id)))
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    storage::discard(
+        Arc::clone(storage.get_ref()),
+        banners.into_iter().map(|v| v.storage_key).collect(),
+    );
+    events
+        .publish(DomainEvent::new(Topic::Servers, server.id))
+        .await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,193 @@
+use actix_multipart::Multipart;
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    web, HttpResponse, Responder,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    entities::{sea_orm_active_enums::ImageKind, server_images, servers},
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    images::{self, SCREENSHOT},
+    storage::{self, Storage},
+    utils::{random_hex, AuthUser},
+};
+
+use super::{owned_server, read_file, ServerImage, MAX_SCREENSHOTS};
+
+#[utoipa::path(
+    post,
+    path = "/api/servers/{id}/gallery",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    request_body(content = ImageUpload, description = "PNG, JPEG or WebP screenshot between 320x180 and 3840x2160, of at most 8 MiB", content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Screenshot and its thumbnail stored", body = ServerImage),
+        (status = 400, description = "Missing file, not an image of the allowed formats and size, or the gallery is full"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Server belongs to someone else"),
+        (status = 404, description = "Server does not exist"),
+        (status = 413, description = "File is too large"),
+        (status = 500, description = "Storage or database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn add_screenshot(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    storage: web::Data<Arc<dyn Storage>>,
+    path: web::Path<i32>,
+    payload: Multipart,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let server = owned_server(&db, path.into_inner(), &user).await?;
+
+    // Checked before the upload is read, and again once the server row is locked
+    check_gallery_room(db.get_ref().as_ref(), server.id).await?;
+
+    let data = read_file(payload, SCREENSHOT.max_bytes).await?;
+    let info = images::inspect(&data, &SCREENSHOT)?;
+    let (data, thumbnail) =
+        web::block(move || images::thumbnail(&data, &info).map(|thumbnail| (data, thumbnail)))
+            .await
+            .map_err(actix_web::Error::from)?
+            .map_err(|e| ErrorBadRequest(format!("Image can not be read: {e}")))?;
+
+    let size = data.len() as i32;
+    let name = format!("servers/{}/screenshot-{}", server.id, random_hex(8));
+    let key = format!("{name}.{}", info.extension());
+    let thumbnail_key = format!("{name}-thumb.jpg");
+
+    storage.put(key.clone(), data, info.content_type()).await?;
+    if let Err(e) = storage
+        .put(thumbnail_key.clone(), thumbnail, "image/jpeg")
+        .await
+    {
+        storage::discard(Arc::clone(storage.get_ref()), vec![key]);
+        return Err(e);
+    }
+
+    let saved = async {
+        let txn = db.begin().await?;
+        servers::Entity::find_by_id(server.id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?;
+        check_gallery_room(&txn, server.id).await?;
+
+        let model = server_images::ActiveModel {
+            server_id: Set(server.id),
+            kind: Set(ImageKind::Screenshot),
+            url: Set(storage.url(&key)),
+            storage_key: Set(key.clone()),
+            thumbnail_url: Set(Some(storage.url(&thumbnail_key))),
+            thumbnail_key: Set(Some(thumbnail_key.clone())),
+            content_type: Set(info.content_type().to_owned()),
+            width: Set(info.width as i32),
+            height: Set(info.height as i32),
+            size: Set(size),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok::<_, AppError>(model)
+    }
+    .await;
+
+    let model = match saved {
+        Ok(model) => model,
+        Err(e) => {
+            storage::discard(Arc::clone(storage.get_ref()), vec![key, thumbnail_key]);
+            return Err(e);
+        }
+    };
+
+    events
+        .publish(DomainEvent::new(Topic::Servers, server.id))
+        .await;
+
+    Ok(HttpResponse::Created().json(ServerImage {
+        id: model.id,
+        url: model.url,
+        thumbnail_url: model.thumbnail_url,
+        width: model.width,
+        height: model.height,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{id}/gallery/{image_id}",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+        ("image_id" = i32, Path, description = "Id of the screenshot"),
+    ),
+    responses(
+        (status = 204, description = "Screenshot removed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Server belongs to someone else"),
+        (status = 404, description = "Server or screenshot does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn remove_screenshot(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    storage: web::Data<Arc<dyn Storage>>,
+    path: web::Path<(i32, i32)>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let (id, image_id) = path.into_inner();
+    let server = owned_server(&db, id, &user).await?;
+
+    let image = server_images::Entity::find_by_id(image_id)
+        .filter(server_images::Column::ServerId.eq(server.id))
+        .filter(server_images::Column::Kind.eq(ImageKind::Screenshot))
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or(ErrorNotFound("No such screenshot exists"))?;
+
+    let keys = [Some(image.storage_key.clone()), image.thumbnail_key.clone()]
+        .into_iter()
+        .flatten()
+        .collect();
+    image.delete(db.get_ref().as_ref()).await?;
+
+    storage::discard(Arc::clone(storage.get_ref()), keys);
+    events
+        .publish(DomainEvent::new(Topic::Servers, server.id))
+        .await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn check_gallery_room<C: ConnectionTrait>(db: &C, server_id: i32) -> Result<(), AppError> {
+    let count = server_images::Entity::find()
+        .filter(server_images::Column::ServerId.eq(server_id))
+        .filter(server_images::Column::Kind.eq(ImageKind::Screenshot))
+        .count(db)
+        .await?;
+    if count >= MAX_SCREENSHOTS {
+        return Err(ErrorBadRequest(format!(
+            "A server can have at most {MAX_SCREENSHOTS} screenshots"
+        ))
+        .into());
+    }
+
+    Ok(())
+}
@@ -1,28 +1,89 @@
-use actix_web::{web, HttpResponse, Responder};
-use sea_orm::DatabaseConnection;
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use migration::{Alias, Expr};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QuerySelect,
+    QueryTrait, RelationTrait,
+};
 use serde_json::json;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use crate::error::AppError;
+use crate::{
+    entities::{server_tags, servers, tags, versions},
+    error::AppError,
+};
 
-use super::utils;
+use super::{utils, ServerFilter};
 
 #[utoipa::path(
     get,
     path = "/api/servers",
     tag = "Servers",
+    params(ServerFilter),
     responses(
         (status = 200, description = "Server object", body = Vec<Server>),
+        (status = 400, description = "Unknown version in supports or invalid language"),
         (status = 500, description = "Server error"),
     ),
 )]
 pub async fn list_servers(
     db: web::Data<Arc<DatabaseConnection>>,
+    filter: web::Query<ServerFilter>,
 ) -> Result<impl Responder, AppError> {
-    let servers = utils::get_server()
-        .into_json()
-        .all(db.get_ref().as_ref())
-        .await?;
+    let mut query = utils::get_server();
+
+    if let Some(supports) = &filter.supports {
+        let ordinal: i32 = versions::Entity::find()
+            .select_only()
+            .column(versions::Column::Ordinal)
+            .filter(versions::Column::Name.eq(supports))
+            .into_tuple()
+            .one(db.get_ref().as_ref())
+            .await?
+            .ok_or(ErrorBadRequest("Unknown version"))?;
+
+        query = query
+            .filter(Expr::col((Alias::new("v1"), versions::Column::Ordinal)).lte(ordinal))
+            .filter(Expr::col((Alias::new("v2"), versions::Column::Ordinal)).gte(ordinal));
+    }
+
+    let slugs: HashSet<&str> = filter
+        .tags
+        .iter()
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+    if !slugs.is_empty() {
+        let tagged = server_tags::Entity::find()
+            .select_only()
+            .column(server_tags::Column::ServerId)
+            .join(JoinType::InnerJoin, server_tags::Relation::Tags.def())
+            .filter(tags::Column::Slug.is_in(slugs.iter().copied()))
+            .filter(tags::Column::ApprovedAt.is_not_null())
+            .group_by(server_tags::Column::ServerId)
+            .having(Expr::cust("COUNT(*)").eq(slugs.len() as i32))
+            .into_query();
+
+        query = query.filter(servers::Column::Id.in_subquery(tagged));
+    }
+
+    if let Some(language) = &filter.language {
+        // Also keeps LIKE wildcards out of the prefix match
+        crate::validation::language(language)
+            .map_err(|_| ErrorBadRequest("Invalid language code"))?;
+
+        query = query.filter(
+            Condition::any()
+                .add(servers::Column::Language.eq(language))
+                .add(servers::Column::Language.starts_with(format!("{language}-"))),
+        );
+    }
+
+    if let Some(country) = &filter.country {
+        query = query.filter(servers::Column::Country.eq(country.to_uppercase()));
+    }
+
+    let servers = query.into_json().all(db.get_ref().as_ref()).await?;
 
     Ok(HttpResponse::Ok().json(json! {servers}))
 }
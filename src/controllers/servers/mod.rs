@@ -1,46 +1,353 @@
-use actix_web::web::{self, ServiceConfig};
-use actix_web_lab::middleware::from_fn;
+use actix_multipart::Multipart;
+use actix_web::{
+    error::{ErrorBadRequest, ErrorForbidden, ErrorNotFound, ErrorPayloadTooLarge},
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use futures::TryStreamExt;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait,
+    DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
+};
 use serde::{Deserialize, Serialize, Serializer};
-use utoipa::ToSchema;
+use serde_json::json;
+use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
-use crate::utils::auth_middleware;
+use crate::{
+    controllers::categories::slugify,
+    entities::{server_tags, servers, tags},
+    error::AppError,
+    permissions::Permission,
+    utils::AuthUser,
+};
 
 pub mod add_server;
+pub mod banner;
+pub mod gallery;
 pub mod get_server;
 pub mod get_user_servers;
 pub mod list_servers;
-mod utils;
+pub mod players;
+pub mod reviews;
+pub mod update_attributes;
+pub(crate) mod utils;
 
-#[derive(Deserialize, ToSchema)]
+/// Most categories a server can be listed in.
+pub const MAX_CATEGORIES: usize = 5;
+/// Most tags a server can have, approved or not.
+pub const MAX_TAGS: usize = 10;
+/// Most tag suggestions a user can have waiting for review.
+pub const MAX_PENDING_TAGS: u64 = 20;
+/// Most screenshots in the gallery of a server.
+pub const MAX_SCREENSHOTS: u64 = 8;
+
+/// Details players filter by besides categories, owners can change them later.
+#[derive(Deserialize, ToSchema, Validate, Default)]
+pub struct ServerAttributes {
+    /// Language code like `en` or `pt-BR`
+    #[validate(custom(function = "crate::validation::language"))]
+    language: Option<String>,
+    /// Two letter country code
+    #[validate(custom(function = "crate::validation::country"))]
+    country: Option<String>,
+    #[validate(custom(function = "crate::validation::http_url"))]
+    website: Option<String>,
+    #[validate(custom(function = "crate::validation::discord_invite"))]
+    discord_invite: Option<String>,
+    #[validate(custom(function = "crate::validation::http_url"))]
+    store_url: Option<String>,
+    /// Tag names, unknown ones are suggested to the admins and shown once approved
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl ServerAttributes {
+    fn apply(&self, model: &mut servers::ActiveModel) {
+        model.language = Set(self.language.clone());
+        model.country = Set(self.country.clone());
+        model.website = Set(self.website.clone());
+        model.discord_invite = Set(self.discord_invite.clone());
+        model.store_url = Set(self.store_url.clone());
+    }
+
+    /// Name and slug of each distinct tag, 400 when one is invalid or there are too many.
+    fn check_tags(&self) -> Result<Vec<(String, String)>, AppError> {
+        let mut wanted: Vec<(String, String)> = Vec::new();
+        for name in &self.tags {
+            let name = name.trim();
+            let slug = slugify(name);
+            if slug.is_empty() || name.chars().count() > 32 || slug.len() > 32 {
+                return Err(ErrorBadRequest(format!("Invalid tag: {name}")).into());
+            }
+            if !wanted.iter().any(|v| v.1 == slug) {
+                wanted.push((name.to_owned(), slug));
+            }
+        }
+
+        if wanted.len() > MAX_TAGS {
+            return Err(
+                ErrorBadRequest(format!("A server can have at most {MAX_TAGS} tags")).into(),
+            );
+        }
+
+        Ok(wanted)
+    }
+
+    /// Replaces the tags of the server, creating the unknown ones as suggestions of `user_id`.
+    async fn save_tags<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        server_id: i32,
+        user_id: i32,
+    ) -> Result<(), AppError> {
+        let wanted = self.check_tags()?;
+
+        // Admins can give a tag another slug than its name would get
+        let existing = tags::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(tags::Column::Slug.is_in(wanted.iter().map(|v| v.1.clone())))
+                    .add(tags::Column::Name.is_in(wanted.iter().map(|v| v.0.clone()))),
+            )
+            .all(db)
+            .await?;
+
+        let new = wanted
+            .iter()
+            .filter(|(name, slug)| {
+                !existing
+                    .iter()
+                    .any(|v| &v.slug == slug || v.name.eq_ignore_ascii_case(name))
+            })
+            .count() as u64;
+        if new > 0 {
+            let pending = tags::Entity::find()
+                .filter(tags::Column::CreatedBy.eq(user_id))
+                .filter(tags::Column::ApprovedAt.is_null())
+                .count(db)
+                .await?;
+
+            if pending + new > MAX_PENDING_TAGS {
+                return Err(ErrorBadRequest(format!(
+                    "You can have at most {MAX_PENDING_TAGS} tag suggestions waiting for review"
+                ))
+                .into());
+            }
+        }
+
+        let mut ids = HashSet::new();
+        for (name, slug) in wanted {
+            let found = existing
+                .iter()
+                .find(|v| v.slug == slug || v.name.eq_ignore_ascii_case(&name));
+            let id = match found {
+                Some(tag) => tag.id,
+                None => {
+                    tags::ActiveModel {
+                        name: Set(name),
+                        slug: Set(slug),
+                        created_by: Set(Some(user_id)),
+                        ..Default::default()
+                    }
+                    .insert(db)
+                    .await?
+                    .id
+                }
+            };
+            ids.insert(id);
+        }
+
+        server_tags::Entity::delete_many()
+            .filter(server_tags::Column::ServerId.eq(server_id))
+            .exec(db)
+            .await?;
+
+        if !ids.is_empty() {
+            server_tags::Entity::insert_many(ids.into_iter().map(|tag_id| {
+                server_tags::ActiveModel {
+                    server_id: Set(server_id),
+                    tag_id: Set(tag_id),
+                }
+            }))
+            .exec(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct ServerData {
+    #[validate(length(max = 64), custom(function = "crate::validation::not_blank"))]
     name: String,
+    #[validate(length(max = 4096))]
     description: String,
+    #[validate(custom(function = "crate::validation::host"))]
     address: String,
+    #[validate(range(min = 1))]
     port: u16,
+    #[validate(
+        length(min = 1),
+        custom(function = "crate::validation::category_names")
+    )]
     categories: Vec<String>,
+    /// One of `categories`, the first one when left out
+    primary_category: Option<String>,
+    #[validate(custom(function = "crate::validation::not_blank"))]
     min_version: String,
+    #[validate(custom(function = "crate::validation::not_blank"))]
     max_version: String,
+    #[serde(flatten)]
+    #[validate(nested)]
+    attributes: ServerAttributes,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ServerFilter {
+    /// Version name, only servers whose version range covers it are listed
+    supports: Option<String>,
+    /// Comma separated tag slugs, servers need every one of them
+    tags: Option<String>,
+    /// Language code like `en`, regional variants like `en-GB` match too
+    language: Option<String>,
+    /// Two letter country code
+    country: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct Category {
     id: i32,
     name: String,
+    slug: String,
+    #[serde(serialize_with = "int_to_bool")]
+    primary: i32,
+}
+
+/// Uploaded image, the thumbnail is only made for screenshots.
+#[derive(Serialize, Deserialize, ToSchema, FromQueryResult)]
+pub struct ServerImage {
+    id: i32,
+    url: String,
+    thumbnail_url: Option<String>,
+    width: i32,
+    height: i32,
+}
+
+#[derive(ToSchema)]
+pub struct ImageUpload {
+    #[schema(value_type = String, format = Binary)]
+    #[allow(dead_code)]
+    file: Vec<u8>,
+}
+
+/// Approved tag of a server.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ServerTag {
+    name: String,
+    slug: String,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct Server {
     id: i32,
     address: String,
+    port: u16,
     name: String,
     min_version: String,
     max_version: String,
     #[serde(serialize_with = "int_to_bool")]
     is_premium: i32,
     user_id: i32,
+    /// Minecraft name of the owner, when they linked their account
+    owner_minecraft_name: Option<String>,
     description: String,
+    language: Option<String>,
+    country: Option<String>,
+    website: Option<String>,
+    discord_invite: Option<String>,
+    store_url: Option<String>,
     created_at: String,
     categories: Vec<Category>,
+    tags: Vec<ServerTag>,
+    /// 468x60 banner, may be animated
+    banner_url: Option<String>,
+    gallery: Vec<ServerImage>,
+}
+
+/// Review as listed on the page of a server.
+#[derive(Serialize, ToSchema, FromQueryResult)]
+pub struct ServerReview {
+    id: i32,
+    user_id: i32,
+    username: String,
+    /// Verified through the linked Microsoft account, empty when none is linked
+    minecraft_name: Option<String>,
+    stars: i32,
+    description: String,
+    created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Server still using a category or version that is being removed.
+#[derive(Serialize, ToSchema, FromQueryResult)]
+pub struct ServerRef {
+    pub id: i32,
+    pub name: String,
+}
+
+/// 409 listing the servers that keep a row from being deleted.
+pub(crate) fn in_use(message: &str, servers: Vec<ServerRef>) -> HttpResponse {
+    HttpResponse::Conflict().json(json!({
+        "code": 409,
+        "message": message,
+        "servers": servers,
+    }))
+}
+
+/// The server when `user` owns it, moderators can change any server.
+pub(crate) async fn owned_server(
+    db: &DatabaseConnection,
+    id: i32,
+    user: &AuthUser,
+) -> Result<servers::Model, AppError> {
+    let server = servers::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(ErrorNotFound("No such server exists"))?;
+
+    // Moderators can clean up what owners put there
+    if server.user_id != user.id && !user.permissions.contains(&Permission::Moderate) {
+        return Err(ErrorForbidden("You are not the owner of this server").into());
+    }
+
+    Ok(server)
+}
+
+/// Content of the `file` field, reading stops once it is over `max_bytes`.
+async fn read_file(mut payload: Multipart, max_bytes: usize) -> Result<Vec<u8>, AppError> {
+    while let Some(mut field) = payload.try_next().await.map_err(actix_web::Error::from)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(actix_web::Error::from)? {
+            if data.len() + chunk.len() > max_bytes {
+                return Err(ErrorPayloadTooLarge(format!(
+                    "Image can be at most {} KiB",
+                    max_bytes / 1024
+                ))
+                .into());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        return Ok(data);
+    }
+
+    Err(ErrorBadRequest("Missing the file field").into())
 }
 
 fn int_to_bool<S>(value: &i32, serializer: S) -> Result<S::Ok, S::Error>
@@ -55,14 +362,25 @@ pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
         config
             .service(
                 web::resource("/servers")
-                    .route(
-                        web::post()
-                            .to(add_server::add_server)
-                            .wrap(from_fn(auth_middleware)),
-                    )
+                    .post(add_server::add_server)
                     .get(list_servers::list_servers),
             )
             .service(web::resource("/servers/{id}").get(get_server::get_server))
+            .service(web::resource("/servers/{id}/players").get(players::players_history))
+            .service(web::resource("/servers/{id}/reviews").get(reviews::list_reviews))
+            .service(
+                web::resource("/servers/{id}/attributes").put(update_attributes::update_attributes),
+            )
+            .service(
+                web::resource("/servers/{id}/banner")
+                    .put(banner::upload_banner)
+                    .delete(banner::remove_banner),
+            )
+            .service(web::resource("/servers/{id}/gallery").post(gallery::add_screenshot))
+            .service(
+                web::resource("/servers/{id}/gallery/{image_id}")
+                    .delete(gallery::remove_screenshot),
+            )
             .service(web::resource("/servers/user/{id}").get(get_user_servers::get_user_servers));
     }
 }
@@ -0,0 +1,43 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+use crate::{
+    entities::servers,
+    error::AppError,
+    player_stats::{self, HistoryQuery},
+    Config,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/servers/{id}/players",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Players online per bucket, peak and uptime", body = PlayersHistory),
+        (status = 400, description = "Empty range, too many buckets or older than the bucket is kept"),
+        (status = 404, description = "Server does not exist or is hidden"),
+        (status = 500, description = "Database error"),
+    ),
+)]
+pub async fn players_history(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<i32>,
+    query: web::Query<HistoryQuery>,
+    config: web::Data<Config>,
+) -> Result<impl Responder, AppError> {
+    let range = query.range(&config.players_retention)?;
+    let server = servers::Entity::find_by_id(path.into_inner())
+        .filter(servers::Column::HiddenAt.is_null())
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or_else(|| ErrorNotFound("No such server exists"))?;
+
+    let history = player_stats::server_history(&db, server.id, range).await?;
+
+    Ok(HttpResponse::Ok().json(history))
+}
@@ -0,0 +1,56 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    entities::{reviews, servers, users},
+    error::AppError,
+};
+
+use super::ServerReview;
+
+#[utoipa::path(
+    get,
+    path = "/api/servers/{id}/reviews",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    responses(
+        (status = 200, description = "Visible reviews of the server, newest first", body = [ServerReview]),
+        (status = 404, description = "Server does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+)]
+pub async fn list_reviews(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let server = servers::Entity::find_by_id(path.into_inner())
+        .filter(servers::Column::HiddenAt.is_null())
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or_else(|| ErrorNotFound("No such server exists"))?;
+
+    let reviews = reviews::Entity::find()
+        .join(JoinType::InnerJoin, reviews::Relation::Users.def())
+        .filter(reviews::Column::ServerId.eq(server.id))
+        .filter(reviews::Column::HiddenAt.is_null())
+        .order_by_desc(reviews::Column::CreatedAt)
+        .select_only()
+        .column(reviews::Column::Id)
+        .column(reviews::Column::UserId)
+        .column(users::Column::Username)
+        .column(users::Column::MinecraftName)
+        .column(reviews::Column::Stars)
+        .column(reviews::Column::Description)
+        .column(reviews::Column::CreatedAt)
+        .into_model::<ServerReview>()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(reviews))
+}
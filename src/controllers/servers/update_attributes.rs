@@ -0,0 +1,55 @@
+use actix_web::{web, HttpResponse, Responder};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, IntoActiveModel, TransactionTrait};
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{owned_server, ServerAttributes};
+
+#[utoipa::path(
+    put,
+    path = "/api/servers/{id}/attributes",
+    tag = "Servers",
+    params(
+        ("id" = i32, Path, description = "Id of the server"),
+    ),
+    request_body(content = ServerAttributes, description = "Attributes and tags replacing the current ones", content_type = "application/json", example = json!({"language": "en", "country": "DE", "discord_invite": "https://discord.gg/craftlist", "tags": ["Cracked", "Cross-play"]})),
+    responses(
+        (status = 204, description = "Attributes changed"),
+        (status = 400, description = "Invalid tag, too many tags or pending tag suggestions"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Server belongs to someone else"),
+        (status = 404, description = "Server does not exist"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn update_attributes(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    path: web::Path<i32>,
+    data: ValidJson<ServerAttributes>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let server = owned_server(&db, path.into_inner(), &user).await?;
+    let id = server.id;
+    let mut model = server.into_active_model();
+    data.apply(&mut model);
+
+    let txn = db.begin().await?;
+    model.update(&txn).await?;
+    data.save_tags(&txn, id, user.id).await?;
+    txn.commit().await?;
+
+    events.publish(DomainEvent::new(Topic::Servers, id)).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
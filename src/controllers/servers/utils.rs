@@ -1,7 +1,7 @@
 use migration::{Alias, Expr};
-use sea_orm::{EntityTrait, QuerySelect, Select};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect, Select};
 
-use crate::entities::{categories, server_categories, servers, servers_info, versions};
+use crate::entities::{categories, server_categories, servers, servers_info, users, versions};
 
 pub fn get_server() -> Select<servers::Entity> {
     servers::Entity::find()
@@ -42,19 +42,36 @@ pub fn get_server() -> Select<servers::Entity> {
                 .to(categories::Column::Id)
                 .into(),
         )
+        .join(
+            sea_orm::JoinType::InnerJoin,
+            servers::Entity::belongs_to(users::Entity)
+                .from(servers::Column::UserId)
+                .to(users::Column::Id)
+                .into(),
+        )
+        .filter(servers::Column::HiddenAt.is_null())
         .group_by(servers_info::Column::Id)
         .group_by(servers::Column::Name)
         .group_by(servers_info::Column::Address)
+        .group_by(servers_info::Column::Port)
         .group_by(Expr::col((Alias::new("v1"), versions::Column::Name)))
         .group_by(Expr::col((Alias::new("v2"), versions::Column::Name)))
+        .group_by(users::Column::MinecraftName)
         .select_only()
         .column(servers::Column::Id)
         .column(servers::Column::Name)
         .column(servers::Column::Description)
         .column(servers::Column::UserId)
         .column(servers::Column::IsPremium)
+        .column(servers::Column::Language)
+        .column(servers::Column::Country)
+        .column(servers::Column::Website)
+        .column(servers::Column::DiscordInvite)
+        .column(servers::Column::StoreUrl)
         .column(servers::Column::CreatedAt)
         .column(servers_info::Column::Address)
+        .column(servers_info::Column::Port)
+        .column_as(users::Column::MinecraftName, "owner_minecraft_name")
         .column_as(
             Expr::col((Alias::new("v1"), versions::Column::Name)),
             "min_version",
@@ -64,8 +81,20 @@ pub fn get_server() -> Select<servers::Entity> {
             "max_version",
         )
         .expr_as(
-            Expr::cust("JSON_ARRAYAGG(JSON_OBJECT('id', categories.id, 'name', categories.name))"),
+            Expr::cust("JSON_ARRAYAGG(JSON_OBJECT('id', categories.id, 'name', categories.name, 'slug', categories.slug, 'primary', server_categories.is_primary))"),
             "categories",
         )
+        .expr_as(
+            Expr::cust("COALESCE((SELECT JSON_ARRAYAGG(JSON_OBJECT('name', tags.name, 'slug', tags.slug)) FROM server_tags JOIN tags ON tags.id = server_tags.tag_id WHERE server_tags.server_id = servers.id AND tags.approved_at IS NOT NULL), JSON_ARRAY())"),
+            "tags",
+        )
+        .expr_as(
+            Expr::cust("(SELECT server_images.url FROM server_images WHERE server_images.server_id = servers.id AND server_images.kind = 'banner' ORDER BY server_images.id DESC LIMIT 1)"),
+            "banner_url",
+        )
+        .expr_as(
+            Expr::cust("COALESCE((SELECT JSON_ARRAYAGG(JSON_OBJECT('id', server_images.id, 'url', server_images.url, 'thumbnail_url', server_images.thumbnail_url, 'width', server_images.width, 'height', server_images.height)) FROM server_images WHERE server_images.server_id = servers.id AND server_images.kind = 'screenshot'), JSON_ARRAY())"),
+            "gallery",
+        )
         .to_owned()
 }
@@ -0,0 +1,9 @@
+use actix_web::web::{self, ServiceConfig};
+
+pub mod total_players;
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(web::resource("/stats/players").get(total_players::total_players));
+    }
+}
@@ -0,0 +1,31 @@
+use actix_web::{web, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::{
+    error::AppError,
+    player_stats::{self, HistoryQuery},
+    Config,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/stats/players",
+    tag = "Stats",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Players online across all listed servers per bucket", body = TotalPlayers),
+        (status = 400, description = "Empty range, too many buckets or older than the bucket is kept"),
+        (status = 500, description = "Database error"),
+    ),
+)]
+pub async fn total_players(
+    db: web::Data<Arc<DatabaseConnection>>,
+    query: web::Query<HistoryQuery>,
+    config: web::Data<Config>,
+) -> Result<impl Responder, AppError> {
+    let range = query.range(&config.players_retention)?;
+    let history = player_stats::total_history(&db, range).await?;
+
+    Ok(HttpResponse::Ok().json(history))
+}
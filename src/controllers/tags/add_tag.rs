@@ -0,0 +1,67 @@
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    controllers::categories::slugify,
+    entities::tags,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{check_unique, Tag};
+
+#[utoipa::path(
+    post,
+    path = "/api/tags",
+    tag = "Tags",
+    request_body(content = Tag, description = "Tag Data", content_type = "application/json", example = json!({"name": "Cross-play"})),
+    responses(
+        (status = 201, description = "Created an approved tag", body = None, example = json!({"message": "Success", "id": 5})),
+        (status = 400, description = "No slug can be derived from the name"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 409, description = "Tag or slug already exists"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn add_tag(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<Tag>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let slug = data.slug.clone().unwrap_or_else(|| slugify(&data.name));
+    if slug.len() > 32 || crate::validation::slug(&slug).is_err() {
+        return Err(ErrorBadRequest("Name can not be turned into a slug, set one").into());
+    }
+
+    check_unique(&db, None, data.name.trim(), &slug).await?;
+
+    let model = tags::ActiveModel {
+        name: Set(data.name.trim().to_owned()),
+        slug: Set(slug),
+        approved_at: Set(Some(Utc::now().naive_utc())),
+        created_by: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(db.get_ref().as_ref())
+    .await?;
+
+    events
+        .publish(DomainEvent::new(Topic::Tags, model.id))
+        .await;
+
+    Ok(HttpResponse::Created().json(json!({"message": "Success", "id": model.id})))
+}
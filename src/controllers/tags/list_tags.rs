@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse, Responder};
+use migration::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Select,
+};
+use std::sync::Arc;
+
+use crate::{entities::tags, error::AppError, permissions::Permission, utils::AuthUser};
+
+use super::TagEntry;
+
+fn tag_entries() -> Select<tags::Entity> {
+    tags::Entity::find()
+        .select_only()
+        .column(tags::Column::Id)
+        .column(tags::Column::Name)
+        .column(tags::Column::Slug)
+        .column(tags::Column::ApprovedAt)
+        .column(tags::Column::CreatedBy)
+        .order_by_asc(tags::Column::Name)
+        .expr_as(
+            Expr::cust("(SELECT COUNT(*) FROM server_tags JOIN servers ON servers.id = server_tags.server_id WHERE server_tags.tag_id = tags.id AND servers.hidden_at IS NULL)"),
+            "server_count",
+        )
+        .to_owned()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    tag = "Tags",
+    responses(
+        (status = 200, description = "Approved tags by name", body = Vec<TagEntry>),
+        (status = 500, description = "Server error"),
+    ),
+)]
+pub async fn list_tags(db: web::Data<Arc<DatabaseConnection>>) -> Result<impl Responder, AppError> {
+    let tags = tag_entries()
+        .filter(tags::Column::ApprovedAt.is_not_null())
+        .into_model::<TagEntry>()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags/pending",
+    tag = "Tags",
+    responses(
+        (status = 200, description = "Tags suggested by owners and waiting for approval", body = Vec<TagEntry>),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn list_pending_tags(
+    db: web::Data<Arc<DatabaseConnection>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let tags = tag_entries()
+        .filter(tags::Column::ApprovedAt.is_null())
+        .into_model::<TagEntry>()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
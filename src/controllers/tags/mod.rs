@@ -0,0 +1,91 @@
+use actix_web::{
+    error::ErrorConflict,
+    web::{self, ServiceConfig},
+};
+use chrono::NaiveDateTime;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{entities::tags, error::AppError};
+
+pub mod add_tag;
+pub mod list_tags;
+pub mod remove_tag;
+pub mod update_tag;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct Tag {
+    #[validate(length(max = 32), custom(function = "crate::validation::not_blank"))]
+    name: String,
+    /// Derived from the name when left out
+    #[validate(length(max = 32), custom(function = "crate::validation::slug"))]
+    slug: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateTag {
+    #[validate(length(max = 32), custom(function = "crate::validation::not_blank"))]
+    name: String,
+    /// Kept when left out
+    #[validate(length(max = 32), custom(function = "crate::validation::slug"))]
+    slug: Option<String>,
+    /// Unapproved tags stay on their servers but are not shown or usable as filters
+    approved: bool,
+}
+
+#[derive(Serialize, ToSchema, FromQueryResult)]
+pub struct TagEntry {
+    id: i32,
+    name: String,
+    slug: String,
+    /// Not set for suggestions waiting for approval
+    approved_at: Option<NaiveDateTime>,
+    /// Owner who suggested the tag
+    created_by: Option<i32>,
+    /// Visible servers with the tag
+    server_count: i64,
+}
+
+/// Name and slug have to be unique, `id` is left out when updating.
+async fn check_unique(
+    db: &DatabaseConnection,
+    id: Option<i32>,
+    name: &str,
+    slug: &str,
+) -> Result<(), AppError> {
+    let mut query = tags::Entity::find().filter(
+        Condition::any()
+            .add(tags::Column::Name.eq(name))
+            .add(tags::Column::Slug.eq(slug)),
+    );
+    if let Some(id) = id {
+        query = query.filter(tags::Column::Id.ne(id));
+    }
+
+    match query.one(db).await? {
+        Some(v) if v.slug == slug => Err(ErrorConflict("Slug is already used").into()),
+        Some(_) => Err(ErrorConflict("Tag already exists").into()),
+        None => Ok(()),
+    }
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(
+                web::resource("/tags")
+                    .get(list_tags::list_tags)
+                    .post(add_tag::add_tag),
+            )
+            .service(web::resource("/tags/pending").get(list_tags::list_pending_tags))
+            .service(
+                web::resource("/tags/{id}")
+                    .put(update_tag::update_tag)
+                    .delete(remove_tag::remove_tag),
+            );
+    }
+}
@@ -0,0 +1,51 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::sync::Arc;
+
+use crate::{
+    entities::tags,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+};
+
+#[utoipa::path(
+    delete,
+    path = "/api/tags/{id}",
+    tag = "Tags",
+    params(
+        ("id" = i32, Path, description = "Id of the tag"),
+    ),
+    responses(
+        (status = 204, description = "Tag removed from every server and deleted"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 404, description = "Tag does not exist"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn remove_tag(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    path: web::Path<i32>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let id = path.into_inner();
+    let res = tags::Entity::delete_by_id(id)
+        .exec(db.get_ref().as_ref())
+        .await?;
+
+    if res.rows_affected == 0 {
+        return Err(ErrorNotFound("No such tag exists").into());
+    }
+
+    events.publish(DomainEvent::new(Topic::Tags, id)).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,72 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    entities::tags,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{check_unique, UpdateTag};
+
+#[utoipa::path(
+    put,
+    path = "/api/tags/{id}",
+    tag = "Tags",
+    params(
+        ("id" = i32, Path, description = "Id of the tag"),
+    ),
+    request_body(content = UpdateTag, description = "Tag Data", content_type = "application/json", example = json!({"name": "Cross-play", "slug": "cross-play", "approved": true})),
+    responses(
+        (status = 200, description = "Successfully updated tag", body = None, example = json!({"message": "Success"})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 404, description = "Tag does not exist"),
+        (status = 409, description = "Name or slug already used by another tag"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Server error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn update_tag(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    path: web::Path<i32>,
+    data: ValidJson<UpdateTag>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
+    let tag = tags::Entity::find_by_id(path.into_inner())
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or(ErrorNotFound("No such tag exists"))?;
+
+    let slug = data.slug.clone().unwrap_or_else(|| tag.slug.clone());
+    check_unique(&db, Some(tag.id), data.name.trim(), &slug).await?;
+
+    let approved_at = match data.approved {
+        true => tag.approved_at.or(Some(Utc::now().naive_utc())),
+        false => None,
+    };
+
+    let mut model = tag.into_active_model();
+    model.name = Set(data.name.trim().to_owned());
+    model.slug = Set(slug);
+    model.approved_at = Set(approved_at);
+    let model = model.update(db.get_ref().as_ref()).await?;
+
+    events
+        .publish(DomainEvent::new(Topic::Tags, model.id))
+        .await;
+
+    Ok(HttpResponse::Ok().json(json!({"message": "Success"})))
+}
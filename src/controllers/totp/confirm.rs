@@ -0,0 +1,66 @@
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{entities::user_totp, error::AppError, totp, utils::AuthUser, validation::ValidJson};
+
+use super::{current_user, RecoveryCodes, TotpCode};
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp/confirm",
+    tag = "Totp",
+    request_body(content = TotpCode, description = "First code generated from the new secret", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = RecoveryCodes),
+        (status = 400, description = "Invalid code or no enrollment started"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn confirm(
+    db: web::Data<Arc<DatabaseConnection>>,
+    data: ValidJson<TotpCode>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    let Some(pending) = user_totp::Entity::find_by_id(user.id)
+        .filter(user_totp::Column::EnabledAt.is_null())
+        .one(db.get_ref().as_ref())
+        .await?
+    else {
+        return Err(ErrorBadRequest("Start the enrollment first").into());
+    };
+
+    let Some(step) = totp::verify_code(&pending, data.code.trim())? else {
+        return Err(ErrorBadRequest("Invalid code").into());
+    };
+
+    let txn = db.begin().await?;
+
+    if !totp::mark_used(&txn, user.id, step).await? {
+        return Err(ErrorBadRequest("Invalid code").into());
+    }
+
+    user_totp::Entity::update_many()
+        .col_expr(
+            user_totp::Column::EnabledAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(user_totp::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    let recovery_codes = totp::regenerate_recovery_codes(&txn, user.id).await?;
+
+    txn.commit().await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes }))
+}
@@ -0,0 +1,68 @@
+use actix_web::{
+    error::{ErrorBadRequest, ErrorForbidden},
+    web, HttpResponse, Responder,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait};
+use std::sync::Arc;
+
+use crate::{
+    entities::{recovery_codes, user_totp},
+    error::AppError,
+    permissions, totp,
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{current_user, TotpCode};
+
+#[utoipa::path(
+    delete,
+    path = "/auth/totp",
+    tag = "Totp",
+    request_body(content = TotpCode, description = "Current code", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Invalid code or two-factor authentication is not enabled"),
+        (status = 403, description = "Users with a role have to keep two-factor authentication"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn disable(
+    db: web::Data<Arc<DatabaseConnection>>,
+    data: ValidJson<TotpCode>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    if !permissions::find_roles(db.get_ref().as_ref(), user.id)
+        .await?
+        .is_empty()
+    {
+        return Err(ErrorForbidden("Staff have to keep two-factor authentication").into());
+    }
+
+    let Some(model) = totp::find_enabled(db.get_ref().as_ref(), user.id).await? else {
+        return Err(ErrorBadRequest("Two-factor authentication is not enabled").into());
+    };
+
+    if !totp::verify_second_factor(db.get_ref().as_ref(), &model, &data.code).await? {
+        return Err(ErrorBadRequest("Invalid code").into());
+    }
+
+    let txn = db.begin().await?;
+
+    recovery_codes::Entity::delete_many()
+        .filter(recovery_codes::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    user_totp::Entity::delete_by_id(user.id).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
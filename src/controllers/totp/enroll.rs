@@ -0,0 +1,60 @@
+use actix_web::{error::ErrorConflict, web, HttpResponse, Responder};
+use sea_orm::{sea_query::OnConflict, DatabaseConnection, EntityTrait, Set};
+use std::sync::Arc;
+
+use crate::{entities::user_totp, error::AppError, totp, utils::AuthUser};
+
+use super::{current_user, TotpEnrollment};
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp",
+    tag = "Totp",
+    responses(
+        (status = 200, description = "New secret, enabled once a code is confirmed", body = TotpEnrollment),
+        (status = 409, description = "Two-factor authentication is already enabled"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn enroll(
+    db: web::Data<Arc<DatabaseConnection>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    if totp::find_enabled(db.get_ref().as_ref(), user.id)
+        .await?
+        .is_some()
+    {
+        return Err(ErrorConflict("Two-factor authentication is already enabled").into());
+    }
+
+    // Starting over replaces a secret that was never confirmed
+    let secret = totp::generate_secret();
+    user_totp::Entity::insert(user_totp::ActiveModel {
+        user_id: Set(user.id),
+        secret: Set(secret.clone()),
+        enabled_at: Set(None),
+        last_used_step: Set(None),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(user_totp::Column::UserId)
+            .update_columns([
+                user_totp::Column::Secret,
+                user_totp::Column::EnabledAt,
+                user_totp::Column::LastUsedStep,
+            ])
+            .to_owned(),
+    )
+    .exec(db.get_ref().as_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(TotpEnrollment {
+        otpauth_uri: totp::otpauth_uri(&secret, &user.email)?,
+        secret,
+    }))
+}
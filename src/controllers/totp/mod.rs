@@ -0,0 +1,52 @@
+use actix_web::web::{self, ServiceConfig};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{entities::users, error::AppError, utils::AuthUser};
+
+pub mod confirm;
+pub mod disable;
+pub mod enroll;
+pub mod recovery_codes;
+
+#[derive(Serialize, ToSchema)]
+pub struct TotpEnrollment {
+    /// Base32 secret for apps that can not scan the uri
+    secret: String,
+    otpauth_uri: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TotpCode {
+    /// Code from the authenticator app, `disable` and `recovery-codes` take a recovery code too
+    #[validate(length(min = 6, max = 32))]
+    code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodes {
+    /// Each one works once in place of a code, they are not shown again
+    recovery_codes: Vec<String>,
+}
+
+async fn current_user(db: &DatabaseConnection, user: &AuthUser) -> Result<users::Model, AppError> {
+    users::Entity::find_by_id(user.id)
+        .one(db)
+        .await?
+        .ok_or(actix_web::error::ErrorUnauthorized("Invalid token").into())
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(
+                web::resource("/totp")
+                    .post(enroll::enroll)
+                    .delete(disable::disable),
+            )
+            .service(web::resource("/totp/confirm").post(confirm::confirm))
+            .service(web::resource("/totp/recovery-codes").post(recovery_codes::recovery_codes));
+    }
+}
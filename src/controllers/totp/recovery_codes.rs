@@ -0,0 +1,42 @@
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::{error::AppError, totp, utils::AuthUser, validation::ValidJson};
+
+use super::{current_user, RecoveryCodes, TotpCode};
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp/recovery-codes",
+    tag = "Totp",
+    request_body(content = TotpCode, description = "Current code", content_type = "application/json"),
+    responses(
+        (status = 200, description = "New recovery codes, the old ones stop working", body = RecoveryCodes),
+        (status = 400, description = "Invalid code or two-factor authentication is not enabled"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn recovery_codes(
+    db: web::Data<Arc<DatabaseConnection>>,
+    data: ValidJson<TotpCode>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    let Some(model) = totp::find_enabled(db.get_ref().as_ref(), user.id).await? else {
+        return Err(ErrorBadRequest("Two-factor authentication is not enabled").into());
+    };
+
+    if !totp::verify_second_factor(db.get_ref().as_ref(), &model, &data.code).await? {
+        return Err(ErrorBadRequest("Invalid code").into());
+    }
+
+    let recovery_codes = totp::regenerate_recovery_codes(db.get_ref().as_ref(), user.id).await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes }))
+}
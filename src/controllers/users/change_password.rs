@@ -0,0 +1,51 @@
+use actix_web::{error::ErrorBadRequest, web, HttpResponse, Responder};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, IntoActiveModel, Set};
+use std::sync::Arc;
+
+use crate::{
+    controllers::auth::verify_password,
+    error::AppError,
+    utils::{hash_password, revoke_user_refresh_tokens, AuthUser},
+    validation::ValidJson,
+};
+
+use super::{current_user, ChangePassword};
+
+#[utoipa::path(
+    put,
+    path = "/api/users/me/password",
+    tag = "Users",
+    request_body(content = ChangePassword, description = "Current and new password", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Password changed, every session is logged out"),
+        (status = 400, description = "Current password is wrong"),
+        (status = 401, description = "Invalid token"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn change_password(
+    db: web::Data<Arc<DatabaseConnection>>,
+    data: ValidJson<ChangePassword>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    if !verify_password(&data.current_password, &user.password) {
+        return Err(ErrorBadRequest("Current password is wrong").into());
+    }
+
+    let password_hash = hash_password(&data.new_password)?;
+
+    let user_id = user.id;
+    let mut model = user.into_active_model();
+    model.password = Set(password_hash);
+    model.update(db.get_ref().as_ref()).await?;
+
+    revoke_user_refresh_tokens(&db, user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,145 @@
+use actix_web::{
+    error::{ErrorBadRequest, ErrorConflict},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+use std::sync::Arc;
+
+use crate::{
+    controllers::auth::{removal_cookie, verify_password},
+    entities::{
+        ads, auth, players_graph, reviews, sea_orm_active_enums::Role, server_categories,
+        server_images, servers, servers_info, user_roles, users,
+    },
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions,
+    storage::{self, Storage},
+    utils::AuthUser,
+    validation::ValidJson,
+};
+
+use super::{current_user, DeleteAccount};
+
+#[utoipa::path(
+    delete,
+    path = "/api/users/me",
+    tag = "Users",
+    request_body(content = DeleteAccount, description = "Password of the account", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Account deleted with its servers, reviews and ads, the refresh_token cookie is removed"),
+        (status = 400, description = "Password is wrong"),
+        (status = 401, description = "Invalid token"),
+        (status = 409, description = "The last admin can not delete their account"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn delete_account(
+    db: web::Data<Arc<DatabaseConnection>>,
+    events: web::Data<Arc<EventBus>>,
+    storage: web::Data<Arc<dyn Storage>>,
+    data: ValidJson<DeleteAccount>,
+    user: AuthUser,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    if !verify_password(&data.password, &user.password) {
+        return Err(ErrorBadRequest("Password is wrong").into());
+    }
+
+    let roles = permissions::find_roles(db.get_ref().as_ref(), user.id).await?;
+    if roles.contains(&Role::Admin) {
+        let admins = user_roles::Entity::find()
+            .filter(user_roles::Column::Role.eq(Role::Admin))
+            .count(db.get_ref().as_ref())
+            .await?;
+
+        if admins <= 1 {
+            return Err(ErrorConflict("Grant the admin role to someone else first").into());
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    let server_ids: Vec<i32> = servers::Entity::find()
+        .select_only()
+        .column(servers::Column::Id)
+        .filter(servers::Column::UserId.eq(user.id))
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    players_graph::Entity::delete_many()
+        .filter(players_graph::Column::ServerId.is_in(server_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    server_categories::Entity::delete_many()
+        .filter(server_categories::Column::ServerId.is_in(server_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    // Rows go with the servers, files once the deletion is committed
+    let files: Vec<String> = server_images::Entity::find()
+        .filter(server_images::Column::ServerId.is_in(server_ids.clone()))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .flat_map(|v| [Some(v.storage_key), v.thumbnail_key])
+        .flatten()
+        .collect();
+
+    servers_info::Entity::delete_many()
+        .filter(servers_info::Column::ServerId.is_in(server_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    // Reviews written by the user and the ones left on their servers
+    reviews::Entity::delete_many()
+        .filter(
+            Condition::any()
+                .add(reviews::Column::UserId.eq(user.id))
+                .add(reviews::Column::ServerId.is_in(server_ids.clone())),
+        )
+        .exec(&txn)
+        .await?;
+
+    servers::Entity::delete_many()
+        .filter(servers::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    ads::Entity::delete_many()
+        .filter(ads::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    auth::Entity::delete_many()
+        .filter(auth::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await?;
+
+    // Tokens, two-factor secrets and roles go with the user through cascades
+    users::Entity::delete_by_id(user.id).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    storage::discard(Arc::clone(storage.get_ref()), files);
+    for id in server_ids {
+        events.publish(DomainEvent::new(Topic::Servers, id)).await;
+    }
+
+    log::info!("User {} deleted their account", user.id);
+
+    Ok(HttpResponse::NoContent()
+        .cookie(removal_cookie(&req))
+        .finish())
+}
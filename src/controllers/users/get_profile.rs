@@ -0,0 +1,44 @@
+use actix_web::{web, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+use crate::{error::AppError, permissions, totp, utils::AuthUser};
+
+use super::{current_user, Profile};
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me",
+    tag = "Users",
+    responses(
+        (status = 200, description = "Account of the logged in user", body = Profile),
+        (status = 401, description = "Invalid token"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn get_profile(
+    db: web::Data<Arc<DatabaseConnection>>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+    let roles = permissions::find_roles(db.get_ref().as_ref(), user.id).await?;
+    let totp_enabled = totp::find_enabled(db.get_ref().as_ref(), user.id)
+        .await?
+        .is_some();
+
+    Ok(HttpResponse::Ok().json(Profile {
+        id: user.id,
+        email: user.email,
+        email_verified_at: user.email_verified_at,
+        pending_email: user.pending_email,
+        username: user.username,
+        minecraft_uuid: user.minecraft_uuid,
+        minecraft_name: user.minecraft_name,
+        roles,
+        totp_enabled,
+        created_at: user.created_at,
+    }))
+}
@@ -0,0 +1,70 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait,
+};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::{
+    controllers::servers::{utils, Server},
+    entities::{reviews, servers, users},
+    error::AppError,
+};
+
+use super::{PublicProfile, PublicReview};
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}",
+    tag = "Users",
+    params(
+        ("id" = i32, Path, description = "Id of the user")
+    ),
+    responses(
+        (status = 200, description = "Public profile with the visible servers and reviews of the user", body = PublicProfile),
+        (status = 404, description = "User does not exist"),
+        (status = 500, description = "Database error"),
+    ),
+)]
+pub async fn get_user(
+    db: web::Data<Arc<DatabaseConnection>>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let user = users::Entity::find_by_id(path.into_inner())
+        .one(db.get_ref().as_ref())
+        .await?
+        .ok_or(ErrorNotFound("No such user exists"))?;
+
+    let servers = utils::get_server()
+        .filter(servers::Column::UserId.eq(user.id))
+        .into_json()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    let reviews = reviews::Entity::find()
+        .join(JoinType::InnerJoin, reviews::Relation::Servers.def())
+        .filter(reviews::Column::UserId.eq(user.id))
+        .filter(reviews::Column::HiddenAt.is_null())
+        .filter(servers::Column::HiddenAt.is_null())
+        .order_by_desc(reviews::Column::CreatedAt)
+        .select_only()
+        .column(reviews::Column::Id)
+        .column(reviews::Column::ServerId)
+        .column_as(servers::Column::Name, "server_name")
+        .column(reviews::Column::Stars)
+        .column(reviews::Column::Description)
+        .column(reviews::Column::CreatedAt)
+        .into_model::<PublicReview>()
+        .all(db.get_ref().as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(PublicProfile {
+        id: user.id,
+        username: user.username,
+        minecraft_name: user.minecraft_name,
+        created_at: user.created_at,
+        servers: serde_json::from_value::<Vec<Server>>(Value::Array(servers))?,
+        reviews,
+    }))
+}
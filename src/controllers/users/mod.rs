@@ -0,0 +1,103 @@
+use actix_web::web::{self, ServiceConfig};
+use chrono::NaiveDateTime;
+use sea_orm::{DatabaseConnection, EntityTrait, FromQueryResult};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    controllers::servers::Server,
+    entities::{sea_orm_active_enums::Role, users},
+    error::AppError,
+    utils::AuthUser,
+};
+
+pub mod change_password;
+pub mod delete_account;
+pub mod get_profile;
+pub mod get_user;
+pub mod update_profile;
+
+/// Account of the logged in user.
+#[derive(Serialize, ToSchema)]
+pub struct Profile {
+    id: i32,
+    email: String,
+    email_verified_at: Option<NaiveDateTime>,
+    /// Requested new email, `email` stays in use until it is confirmed
+    pending_email: Option<String>,
+    username: String,
+    minecraft_uuid: Option<String>,
+    minecraft_name: Option<String>,
+    roles: Vec<Role>,
+    totp_enabled: bool,
+    created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateProfile {
+    #[validate(custom(function = "crate::validation::username"))]
+    username: Option<String>,
+    /// A new address is only used once confirmed through the link mailed to it
+    #[validate(email(message = "Must be a valid email address"), length(max = 255))]
+    email: Option<String>,
+    /// Required to change the email
+    #[validate(length(min = 1, max = 128))]
+    current_password: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ChangePassword {
+    #[validate(length(min = 1, max = 128))]
+    current_password: String,
+    #[validate(custom(function = "crate::validation::password"))]
+    new_password: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteAccount {
+    #[validate(length(min = 1, max = 128))]
+    password: String,
+}
+
+#[derive(Serialize, ToSchema, FromQueryResult)]
+pub struct PublicReview {
+    id: i32,
+    server_id: i32,
+    server_name: String,
+    stars: i32,
+    description: String,
+    created_at: Option<NaiveDateTime>,
+}
+
+/// What anyone can see of an account.
+#[derive(Serialize, ToSchema)]
+pub struct PublicProfile {
+    id: i32,
+    username: String,
+    minecraft_name: Option<String>,
+    created_at: Option<NaiveDateTime>,
+    servers: Vec<Server>,
+    reviews: Vec<PublicReview>,
+}
+
+async fn current_user(db: &DatabaseConnection, user: &AuthUser) -> Result<users::Model, AppError> {
+    users::Entity::find_by_id(user.id)
+        .one(db)
+        .await?
+        .ok_or(actix_web::error::ErrorUnauthorized("Invalid token").into())
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config
+            .service(
+                web::resource("/users/me")
+                    .get(get_profile::get_profile)
+                    .patch(update_profile::update_profile)
+                    .delete(delete_account::delete_account),
+            )
+            .service(web::resource("/users/me/password").put(change_password::change_password))
+            .service(web::resource("/users/{id}").get(get_user::get_user));
+    }
+}
@@ -0,0 +1,110 @@
+use actix_web::{
+    error::{ErrorBadRequest, ErrorConflict},
+    web, HttpResponse, Responder,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, Set,
+};
+use std::sync::Arc;
+
+use crate::{
+    controllers::auth::{send_email_change_mails, verify_password},
+    entities::users,
+    error::AppError,
+    mailer::Mailer,
+    utils::AuthUser,
+    validation::ValidJson,
+    Config,
+};
+
+use super::{current_user, UpdateProfile};
+
+#[utoipa::path(
+    patch,
+    path = "/api/users/me",
+    tag = "Users",
+    request_body(content = UpdateProfile, description = "Fields to change, the others are kept", content_type = "application/json", example = json!({"email": "steve@example.com", "current_password": "password1"})),
+    responses(
+        (status = 204, description = "Profile updated, a new email is confirmed through a mail sent to it and only used after that"),
+        (status = 400, description = "Missing or wrong current password for an email change"),
+        (status = 401, description = "Invalid token"),
+        (status = 409, description = "Email or username already taken"),
+        (status = 422, description = "Invalid fields, listed in errors"),
+        (status = 500, description = "Database error"),
+    ),
+    security(
+        ("Authorization" = [])
+    )
+)]
+pub async fn update_profile(
+    db: web::Data<Arc<DatabaseConnection>>,
+    config: web::Data<Config>,
+    mailer: web::Data<Arc<dyn Mailer>>,
+    data: ValidJson<UpdateProfile>,
+    user: AuthUser,
+) -> Result<impl Responder, AppError> {
+    let user = current_user(&db, &user).await?;
+
+    let username = data.username.clone().filter(|v| *v != user.username);
+    let email = data
+        .email
+        .clone()
+        .filter(|v| !v.eq_ignore_ascii_case(&user.email));
+
+    if username.is_none() && email.is_none() {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+
+    // A stolen access token alone must not be enough to take over the account
+    if email.is_some() {
+        let password = data.current_password.as_deref().unwrap_or_default();
+        if !verify_password(password, &user.password) {
+            return Err(ErrorBadRequest("Current password is wrong").into());
+        }
+    }
+
+    let mut taken = Condition::any();
+    if let Some(username) = &username {
+        taken = taken.add(users::Column::Username.eq(username.clone()));
+    }
+    if let Some(email) = &email {
+        taken = taken.add(users::Column::Email.eq(email.clone()));
+    }
+
+    let existing = users::Entity::find()
+        .filter(taken)
+        .filter(users::Column::Id.ne(user.id))
+        .one(db.get_ref().as_ref())
+        .await?;
+
+    match (existing, &email) {
+        (Some(v), Some(email)) if v.email.eq_ignore_ascii_case(email) => {
+            return Err(ErrorConflict("Email is already registered").into())
+        }
+        (Some(_), _) => return Err(ErrorConflict("Username is already taken").into()),
+        (None, _) => {}
+    }
+
+    let mut model = user.clone().into_active_model();
+    if let Some(username) = username {
+        model.username = Set(username);
+    }
+    // The current email keeps working until the new one is confirmed
+    if let Some(email) = &email {
+        model.pending_email = Set(Some(email.clone()));
+    }
+
+    let updated = model.update(db.get_ref().as_ref()).await?;
+
+    if let Some(email) = &email {
+        // Notices go to the address the account had so far, under the name it had so far
+        let user = users::Model {
+            username: updated.username,
+            ..user
+        };
+        send_email_change_mails(&db, &config, mailer.get_ref().clone(), &user, email).await?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,9 +1,21 @@
 use actix_web::{error::ErrorConflict, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QuerySelect,
+    TransactionTrait,
+};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::versions, error::AppError};
+use crate::{
+    controllers::servers::in_use,
+    entities::versions,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+    version_sync::{reorder, INVERTED_RANGES},
+};
 
 use super::Version;
 
@@ -11,13 +23,13 @@ use super::Version;
     post,
     path = "/api/versions",
     tag = "Versions",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
-    request_body(content = Version, description = "Version Data", content_type = "application/json", example = json!({"name": "1.8", "protocol": 47})),
+    request_body(content = Version, description = "Version Data", content_type = "application/json", example = json!({"name": "1.8", "protocol": 47, "released_at": "2014-09-02T08:39:00"})),
     responses(
         (status = 201, description = "Created new version", body = None, example = json!({"message": "Success", "id": 3})),
-        (status = 409, description = "Version already exists", body = None, example = json!({"message": "Version already exists"})),
+        (status = 409, description = "Version already exists, or its release date would give servers a minimum version newer than their maximum", body = None, example = json!({"message": "Version already exists"})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -26,8 +38,12 @@ use super::Version;
 )]
 pub async fn add_version(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<Version>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<Version>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
     let versions: Vec<String> = versions::Entity::find()
         .all(db.get_ref().as_ref())
         .await?
@@ -39,14 +55,38 @@ pub async fn add_version(
         return Err(ErrorConflict("Version already exists").into());
     }
 
+    let txn = db.begin().await?;
+
+    let newest: Option<i32> = versions::Entity::find()
+        .select_only()
+        .column_as(versions::Column::Ordinal.max(), "ordinal")
+        .into_tuple()
+        .one(&txn)
+        .await?
+        .flatten();
+
     let model = versions::ActiveModel {
         name: Set(data.name.clone()),
         protocol: Set(data.protocol),
+        ordinal: Set(newest.unwrap_or(0) + 1),
+        released_at: Set(data.released_at),
         ..Default::default()
     };
 
-    let model_i = model.insert(db.get_ref().as_ref()).await?;
+    let model_i = model.insert(&txn).await?;
+    if data.released_at.is_some() {
+        let inverted = reorder(&txn).await?;
+        if !inverted.is_empty() {
+            txn.rollback().await?;
+            return Ok(in_use(INVERTED_RANGES, inverted));
+        }
+    }
+
+    txn.commit().await?;
     let last_id = model_i.id;
+    events
+        .publish(DomainEvent::new(Topic::Versions, last_id))
+        .await;
 
     Ok(HttpResponse::Created().json(json!({"message": "Success", "id": last_id})))
 }
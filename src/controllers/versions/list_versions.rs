@@ -1,5 +1,5 @@
 use actix_web::{web, HttpResponse, Responder};
-use sea_orm::{DatabaseConnection, EntityTrait};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
 use serde_json::json;
 use std::sync::Arc;
 
@@ -10,14 +10,18 @@ use crate::{entities::versions, error::AppError};
     path = "/api/versions",
     tag = "Versions",
     responses(
-        (status = 200, description = "List of versions", body = Vec<crate::entities::versions::Model>, example = json!([{"id": 1, "name": "1.7", "protocol": 3}, {"id": 3, "name": "1.8", "protocol": 47}])),
+        (status = 200, description = "List of versions", body = Vec<crate::entities::versions::Model>, example = json!([{"id": 1, "name": "1.7", "protocol": 3, "ordinal": 1, "release_type": "release", "released_at": "2013-10-22T15:04:05", "archived_at": null}, {"id": 3, "name": "1.8", "protocol": 47, "ordinal": 2, "release_type": null, "released_at": null, "archived_at": null}])),
         (status = 500, description = "Server error"),
     )
 )]
 pub async fn list_versions(
     db: web::Data<Arc<DatabaseConnection>>,
 ) -> Result<impl Responder, AppError> {
-    let versions = versions::Entity::find().all(db.get_ref().as_ref()).await?;
+    let versions = versions::Entity::find()
+        .filter(versions::Column::ArchivedAt.is_null())
+        .order_by_asc(versions::Column::Ordinal)
+        .all(db.get_ref().as_ref())
+        .await?;
 
     Ok(HttpResponse::Ok().json(json! {versions}))
 }
@@ -1,53 +1,59 @@
 use actix_web::web::{self, ServiceConfig};
-use actix_web_lab::middleware::from_fn;
+use chrono::NaiveDateTime;
 use serde::Deserialize;
 use utoipa::ToSchema;
-
-use crate::utils::admin_auth_middleware;
+use validator::Validate;
 
 pub mod add_version;
 pub mod list_versions;
 pub mod remove_version;
 pub mod update_version;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct Version {
+    #[validate(length(max = 32), custom(function = "crate::validation::not_blank"))]
     name: String,
+    #[validate(range(min = 0))]
     protocol: i32,
+    /// Places the version among the others, it becomes the newest when left out
+    released_at: Option<NaiveDateTime>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateVersion {
+    #[validate(range(min = 1))]
     id: i32,
+    #[validate(length(max = 32), custom(function = "crate::validation::not_blank"))]
     name: Option<String>,
+    #[validate(range(min = 0))]
     protocol: Option<i32>,
+    /// Moves the version to its place among the others by release date
+    released_at: Option<NaiveDateTime>,
+    /// Archived versions are left out of listings and can not be picked for servers
+    archived: Option<bool>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct DeleteVersion {
+    #[validate(range(min = 1))]
     id: Option<i32>,
+    #[validate(length(max = 32))]
     name: String,
+    /// Servers using the version as minimum or maximum are moved to this one first
+    #[validate(range(min = 1))]
+    reassign_to: Option<i32>,
+    /// Keep the row archived instead of deleting it, servers keep the version
+    #[serde(default)]
+    archive: bool,
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
         config.service(
             web::resource("/versions")
-                .route(
-                    web::post()
-                        .to(add_version::add_version)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
-                .route(
-                    web::delete()
-                        .to(remove_version::remove_version)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
-                .route(
-                    web::put()
-                        .to(update_version::update_version)
-                        .wrap(from_fn(admin_auth_middleware)),
-                )
+                .post(add_version::add_version)
+                .delete(remove_version::remove_version)
+                .put(update_version::update_version)
                 .get(list_versions::list_versions),
         );
     }
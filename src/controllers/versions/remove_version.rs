@@ -1,9 +1,25 @@
-use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter};
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    web, HttpResponse, Responder,
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, ModelTrait,
+    PaginatorTrait, QueryFilter, QuerySelect, RelationTrait, TransactionTrait,
+};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::versions, error::AppError};
+use crate::{
+    controllers::servers::{in_use, ServerRef},
+    entities::{servers, servers_info, versions},
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+    version_sync::{inverted_ranges, INVERTED_RANGES},
+};
 
 use super::DeleteVersion;
 
@@ -11,16 +27,20 @@ use super::DeleteVersion;
     delete,
     path = "/api/versions",
     tag = "Versions",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
     request_body(content = DeleteVersion, description = "Version Data", content_type = "application/json", examples(
         ("Full" = (value = json!({"id": 3, "name": "1.8"}))),
-        ("No Id" = (value = json!({"name": "1.8"})))
+        ("No Id" = (value = json!({"name": "1.8"}))),
+        ("Reassign" = (value = json!({"id": 3, "name": "1.8", "reassign_to": 4}))),
+        ("Archive" = (value = json!({"id": 3, "name": "1.8", "archive": true})))
     )),
     responses(
-        (status = 200, description = "Successfully deleted version", body = None, example = json!({"message": "Success"})),
+        (status = 200, description = "Successfully deleted or archived version", body = None, example = json!({"message": "Success"})),
+        (status = 400, description = "Version to reassign to does not exist, is archived or is the version itself"),
         (status = 404, description = "version does not exist", body = None, example = json!({"message": "No such version exist"})),
+        (status = 409, description = "Servers still use the version, or reassigning would give them a minimum version newer than their maximum, they are listed", body = None, example = json!({"code": 409, "message": "Servers still use the version", "servers": [{"id": 4, "name": "Hypixel"}]})),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -29,20 +49,102 @@ use super::DeleteVersion;
 )]
 pub async fn remove_version(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<DeleteVersion>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<DeleteVersion>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
     let version = if let Some(id) = data.id {
         versions::Entity::find().filter(versions::Column::Id.eq(id))
     } else {
         versions::Entity::find().filter(versions::Column::Name.eq(&data.name))
     }
     .one(db.get_ref().as_ref())
-    .await?;
+    .await?
+    .ok_or(ErrorNotFound("No such version exist"))?;
+    let id = version.id;
+
+    if let Some(target) = data.reassign_to {
+        let exists = versions::Entity::find_by_id(target)
+            .filter(versions::Column::ArchivedAt.is_null())
+            .count(db.get_ref().as_ref())
+            .await?;
+        if target == id || exists == 0 {
+            return Err(ErrorBadRequest("Version to reassign to does not exist").into());
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    // Servers picking the version wait for this lock, so the list below stays complete
+    versions::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let servers: Vec<ServerRef> = servers::Entity::find()
+        .join(JoinType::InnerJoin, servers::Relation::ServersInfo.def())
+        .filter(
+            Condition::any()
+                .add(servers_info::Column::MinVersion.eq(id))
+                .add(servers_info::Column::MaxVersion.eq(id)),
+        )
+        .select_only()
+        .column(servers::Column::Id)
+        .column(servers::Column::Name)
+        .into_model()
+        .all(&txn)
+        .await?;
+
+    if data.reassign_to.is_none() && !data.archive && !servers.is_empty() {
+        txn.rollback().await?;
+        return Ok(in_use("Servers still use the version", servers));
+    }
+
+    if let Some(target) = data.reassign_to {
+        servers_info::Entity::update_many()
+            .col_expr(servers_info::Column::MinVersion, Expr::value(target))
+            .filter(servers_info::Column::MinVersion.eq(id))
+            .exec(&txn)
+            .await?;
+
+        servers_info::Entity::update_many()
+            .col_expr(servers_info::Column::MaxVersion, Expr::value(target))
+            .filter(servers_info::Column::MaxVersion.eq(id))
+            .exec(&txn)
+            .await?;
+
+        let inverted = inverted_ranges(&txn).await?;
+        if !inverted.is_empty() {
+            txn.rollback().await?;
+            return Ok(in_use(INVERTED_RANGES, inverted));
+        }
+    }
+
+    if data.archive {
+        versions::Entity::update_many()
+            .col_expr(
+                versions::Column::ArchivedAt,
+                Expr::value(Utc::now().naive_utc()),
+            )
+            .filter(versions::Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+    } else {
+        version.delete(&txn).await?;
+    }
+
+    txn.commit().await?;
 
-    if let Some(version) = version {
-        version.delete(db.get_ref().as_ref()).await?;
-        return Ok(HttpResponse::Ok().json(json!({"message": "Success"})));
+    events.publish(DomainEvent::new(Topic::Versions, id)).await;
+    if data.reassign_to.is_some() {
+        for server in &servers {
+            events
+                .publish(DomainEvent::new(Topic::Servers, server.id))
+                .await;
+        }
     }
 
-    Err(ErrorNotFound("No such version exist").into())
+    Ok(HttpResponse::Ok().json(json!({"message": "Success"})))
 }
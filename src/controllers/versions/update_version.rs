@@ -1,9 +1,22 @@
 use actix_web::{error::ErrorNotFound, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::{entities::versions, error::AppError};
+use crate::{
+    controllers::servers::in_use,
+    entities::versions,
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+    permissions::Permission,
+    utils::AuthUser,
+    validation::ValidJson,
+    version_sync::{reorder, INVERTED_RANGES},
+};
 
 use super::UpdateVersion;
 
@@ -11,13 +24,14 @@ use super::UpdateVersion;
     put,
     path = "/api/versions",
     tag = "Versions",
-    params(
-        ("Authentication" = String, Header, description = "JWT access token"),
-    ),
-    request_body(content = UpdateVersion, description = "Version Data", content_type = "application/json", example = json!({"id": 3, "name": "1.8", "protocol": 47})),
+    request_body(content = UpdateVersion, description = "Version Data", content_type = "application/json", example = json!({"id": 3, "name": "1.8", "protocol": 47, "released_at": "2014-09-02T08:39:00"})),
     responses(
         (status = 200, description = "Successfully updated version", body = None, example = json!({"message": "Success"})),
         (status = 404, description = "Version does not exist", body = None, example = json!({"message": "No such version exist"})),
+        (status = 409, description = "The new release date would put the minimum version of servers after their maximum, they are listed"),
+        (status = 401, description = "Invalid token"),
+        (status = 403, description = "Missing the manage_catalog permission"),
+        (status = 422, description = "Invalid fields, listed in errors"),
         (status = 500, description = "Server error"),
     ),
     security(
@@ -26,14 +40,19 @@ use super::UpdateVersion;
 )]
 pub async fn update_version(
     db: web::Data<Arc<DatabaseConnection>>,
-    data: web::Json<UpdateVersion>,
+    events: web::Data<Arc<EventBus>>,
+    data: ValidJson<UpdateVersion>,
+    user: AuthUser,
 ) -> Result<impl Responder, AppError> {
+    user.require(Permission::ManageCatalog)?;
+
     let version = versions::Entity::find()
         .filter(versions::Column::Id.eq(data.id))
         .one(db.get_ref().as_ref())
         .await?;
 
     if let Some(version) = version {
+        let archived_at = version.archived_at;
         let mut new_version: versions::ActiveModel = version.into();
         if let Some(name) = &data.name {
             new_version.name = Set(name.to_owned());
@@ -41,7 +60,32 @@ pub async fn update_version(
         if let Some(protocol) = data.protocol {
             new_version.protocol = Set(protocol);
         }
-        new_version.update(db.get_ref().as_ref()).await?;
+        match data.archived {
+            Some(true) if archived_at.is_none() => {
+                new_version.archived_at = Set(Some(Utc::now().naive_utc()))
+            }
+            Some(false) => new_version.archived_at = Set(None),
+            _ => {}
+        }
+        if let Some(released_at) = data.released_at {
+            new_version.released_at = Set(Some(released_at));
+        }
+
+        let txn = db.begin().await?;
+        new_version.update(&txn).await?;
+
+        if data.released_at.is_some() {
+            let inverted = reorder(&txn).await?;
+            if !inverted.is_empty() {
+                txn.rollback().await?;
+                return Ok(in_use(INVERTED_RANGES, inverted));
+            }
+        }
+
+        txn.commit().await?;
+        events
+            .publish(DomainEvent::new(Topic::Versions, data.id))
+            .await;
 
         return Ok(HttpResponse::Ok().json(json!({"message": "Success"})));
     }
@@ -6,8 +6,26 @@ use utoipa::{Modify, OpenApi};
     paths(
         // Auth
         crate::controllers::auth::login::login,
+        crate::controllers::auth::login_totp::login_totp,
         crate::controllers::auth::register::register,
         crate::controllers::auth::refresh::refresh,
+        crate::controllers::auth::logout::logout,
+        crate::controllers::auth::logout::logout_all,
+        crate::controllers::auth::verify_email::verify_email,
+        crate::controllers::auth::confirm_email::confirm_email,
+        crate::controllers::auth::forgot_password::forgot_password,
+        crate::controllers::auth::reset_password::reset_password,
+        crate::controllers::auth::microsoft::microsoft_login,
+        crate::controllers::auth::microsoft::microsoft_link,
+        crate::controllers::auth::microsoft::microsoft_unlink,
+        crate::controllers::auth::microsoft::microsoft_callback,
+        crate::controllers::auth::jwks::jwks,
+
+        // Totp
+        crate::controllers::totp::enroll::enroll,
+        crate::controllers::totp::confirm::confirm,
+        crate::controllers::totp::recovery_codes::recovery_codes,
+        crate::controllers::totp::disable::disable,
 
         // Categories
         crate::controllers::categories::add_category::add_category,
@@ -21,11 +39,49 @@ use utoipa::{Modify, OpenApi};
         crate::controllers::versions::update_version::update_version,
         crate::controllers::versions::remove_version::remove_version,
 
+        // Admin
+        crate::controllers::admin::list_tasks::list_tasks,
+        crate::controllers::admin::sse_metrics::sse_metrics,
+        crate::controllers::admin::sync_versions::sync_versions,
+        crate::controllers::catalog::categories::export_categories,
+        crate::controllers::catalog::categories::import_categories,
+        crate::controllers::catalog::versions::export_versions,
+        crate::controllers::catalog::versions::import_versions,
+        crate::controllers::admin::user_roles::list_roles,
+        crate::controllers::admin::user_roles::grant_role,
+        crate::controllers::admin::user_roles::revoke_role,
+
+        // Moderation
+        crate::controllers::moderation::hide_server::hide_server,
+        crate::controllers::moderation::hide_review::hide_review,
+
+        // Users
+        crate::controllers::users::get_profile::get_profile,
+        crate::controllers::users::update_profile::update_profile,
+        crate::controllers::users::change_password::change_password,
+        crate::controllers::users::delete_account::delete_account,
+        crate::controllers::users::get_user::get_user,
+
         // Servers
         crate::controllers::servers::list_servers::list_servers,
         crate::controllers::servers::get_server::get_server,
         crate::controllers::servers::get_user_servers::get_user_servers,
         crate::controllers::servers::add_server::add_server,
+        crate::controllers::servers::update_attributes::update_attributes,
+        crate::controllers::servers::banner::upload_banner,
+        crate::controllers::servers::banner::remove_banner,
+        crate::controllers::servers::gallery::add_screenshot,
+        crate::controllers::servers::gallery::remove_screenshot,
+        crate::controllers::servers::players::players_history,
+        crate::controllers::servers::reviews::list_reviews,
+
+        // Stats
+        crate::controllers::stats::total_players::total_players,
+        crate::controllers::tags::list_tags::list_tags,
+        crate::controllers::tags::list_tags::list_pending_tags,
+        crate::controllers::tags::add_tag::add_tag,
+        crate::controllers::tags::update_tag::update_tag,
+        crate::controllers::tags::remove_tag::remove_tag,
     ),
     components(
         // Auth
@@ -33,6 +89,20 @@ use utoipa::{Modify, OpenApi};
             crate::controllers::auth::LoginRequest,
             crate::controllers::auth::LoginResponse,
             crate::controllers::auth::RegisterRequest,
+            crate::controllers::auth::VerifyEmailRequest,
+            crate::controllers::auth::ForgotPasswordRequest,
+            crate::controllers::auth::ResetPasswordRequest,
+            crate::controllers::auth::LoginChallenge,
+            crate::controllers::auth::TotpLoginRequest,
+            crate::controllers::auth::microsoft::MicrosoftAuthorization,
+            crate::controllers::auth::microsoft::MinecraftAccount,
+        ),
+
+        // Totp
+        schemas(
+            crate::controllers::totp::TotpEnrollment,
+            crate::controllers::totp::TotpCode,
+            crate::controllers::totp::RecoveryCodes,
         ),
 
         // Categories
@@ -40,6 +110,7 @@ use utoipa::{Modify, OpenApi};
             crate::controllers::categories::Category,
             crate::controllers::categories::UpdateCategory,
             crate::controllers::categories::DeleteCategory,
+            crate::controllers::categories::CategoryNode,
         ),
 
         // Versions
@@ -54,6 +125,54 @@ use utoipa::{Modify, OpenApi};
             crate::controllers::servers::Server,
             crate::controllers::servers::Category,
             crate::controllers::servers::ServerData,
+            crate::controllers::servers::ServerRef,
+            crate::controllers::servers::ServerAttributes,
+            crate::controllers::servers::ServerTag,
+            crate::controllers::servers::ServerImage,
+            crate::controllers::servers::ServerReview,
+            crate::controllers::servers::ImageUpload,
+            crate::controllers::tags::Tag,
+            crate::controllers::tags::UpdateTag,
+            crate::controllers::tags::TagEntry,
+        ),
+
+        // Stats
+        schemas(
+            crate::player_stats::Bucket,
+            crate::player_stats::PlayersBucket,
+            crate::player_stats::PlayersPeak,
+            crate::player_stats::PlayersHistory,
+            crate::player_stats::TotalPlayers,
+        ),
+
+        // Users
+        schemas(
+            crate::controllers::users::Profile,
+            crate::controllers::users::UpdateProfile,
+            crate::controllers::users::ChangePassword,
+            crate::controllers::users::DeleteAccount,
+            crate::controllers::users::PublicReview,
+            crate::controllers::users::PublicProfile,
+        ),
+
+        // Admin
+        schemas(
+            crate::supervisor::TaskStatus,
+            crate::sender::BroadcasterMetrics,
+            crate::controllers::admin::RoleGrant,
+            crate::controllers::admin::UserRoles,
+            crate::version_sync::SyncReport,
+            crate::controllers::catalog::CategoryRecord,
+            crate::controllers::catalog::VersionRecord,
+            crate::controllers::catalog::ImportConflict,
+            crate::controllers::catalog::ImportReport,
+            crate::controllers::catalog::Format,
+            crate::permissions::Permission,
+        ),
+
+        // Moderation
+        schemas(
+            crate::controllers::moderation::Visibility,
         ),
 
         // Entities
@@ -62,14 +181,26 @@ use utoipa::{Modify, OpenApi};
             crate::entities::auth::Model,
             crate::entities::categories::Model,
             crate::entities::players_graph::Model,
+            crate::entities::players_graph_daily::Model,
+            crate::entities::players_graph_hourly::Model,
             crate::entities::reviews::Model,
             crate::entities::server_categories::Model,
             crate::entities::servers::Model,
+            crate::entities::server_tags::Model,
+            crate::entities::server_images::Model,
+            crate::entities::tags::Model,
             crate::entities::servers_info::Model,
+            crate::entities::user_roles::Model,
+            crate::entities::user_tokens::Model,
             crate::entities::users::Model,
+            crate::entities::login_attempts::Model,
             crate::entities::versions::Model,
 
             crate::entities::sea_orm_active_enums::Role,
+            crate::entities::sea_orm_active_enums::Purpose,
+            crate::entities::sea_orm_active_enums::LoginOutcome,
+            crate::entities::sea_orm_active_enums::ReleaseType,
+            crate::entities::sea_orm_active_enums::ImageKind,
         ),
 
         // Errors
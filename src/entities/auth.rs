@@ -12,10 +12,16 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    pub token: String,
+    /// Hex SHA-256 of the refresh token, the token itself is not stored
+    #[sea_orm(unique)]
+    pub token_hash: String,
     pub user_id: i32,
+    pub family: String,
+    pub session_started_at: DateTime,
     pub created_at: Option<DateTime>,
     pub expires_at: DateTime,
+    pub revoked_at: Option<DateTime>,
+    pub replaced_by: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
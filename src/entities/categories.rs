@@ -12,12 +12,31 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    pub parent_id: Option<i32>,
     pub name: String,
+    #[sea_orm(unique)]
+    pub slug: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    /// Hex colour like `#55ff55`
+    pub colour: Option<String>,
+    pub sort_order: i32,
+    /// Set once archived, servers keep it but it can not be picked anymore
+    pub archived_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
-    #[sea_orm(has_one = "super::server_categories::Entity")]
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "Restrict",
+        on_delete = "Restrict"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::server_categories::Entity")]
     ServerCategories,
 }
 
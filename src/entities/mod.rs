@@ -1,15 +1,30 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+#[allow(unused_imports)]
 pub mod prelude;
 
 pub mod ads;
 pub mod auth;
 pub mod categories;
+pub mod leader_lease;
+pub mod login_attempts;
+pub mod login_throttle_locks;
+pub mod oauth_nonces;
 pub mod players_graph;
+pub mod players_graph_daily;
+pub mod players_graph_hourly;
+pub mod pubsub_messages;
+pub mod recovery_codes;
 pub mod reviews;
 pub mod sea_orm_active_enums;
 pub mod server_categories;
+pub mod server_images;
+pub mod server_tags;
 pub mod servers;
 pub mod servers_info;
+pub mod tags;
+pub mod user_roles;
+pub mod user_tokens;
+pub mod user_totp;
 pub mod users;
 pub mod versions;
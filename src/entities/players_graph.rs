@@ -14,6 +14,8 @@ pub struct Model {
     pub id: i32,
     pub server_id: i32,
     pub players_online: i32,
+    /// Whether the ping got an answer, players are 0 when it did not
+    pub online: i8,
     pub date: Option<DateTime>,
 }
 
@@ -24,7 +26,7 @@ pub enum Relation {
         from = "Column::ServerId",
         to = "super::servers::Column::Id",
         on_update = "Restrict",
-        on_delete = "Restrict"
+        on_delete = "Cascade"
     )]
     Servers,
 }
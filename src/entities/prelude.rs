@@ -3,10 +3,24 @@
 pub use super::ads::Entity as Ads;
 pub use super::auth::Entity as Auth;
 pub use super::categories::Entity as Categories;
+pub use super::leader_lease::Entity as LeaderLease;
+pub use super::login_attempts::Entity as LoginAttempts;
+pub use super::login_throttle_locks::Entity as LoginThrottleLocks;
+pub use super::oauth_nonces::Entity as OauthNonces;
 pub use super::players_graph::Entity as PlayersGraph;
+pub use super::players_graph_daily::Entity as PlayersGraphDaily;
+pub use super::players_graph_hourly::Entity as PlayersGraphHourly;
+pub use super::pubsub_messages::Entity as PubsubMessages;
+pub use super::recovery_codes::Entity as RecoveryCodes;
 pub use super::reviews::Entity as Reviews;
 pub use super::server_categories::Entity as ServerCategories;
+pub use super::server_images::Entity as ServerImages;
+pub use super::server_tags::Entity as ServerTags;
 pub use super::servers::Entity as Servers;
 pub use super::servers_info::Entity as ServersInfo;
+pub use super::tags::Entity as Tags;
+pub use super::user_roles::Entity as UserRoles;
+pub use super::user_tokens::Entity as UserTokens;
+pub use super::user_totp::Entity as UserTotp;
 pub use super::users::Entity as Users;
 pub use super::versions::Entity as Versions;
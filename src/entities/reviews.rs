@@ -17,6 +17,9 @@ pub struct Model {
     pub description: String,
     pub stars: i32,
     pub created_at: Option<DateTime>,
+    /// Set while a moderator hides it from listings
+    pub hidden_at: Option<DateTime>,
+    pub hidden_by: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
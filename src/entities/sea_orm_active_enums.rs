@@ -3,21 +3,135 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// Granted through `user_roles`, a user without any is a plain user.
 #[derive(
     Debug,
     Clone,
+    Copy,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
+    Hash,
     EnumIter,
     DeriveActiveEnum,
     Serialize,
     Deserialize,
     utoipa :: ToSchema,
 )]
-#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "role")]
+#[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
 pub enum Role {
     #[sea_orm(string_value = "Admin")]
     Admin,
-    #[sea_orm(string_value = "User")]
-    User,
+    #[sea_orm(string_value = "Moderator")]
+    Moderator,
+    #[sea_orm(string_value = "ContentEditor")]
+    ContentEditor,
+    #[sea_orm(string_value = "AdsManager")]
+    AdsManager,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "purpose")]
+pub enum Purpose {
+    #[sea_orm(string_value = "VerifyEmail")]
+    VerifyEmail,
+    #[sea_orm(string_value = "ResetPassword")]
+    ResetPassword,
+    /// Sent to the new address, the email only changes once it is used
+    #[sea_orm(string_value = "ChangeEmail")]
+    ChangeEmail,
+}
+
+/// Stored as a plain string, new outcomes do not need a migration.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
+pub enum LoginOutcome {
+    #[sea_orm(string_value = "Success")]
+    Success,
+    #[sea_orm(string_value = "UnknownEmail")]
+    UnknownEmail,
+    #[sea_orm(string_value = "BadPassword")]
+    BadPassword,
+    /// Password was right, the second factor is still missing
+    #[sea_orm(string_value = "PasswordAccepted")]
+    PasswordAccepted,
+    #[sea_orm(string_value = "BadCode")]
+    BadCode,
+    /// Rejected before the password was checked
+    #[sea_orm(string_value = "Throttled")]
+    Throttled,
+    /// Still being checked, or the request ended before it was
+    #[sea_orm(string_value = "Pending")]
+    Pending,
+}
+
+/// Channel a version was published on, as named by the version manifest.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseType {
+    #[sea_orm(string_value = "release")]
+    Release,
+    #[sea_orm(string_value = "snapshot")]
+    Snapshot,
+    #[sea_orm(string_value = "beta")]
+    Beta,
+    #[sea_orm(string_value = "alpha")]
+    Alpha,
+}
+
+/// Place an uploaded image is shown in.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ImageKind {
+    #[sea_orm(string_value = "banner")]
+    Banner,
+    #[sea_orm(string_value = "screenshot")]
+    Screenshot,
 }
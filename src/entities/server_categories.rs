@@ -12,8 +12,9 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub server_id: i32,
-    #[sea_orm(unique)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub category_id: i32,
+    pub is_primary: i8,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -31,7 +32,7 @@ pub enum Relation {
         from = "Column::ServerId",
         to = "super::servers::Column::Id",
         on_update = "Restrict",
-        on_delete = "Restrict"
+        on_delete = "Cascade"
     )]
     Servers,
 }
@@ -14,19 +14,37 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub description: String,
+    /// Language code like `en` or `pt-BR`
+    pub language: Option<String>,
+    /// ISO 3166-1 alpha-2 code
+    pub country: Option<String>,
+    pub website: Option<String>,
+    pub discord_invite: Option<String>,
+    pub store_url: Option<String>,
     pub user_id: i32,
     pub is_premium: i8,
     pub created_at: Option<DateTime>,
+    /// Set while a moderator hides it from listings
+    pub hidden_at: Option<DateTime>,
+    pub hidden_by: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::players_graph::Entity")]
     PlayersGraph,
+    #[sea_orm(has_many = "super::players_graph_daily::Entity")]
+    PlayersGraphDaily,
+    #[sea_orm(has_many = "super::players_graph_hourly::Entity")]
+    PlayersGraphHourly,
     #[sea_orm(has_many = "super::reviews::Entity")]
     Reviews,
     #[sea_orm(has_many = "super::server_categories::Entity")]
     ServerCategories,
+    #[sea_orm(has_many = "super::server_images::Entity")]
+    ServerImages,
+    #[sea_orm(has_many = "super::server_tags::Entity")]
+    ServerTags,
     #[sea_orm(has_many = "super::servers_info::Entity")]
     ServersInfo,
     #[sea_orm(
@@ -45,6 +63,18 @@ impl Related<super::players_graph::Entity> for Entity {
     }
 }
 
+impl Related<super::players_graph_daily::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlayersGraphDaily.def()
+    }
+}
+
+impl Related<super::players_graph_hourly::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlayersGraphHourly.def()
+    }
+}
+
 impl Related<super::reviews::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Reviews.def()
@@ -57,6 +87,18 @@ impl Related<super::server_categories::Entity> for Entity {
     }
 }
 
+impl Related<super::server_images::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ServerImages.def()
+    }
+}
+
+impl Related<super::server_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ServerTags.def()
+    }
+}
+
 impl Related<super::servers_info::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ServersInfo.def()
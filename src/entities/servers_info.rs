@@ -14,6 +14,7 @@ pub struct Model {
     pub id: i32,
     pub server_id: i32,
     pub address: String,
+    pub port: u16,
     pub min_version: i32,
     pub max_version: i32,
     pub created_at: Option<DateTime>,
@@ -1,6 +1,5 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
-use super::sea_orm_active_enums::Role;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -15,10 +14,16 @@ pub struct Model {
     pub id: i32,
     #[sea_orm(unique)]
     pub email: String,
+    pub email_verified_at: Option<DateTime>,
+    /// Becomes `email` once confirmed through the mailed link
+    pub pending_email: Option<String>,
     #[sea_orm(unique)]
     pub username: String,
+    #[sea_orm(unique)]
+    pub minecraft_uuid: Option<String>,
+    pub minecraft_name: Option<String>,
+    pub minecraft_linked_at: Option<DateTime>,
     pub password: String,
-    pub role: Role,
     pub created_at: Option<DateTime>,
 }
 
@@ -28,10 +33,20 @@ pub enum Relation {
     Ads,
     #[sea_orm(has_many = "super::auth::Entity")]
     Auth,
+    #[sea_orm(has_many = "super::recovery_codes::Entity")]
+    RecoveryCodes,
     #[sea_orm(has_many = "super::reviews::Entity")]
     Reviews,
     #[sea_orm(has_many = "super::servers::Entity")]
     Servers,
+    #[sea_orm(has_many = "super::tags::Entity")]
+    Tags,
+    #[sea_orm(has_many = "super::user_roles::Entity")]
+    UserRoles,
+    #[sea_orm(has_many = "super::user_tokens::Entity")]
+    UserTokens,
+    #[sea_orm(has_one = "super::user_totp::Entity")]
+    UserTotp,
 }
 
 impl Related<super::ads::Entity> for Entity {
@@ -46,6 +61,12 @@ impl Related<super::auth::Entity> for Entity {
     }
 }
 
+impl Related<super::recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCodes.def()
+    }
+}
+
 impl Related<super::reviews::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Reviews.def()
@@ -58,4 +79,28 @@ impl Related<super::servers::Entity> for Entity {
     }
 }
 
+impl Related<super::tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Tags.def()
+    }
+}
+
+impl Related<super::user_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRoles.def()
+    }
+}
+
+impl Related<super::user_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserTokens.def()
+    }
+}
+
+impl Related<super::user_totp::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserTotp.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -3,6 +3,8 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use super::sea_orm_active_enums::ReleaseType;
+
 #[derive(
     Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, utoipa :: ToSchema,
 )]
@@ -14,6 +16,13 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub protocol: i32,
+    /// Position from the oldest version, ranges are compared with it
+    pub ordinal: i32,
+    /// Left empty for versions added by hand
+    pub release_type: Option<ReleaseType>,
+    pub released_at: Option<DateTime>,
+    /// Set once archived, servers keep it but it can not be picked anymore
+    pub archived_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
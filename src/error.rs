@@ -1,8 +1,11 @@
 use actix_web::http::header::ContentType;
 use actix_web::{body, http::StatusCode, HttpResponse};
+use sea_orm::SqlErr;
 use serde_json::json;
+use std::collections::BTreeMap;
 use thiserror::Error;
 use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
 #[derive(Debug, Error, ToSchema)]
 pub enum AppError {
@@ -20,33 +23,157 @@ pub enum AppError {
 
     #[error("{0}")]
     JsonWebToken(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Mail Error: {0}")]
+    Mail(String),
+
+    #[error("Totp Error: {0}")]
+    Totp(String),
+
+    #[error("Key Error: {0}")]
+    Key(String),
+
+    #[error("Storage Error: {0}")]
+    Storage(String),
+
+    #[error("Validation failed")]
+    Validation(#[from] validator::ValidationErrors),
 }
 
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
-            Self::Db(_) | Self::Io(_) | Self::JsonWebToken(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Db(e) if is_unique_violation(e) => StatusCode::CONFLICT,
+            Self::Db(_)
+            | Self::Io(_)
+            | Self::JsonWebToken(_)
+            | Self::Mail(_)
+            | Self::Totp(_)
+            | Self::Key(_)
+            | Self::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::Actix(e) => e.as_response_error().status_code(),
             Self::Serde(_) => StatusCode::BAD_REQUEST,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    /// Every invalid field with its messages, nested ones as `field.inner` or `field[0].inner`.
+    fn field_errors(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        let Self::Validation(errors) = self else {
+            return None;
+        };
+
+        let mut fields = BTreeMap::new();
+        collect_field_errors(errors, "", &mut fields);
+        Some(fields)
+    }
+}
+
+fn collect_field_errors(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut BTreeMap<String, Vec<String>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            "" => (*field).to_owned(),
+            _ => format!("{prefix}.{field}"),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = errors.iter().map(|e| match &e.message {
+                    Some(message) => message.to_string(),
+                    None => format!("Invalid value ({})", e.code),
+                });
+                fields.entry(path).or_default().extend(messages);
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{path}[{index}]"), fields);
+                }
+            }
         }
     }
 }
 
+fn is_unique_violation(e: &sea_orm::DbErr) -> bool {
+    matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
+}
+
 impl actix_web::error::ResponseError for AppError {
     fn error_response(&self) -> HttpResponse<body::BoxBody> {
+        let message = match self {
+            // The raw database message names tables and keys
+            Self::Db(e) if is_unique_violation(e) => "Resource already exists".to_owned(),
+            _ => self.to_string(),
+        };
+
+        let mut body = json!({
+            "code": self.status_code().as_u16(),
+            "message": message
+        });
+        if let Some(errors) = self.field_errors() {
+            body["errors"] = json!(errors);
+        }
+
         HttpResponse::build(self.status_code())
             .insert_header(ContentType::json())
-            .json(json!({
-                "code": self.status_code().as_u16(),
-                "message": self.to_string()
-            }))
+            .json(body)
     }
 
     fn status_code(&self) -> StatusCode {
-        match self {
-            Self::Db(_) | Self::Io(_) | Self::JsonWebToken(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::Actix(e) => e.as_response_error().status_code(),
-            Self::Serde(_) => StatusCode::BAD_REQUEST,
-        }
+        AppError::status_code(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Validate)]
+    struct Inner {
+        #[validate(length(min = 1, message = "Can not be empty"))]
+        name: String,
+    }
+
+    #[derive(Validate)]
+    struct Outer {
+        #[validate(range(min = 1))]
+        port: u16,
+        #[validate(nested)]
+        inner: Inner,
+        #[validate(nested)]
+        items: Vec<Inner>,
+    }
+
+    #[test]
+    fn field_errors_include_nested_structs_and_lists() {
+        let outer = Outer {
+            port: 0,
+            inner: Inner {
+                name: String::new(),
+            },
+            items: vec![
+                Inner {
+                    name: "ok".to_owned(),
+                },
+                Inner {
+                    name: String::new(),
+                },
+            ],
+        };
+        let error = AppError::from(outer.validate().unwrap_err());
+
+        let fields = error.field_errors().unwrap();
+        assert_eq!(
+            fields.keys().collect::<Vec<_>>(),
+            ["inner.name", "items[1].name", "port"]
+        );
+        assert_eq!(fields["inner.name"], ["Can not be empty"]);
+        assert_eq!(fields["port"], ["Invalid value (range)"]);
     }
 }
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::pubsub::{Message, PubSubBackend};
+use crate::sender::Broadcaster;
+use crate::shutdown::Shutdown;
+
+// Events buffered per subscriber before it starts lagging
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Table a domain event refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Topic {
+    Servers,
+    PlayersGraph,
+    Categories,
+    Versions,
+    Tags,
+}
+
+/// Published by whoever writes to the database, right after the write succeeded.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DomainEvent {
+    pub topic: Topic,
+    /// Id of the changed row, for `PlayersGraph` this is the server id.
+    pub id: i32,
+}
+
+impl DomainEvent {
+    pub fn new(topic: Topic, id: i32) -> Self {
+        Self { topic, id }
+    }
+}
+
+/// Routes domain events and SSE payloads through the pub/sub backend, so
+/// they reach every instance and not only the one that produced them.
+pub struct EventBus {
+    backend: Arc<dyn PubSubBackend>,
+    sender: broadcast::Sender<DomainEvent>,
+}
+
+impl EventBus {
+    pub fn create(
+        backend: Arc<dyn PubSubBackend>,
+        broadcaster: Arc<Broadcaster>,
+        shutdown: Arc<Shutdown>,
+    ) -> Arc<Self> {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let this = Arc::new(Self { backend, sender });
+        EventBus::spawn_relay(Arc::clone(&this), broadcaster, shutdown);
+        this
+    }
+
+    fn spawn_relay(this: Arc<Self>, broadcaster: Arc<Broadcaster>, shutdown: Arc<Shutdown>) {
+        let mut messages = this.backend.subscribe();
+
+        actix_web::rt::spawn(async move {
+            loop {
+                let message = tokio::select! {
+                    message = messages.recv() => message,
+                    _ = shutdown.triggered() => return,
+                };
+
+                match message {
+                    Ok(Message::Event(event)) => {
+                        // Nobody listening is not an error, the reconciliation pass catches up
+                        let _ = this.sender.send(event);
+                    }
+                    Ok(Message::Broadcast(msg)) => broadcaster.broadcast(&msg),
+                    Err(RecvError::Lagged(n)) => log::warn!("Event relay skipped {n} messages"),
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    pub async fn publish(&self, event: DomainEvent) {
+        if let Err(e) = self.backend.publish(Message::Event(event)).await {
+            log::error!("Failed to publish {event:?}: {e}");
+        }
+    }
+
+    /// Sends the payload to the SSE clients of every instance.
+    pub async fn broadcast(&self, msg: String) {
+        if let Err(e) = self.backend.publish(Message::Broadcast(msg)).await {
+            log::error!("Failed to publish broadcast: {e}");
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::Cursor;
+
+use actix_web::error::ErrorBadRequest;
+use image::{ImageFormat, ImageReader, ImageResult, Limits};
+
+use crate::error::AppError;
+
+/// What an upload has to be, checked before anything is stored.
+pub struct ImageRules {
+    pub formats: &'static [ImageFormat],
+    pub min_size: (u32, u32),
+    pub max_size: (u32, u32),
+    pub max_bytes: usize,
+}
+
+/// List-site standard banner, animated GIFs keep their frames.
+pub const BANNER: ImageRules = ImageRules {
+    formats: &[ImageFormat::Gif, ImageFormat::Png, ImageFormat::Jpeg],
+    min_size: (468, 60),
+    max_size: (468, 60),
+    max_bytes: 2 * 1024 * 1024,
+};
+
+pub const SCREENSHOT: ImageRules = ImageRules {
+    formats: &[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP],
+    min_size: (320, 180),
+    max_size: (3840, 2160),
+    max_bytes: 8 * 1024 * 1024,
+};
+
+/// Screenshots are scaled down to fit in this box for listings.
+pub const THUMBNAIL_SIZE: (u32, u32) = (480, 270);
+
+#[derive(Clone, Copy)]
+pub struct ImageInfo {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageInfo {
+    pub fn content_type(&self) -> &'static str {
+        self.format.to_mime_type()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.format.extensions_str()[0]
+    }
+}
+
+/// Format from the content rather than the file name, and dimensions from the header.
+pub fn inspect(data: &[u8], rules: &ImageRules) -> Result<ImageInfo, AppError> {
+    if data.len() > rules.max_bytes {
+        return Err(ErrorBadRequest(format!(
+            "Image can be at most {} KiB",
+            rules.max_bytes / 1024
+        ))
+        .into());
+    }
+
+    let reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+    let format = reader
+        .format()
+        .filter(|v| rules.formats.contains(v))
+        .ok_or_else(|| {
+            let names: Vec<&str> = rules
+                .formats
+                .iter()
+                .map(|v| v.extensions_str()[0])
+                .collect();
+            ErrorBadRequest(format!("Image has to be one of {}", names.join(", ")))
+        })?;
+
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|e| ErrorBadRequest(format!("Image can not be read: {e}")))?;
+
+    let (min, max) = (rules.min_size, rules.max_size);
+    if width < min.0 || height < min.1 || width > max.0 || height > max.1 {
+        let message = match min == max {
+            true => format!("Image has to be {}x{}", min.0, min.1),
+            false => format!(
+                "Image has to be between {}x{} and {}x{}",
+                min.0, min.1, max.0, max.1
+            ),
+        };
+        return Err(ErrorBadRequest(message).into());
+    }
+
+    Ok(ImageInfo {
+        format,
+        width,
+        height,
+    })
+}
+
+/// JPEG scaled down to fit in `THUMBNAIL_SIZE`, decoding is CPU bound so run it off the workers.
+pub fn thumbnail(data: &[u8], info: &ImageInfo) -> ImageResult<Vec<u8>> {
+    let mut reader = ImageReader::with_format(Cursor::new(data), info.format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(info.width);
+    limits.max_image_height = Some(info.height);
+    reader.limits(limits);
+
+    let mut out = Cursor::new(Vec::new());
+    reader
+        .decode()?
+        .thumbnail(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1)
+        .to_rgb8()
+        .write_to(&mut out, ImageFormat::Jpeg)?;
+
+    Ok(out.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+
+    use super::*;
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        RgbImage::new(width, height)
+            .write_to(&mut out, format)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn inspect_reads_format_and_size_from_the_content() {
+        let info = inspect(&encode(640, 360, ImageFormat::Png), &SCREENSHOT).unwrap();
+
+        assert_eq!(info.format, ImageFormat::Png);
+        assert_eq!((info.width, info.height), (640, 360));
+        assert_eq!(info.content_type(), "image/png");
+        assert_eq!(info.extension(), "png");
+    }
+
+    #[test]
+    fn inspect_rejects_formats_outside_the_rules() {
+        assert!(inspect(&encode(640, 360, ImageFormat::Gif), &SCREENSHOT).is_err());
+        assert!(inspect(&encode(468, 60, ImageFormat::Gif), &BANNER).is_ok());
+        assert!(inspect(b"not an image at all", &SCREENSHOT).is_err());
+    }
+
+    #[test]
+    fn inspect_checks_dimensions_and_bytes() {
+        assert!(inspect(&encode(319, 180, ImageFormat::Png), &SCREENSHOT).is_err());
+        assert!(inspect(&encode(3841, 2160, ImageFormat::Png), &SCREENSHOT).is_err());
+        assert!(inspect(&encode(469, 60, ImageFormat::Png), &BANNER).is_err());
+
+        let tiny = ImageRules {
+            max_bytes: 16,
+            ..SCREENSHOT
+        };
+        assert!(inspect(&encode(640, 360, ImageFormat::Png), &tiny).is_err());
+    }
+
+    #[test]
+    fn thumbnails_fit_in_the_box() {
+        let data = encode(1920, 1080, ImageFormat::Png);
+        let info = inspect(&data, &SCREENSHOT).unwrap();
+
+        let thumbnail = thumbnail(&data, &info).unwrap();
+        let thumbnail = inspect(&thumbnail, &SCREENSHOT).unwrap();
+        assert_eq!(thumbnail.format, ImageFormat::Jpeg);
+        assert_eq!((thumbnail.width, thumbnail.height), THUMBNAIL_SIZE);
+    }
+}
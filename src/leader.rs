@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt::time::interval;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, Statement};
+
+use crate::entities::leader_lease;
+use crate::error::AppError;
+use crate::shutdown::Shutdown;
+
+const LEASE_NAME: &str = "tasks";
+const LEASE_DURATION: Duration = Duration::from_secs(15);
+const RENEW_INTERVAL: Duration = Duration::from_secs(5);
+
+// Takes the lease over when it expired, extends it when we already hold it.
+// MySQL applies the assignments in order, so `expires_at` sees the new holder.
+const ACQUIRE_SQL: &str = r#"
+INSERT INTO leader_lease (name, holder, expires_at)
+VALUES (?, ?, DATE_ADD(UTC_TIMESTAMP(), INTERVAL ? SECOND))
+ON DUPLICATE KEY UPDATE
+    holder = IF(expires_at < UTC_TIMESTAMP() OR holder = VALUES(holder), VALUES(holder), holder),
+    expires_at = IF(holder = VALUES(holder), VALUES(expires_at), expires_at)
+"#;
+
+// Lets another instance take over right away instead of waiting for the lease to expire
+const RESIGN_SQL: &str = r#"
+UPDATE leader_lease SET expires_at = UTC_TIMESTAMP() WHERE name = ? AND holder = ?
+"#;
+
+/// Decides which instance runs the background tasks.
+pub struct Leader {
+    is_leader: AtomicBool,
+    election: Option<(Arc<DatabaseConnection>, String)>,
+}
+
+impl Leader {
+    /// Used when there is only one instance.
+    pub fn always() -> Arc<Self> {
+        Arc::new(Self {
+            is_leader: AtomicBool::new(true),
+            election: None,
+        })
+    }
+
+    /// Competes for a lease in the `leader_lease` table, renewing it while it is held.
+    pub fn elect(
+        conn: Arc<DatabaseConnection>,
+        instance: String,
+        shutdown: Arc<Shutdown>,
+    ) -> Arc<Self> {
+        let this = Arc::new(Self {
+            is_leader: AtomicBool::new(false),
+            election: Some((Arc::clone(&conn), instance.clone())),
+        });
+        Leader::spawn_renew(Arc::clone(&this), conn, instance, shutdown);
+        this
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+
+    /// Gives up the lease, called once the tasks of this instance stopped.
+    pub async fn resign(&self) {
+        let Some((conn, instance)) = &self.election else {
+            return;
+        };
+
+        if !self.is_leader.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::MySql,
+                RESIGN_SQL,
+                [LEASE_NAME.into(), instance.as_str().into()],
+            ))
+            .await
+        {
+            log::error!("Failed to release leader lease: {e}");
+        }
+    }
+
+    fn spawn_renew(
+        this: Arc<Self>,
+        conn: Arc<DatabaseConnection>,
+        instance: String,
+        shutdown: Arc<Shutdown>,
+    ) {
+        actix_web::rt::spawn(async move {
+            let mut interval = interval(RENEW_INTERVAL);
+
+            loop {
+                // The lease is released by `resign` or runs out on its own
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.triggered() => return,
+                }
+
+                // Without a confirmed lease another instance may already have taken over
+                let is_leader = acquire(&conn, &instance).await.unwrap_or_else(|e| {
+                    log::error!("Failed to renew leader lease: {e}");
+                    false
+                });
+
+                if this.is_leader.swap(is_leader, Ordering::Relaxed) != is_leader {
+                    match is_leader {
+                        true => log::info!("Instance {instance} became the leader"),
+                        false => log::info!("Instance {instance} is no longer the leader"),
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn acquire(conn: &DatabaseConnection, instance: &str) -> Result<bool, AppError> {
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::MySql,
+        ACQUIRE_SQL,
+        [
+            LEASE_NAME.into(),
+            instance.into(),
+            LEASE_DURATION.as_secs().into(),
+        ],
+    ))
+    .await?;
+
+    let lease = leader_lease::Entity::find_by_id(LEASE_NAME)
+        .one(conn)
+        .await?;
+
+    Ok(lease.is_some_and(|v| v.holder == instance))
+}
@@ -0,0 +1,367 @@
+use std::net::IpAddr;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DatabaseTransaction, DbErr, EntityTrait, QueryFilter, QuerySelect, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::entities::{login_attempts, login_throttle_locks, sea_orm_active_enums::LoginOutcome};
+
+// The audit log is kept this long
+const RETENTION: Duration = Duration::days(90);
+
+// Attempts still being checked count as well, so parallel guesses can not slip past the limit
+const FAILURES: [LoginOutcome; 4] = [
+    LoginOutcome::UnknownEmail,
+    LoginOutcome::BadPassword,
+    LoginOutcome::BadCode,
+    LoginOutcome::Pending,
+];
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct LoginThrottleConfig {
+    /// Failures older than this are forgotten
+    pub window_secs: u64,
+    /// Failures of an account before every further attempt has to wait
+    pub delay_after: u64,
+    /// Wait after `delay_after` failures, doubled with every further one
+    pub base_delay_secs: u64,
+    pub max_delay_secs: u64,
+    /// Failures of an account before it is locked for `lockout_secs`
+    pub lockout_after: u64,
+    pub lockout_secs: u64,
+    /// Same thresholds per address, higher since many users can share one
+    pub ip_delay_after: u64,
+    pub ip_lockout_after: u64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 15 * 60,
+            delay_after: 3,
+            base_delay_secs: 1,
+            max_delay_secs: 60,
+            lockout_after: 10,
+            lockout_secs: 15 * 60,
+            ip_delay_after: 20,
+            ip_lockout_after: 100,
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    fn wait(&self, failures: u64, delay_after: u64, lockout_after: u64) -> Option<Duration> {
+        if failures >= lockout_after {
+            return Some(Duration::seconds(self.lockout_secs as i64));
+        }
+
+        if failures < delay_after {
+            return None;
+        }
+
+        let doublings = (failures - delay_after).min(32) as u32;
+        let secs = self
+            .base_delay_secs
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay_secs);
+        Some(Duration::seconds(secs as i64))
+    }
+}
+
+/// Email the attempts are tracked under, so differently written addresses share a counter.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Login attempt, recorded as pending until its outcome is known. The locks of its email and
+/// address are only held while counting and while recording, not during the password check.
+pub struct Attempt {
+    id: i64,
+    email: String,
+    ip: Option<IpAddr>,
+    retry: Option<u64>,
+}
+
+impl Attempt {
+    /// Seconds the client has to wait before the next attempt, `None` if it may try now.
+    /// Applies to unknown emails the same way, the answer does not tell them apart.
+    pub fn retry_after(&self) -> Option<u64> {
+        self.retry
+    }
+
+    /// Replaces the pending record with the outcome.
+    pub async fn finish(
+        self,
+        db: &DatabaseConnection,
+        user_id: Option<i32>,
+        outcome: LoginOutcome,
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        lock(&txn, &self.email, self.ip).await?;
+
+        login_attempts::ActiveModel {
+            id: Set(self.id),
+            user_id: Set(user_id),
+            outcome: Set(outcome),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+
+        txn.commit().await
+    }
+}
+
+/// Counts the failures of the email and address and records the attempt as pending, one
+/// attempt at a time. Dropping the attempt without `finish` leaves it counted as a failure.
+pub async fn start(
+    db: &DatabaseConnection,
+    config: &LoginThrottleConfig,
+    email: &str,
+    ip: Option<IpAddr>,
+) -> Result<Attempt, DbErr> {
+    let txn = db.begin().await?;
+    lock(&txn, email, ip).await?;
+    let retry = check(&txn, config, email, ip).await?;
+
+    let pending = login_attempts::ActiveModel {
+        email: Set(email.to_owned()),
+        ip: Set(ip.map(|v| v.to_string())),
+        outcome: Set(LoginOutcome::Pending),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(Attempt {
+        id: pending.id,
+        email: email.to_owned(),
+        ip,
+        retry,
+    })
+}
+
+// Upserting takes an exclusive lock on the row, held until the transaction ends
+async fn lock(txn: &DatabaseTransaction, email: &str, ip: Option<IpAddr>) -> Result<(), DbErr> {
+    let now = Utc::now().naive_utc();
+    // Always in the same order, `email:` before `ip:`, so two attempts can not deadlock
+    let scopes = std::iter::once(format!("email:{email}")).chain(ip.map(|ip| format!("ip:{ip}")));
+
+    login_throttle_locks::Entity::insert_many(scopes.map(|scope| {
+        login_throttle_locks::ActiveModel {
+            scope: Set(scope),
+            last_attempt_at: Set(now),
+        }
+    }))
+    .on_conflict(
+        OnConflict::column(login_throttle_locks::Column::Scope)
+            .update_column(login_throttle_locks::Column::LastAttemptAt)
+            .to_owned(),
+    )
+    .exec_without_returning(txn)
+    .await?;
+
+    Ok(())
+}
+
+async fn check<C: ConnectionTrait>(
+    db: &C,
+    config: &LoginThrottleConfig,
+    email: &str,
+    ip: Option<IpAddr>,
+) -> Result<Option<u64>, DbErr> {
+    let now = Utc::now().naive_utc();
+    let window_start = now - Duration::seconds(config.window_secs as i64);
+
+    // A successful login starts the account over, the address keeps its count
+    let last_success: Option<NaiveDateTime> = login_attempts::Entity::find()
+        .select_only()
+        .column_as(Expr::col(login_attempts::Column::CreatedAt).max(), "last")
+        .filter(login_attempts::Column::Email.eq(email))
+        .filter(login_attempts::Column::Outcome.eq(LoginOutcome::Success))
+        .filter(login_attempts::Column::CreatedAt.gt(window_start))
+        .into_tuple()
+        .one(db)
+        .await?
+        .flatten();
+
+    let (count, last) = failures(
+        db,
+        Condition::all()
+            .add(login_attempts::Column::Email.eq(email))
+            .add(login_attempts::Column::CreatedAt.gt(last_success.unwrap_or(window_start))),
+    )
+    .await?;
+    let mut retry = retry_after(
+        config.wait(count, config.delay_after, config.lockout_after),
+        last,
+        now,
+    );
+
+    if let Some(ip) = ip {
+        let (count, last) = failures(
+            db,
+            Condition::all()
+                .add(login_attempts::Column::Ip.eq(ip.to_string()))
+                .add(login_attempts::Column::CreatedAt.gt(window_start)),
+        )
+        .await?;
+        let ip_retry = retry_after(
+            config.wait(count, config.ip_delay_after, config.ip_lockout_after),
+            last,
+            now,
+        );
+        retry = retry.max(ip_retry);
+    }
+
+    Ok(retry)
+}
+
+async fn failures<C: ConnectionTrait>(
+    db: &C,
+    filter: Condition,
+) -> Result<(u64, Option<NaiveDateTime>), DbErr> {
+    let res: Option<(i64, Option<NaiveDateTime>)> = login_attempts::Entity::find()
+        .select_only()
+        .column_as(Expr::col(login_attempts::Column::Id).count(), "count")
+        .column_as(Expr::col(login_attempts::Column::CreatedAt).max(), "last")
+        .filter(filter)
+        .filter(login_attempts::Column::Outcome.is_in(FAILURES))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(res.map_or((0, None), |(count, last)| (count as u64, last)))
+}
+
+fn retry_after(
+    wait: Option<Duration>,
+    last: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> Option<u64> {
+    let remaining = (last? + wait?) - now;
+    (remaining > Duration::zero()).then(|| remaining.num_seconds().max(1) as u64)
+}
+
+pub async fn purge(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let cutoff = Utc::now().naive_utc() - RETENTION;
+
+    let res = login_attempts::Entity::delete_many()
+        .filter(login_attempts::Column::CreatedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    // A deleted lock row is simply inserted again by the next attempt
+    login_throttle_locks::Entity::delete_many()
+        .filter(login_throttle_locks::Column::LastAttemptAt.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ConnectOptions, Database, DbBackend, Schema};
+
+    use super::*;
+
+    #[test]
+    fn waits_double_until_the_lockout() {
+        let config = LoginThrottleConfig::default();
+        let wait = |failures| config.wait(failures, config.delay_after, config.lockout_after);
+
+        assert_eq!(wait(2), None);
+        assert_eq!(wait(3), Some(Duration::seconds(1)));
+        assert_eq!(wait(5), Some(Duration::seconds(4)));
+        assert_eq!(wait(9), Some(Duration::seconds(60)));
+        assert_eq!(wait(10), Some(Duration::seconds(15 * 60)));
+    }
+
+    // An in-memory SQLite database stands in for MySQL
+    async fn stand_in() -> DatabaseConnection {
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1);
+        let conn = Database::connect(options).await.unwrap();
+
+        let schema = Schema::new(DbBackend::Sqlite);
+        for table in [
+            schema.create_table_from_entity(login_attempts::Entity),
+            schema.create_table_from_entity(login_throttle_locks::Entity),
+        ] {
+            conn.execute(conn.get_database_backend().build(&table))
+                .await
+                .unwrap();
+        }
+        conn
+    }
+
+    #[actix_web::test]
+    async fn pending_attempts_count_until_they_finish() {
+        let db = stand_in().await;
+        let config = LoginThrottleConfig {
+            delay_after: 1,
+            base_delay_secs: 60,
+            ..Default::default()
+        };
+        let ip = Some(IpAddr::from([127, 0, 0, 1]));
+
+        let first = start(&db, &config, "steve@example.com", ip).await.unwrap();
+        assert_eq!(first.retry_after(), None);
+
+        // The first password is still being checked
+        let second = start(&db, &config, "steve@example.com", ip).await.unwrap();
+        assert!(second.retry_after().is_some());
+        second
+            .finish(&db, None, LoginOutcome::Throttled)
+            .await
+            .unwrap();
+
+        first
+            .finish(&db, Some(1), LoginOutcome::Success)
+            .await
+            .unwrap();
+        let third = start(&db, &config, "steve@example.com", ip).await.unwrap();
+        assert_eq!(third.retry_after(), None);
+
+        let outcomes: Vec<LoginOutcome> = login_attempts::Entity::find()
+            .select_only()
+            .column(login_attempts::Column::Outcome)
+            .into_tuple()
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcomes,
+            [
+                LoginOutcome::Success,
+                LoginOutcome::Throttled,
+                LoginOutcome::Pending
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_counts_from_the_last_failure() {
+        let now = Utc::now().naive_utc();
+        let wait = Some(Duration::seconds(10));
+
+        assert_eq!(
+            retry_after(wait, Some(now - Duration::seconds(4)), now),
+            Some(6)
+        );
+        assert_eq!(
+            retry_after(wait, Some(now - Duration::seconds(10)), now),
+            None
+        );
+        assert_eq!(retry_after(None, Some(now), now), None);
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use super::{Mail, Mailer, SendFuture};
+use crate::utils::random_hex;
+
+/// Development sink, nothing leaves the machine.
+pub struct FileMailer {
+    dir: Option<PathBuf>,
+}
+
+impl FileMailer {
+    pub fn create(dir: Option<&str>) -> Arc<Self> {
+        Arc::new(Self {
+            dir: dir.map(PathBuf::from),
+        })
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, mail: Mail) -> SendFuture<'_> {
+        Box::pin(async move {
+            // Bodies carry tokens, they are only kept where `dir` points
+            let Some(dir) = &self.dir else {
+                log::info!("Mail to {}: {}", mail.to, mail.subject);
+                return Ok(());
+            };
+
+            std::fs::create_dir_all(dir)?;
+            let name = format!("{}-{}.eml", Utc::now().timestamp_millis(), random_hex(4));
+            std::fs::write(
+                dir.join(name),
+                format!(
+                    "To: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+                    mail.to, mail.subject, mail.body
+                ),
+            )?;
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use super::{Mail, Mailer, SendFuture};
+
+/// Keeps every mail, so tests can read what would have been sent.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Mail>>,
+}
+
+impl MemoryMailer {
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn sent(&self) -> Vec<Mail> {
+        self.sent.lock().clone()
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, mail: Mail) -> SendFuture<'_> {
+        self.sent.lock().push(mail);
+        Box::pin(async { Ok(()) })
+    }
+}
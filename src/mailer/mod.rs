@@ -0,0 +1,108 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::error::AppError;
+
+pub mod file;
+#[cfg(test)]
+pub mod memory;
+pub mod smtp;
+
+pub use file::FileMailer;
+pub use smtp::SmtpMailer;
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, mail: Mail) -> SendFuture<'_>;
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailerKind {
+    /// Mails are written to `dir`, or only logged without one
+    #[default]
+    File,
+    Smtp,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub tls: SmtpTls,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_owned(),
+            port: 587,
+            username: String::new(),
+            password: String::new(),
+            tls: SmtpTls::StartTls,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct MailConfig {
+    pub backend: MailerKind,
+    pub from: String,
+    /// Address of the frontend, links in mails point there
+    pub app_url: String,
+    pub dir: Option<String>,
+    pub smtp: SmtpConfig,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            backend: MailerKind::File,
+            from: "CraftList <no-reply@localhost>".to_owned(),
+            app_url: "http://localhost:3000".to_owned(),
+            dir: None,
+            smtp: SmtpConfig::default(),
+        }
+    }
+}
+
+pub fn create(config: &MailConfig) -> Result<Arc<dyn Mailer>, AppError> {
+    Ok(match config.backend {
+        MailerKind::File => FileMailer::create(config.dir.as_deref()),
+        MailerKind::Smtp => SmtpMailer::create(config)?,
+    })
+}
+
+/// Sends the mail without making the request wait on it, failures are only logged.
+pub fn dispatch(mailer: Arc<dyn Mailer>, mail: Mail) {
+    actix_web::rt::spawn(async move {
+        let to = mail.to.clone();
+        if let Err(e) = mailer.send(mail).await {
+            log::error!("Failed to send mail to {to}: {e}");
+        }
+    });
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{Mail, MailConfig, Mailer, SendFuture, SmtpTls};
+use crate::error::AppError;
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn create(config: &MailConfig) -> Result<Arc<Self>, AppError> {
+        let smtp = &config.smtp;
+        let builder = match smtp.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+                .map_err(|e| AppError::Mail(e.to_string()))?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)
+                .map_err(|e| AppError::Mail(e.to_string()))?,
+        };
+
+        let mut builder = builder.port(smtp.port);
+        if !smtp.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                smtp.username.clone(),
+                smtp.password.clone(),
+            ));
+        }
+
+        let from = config
+            .from
+            .parse()
+            .map_err(|e| AppError::Mail(format!("Invalid sender address: {e}")))?;
+
+        Ok(Arc::new(Self {
+            transport: builder.build(),
+            from,
+        }))
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, mail: Mail) -> SendFuture<'_> {
+        Box::pin(async move {
+            let to: Mailbox = mail
+                .to
+                .parse()
+                .map_err(|e| AppError::Mail(format!("Invalid recipient address: {e}")))?;
+
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(mail.subject)
+                .header(ContentType::TEXT_PLAIN)
+                .body(mail.body)
+                .map_err(|e| AppError::Mail(e.to_string()))?;
+
+            self.transport
+                .send(message)
+                .await
+                .map_err(|e| AppError::Mail(e.to_string()))?;
+
+            Ok(())
+        })
+    }
+}
@@ -3,28 +3,57 @@ mod controllers;
 mod docs;
 mod entities;
 mod error;
+mod events;
+mod images;
+mod jwt;
+mod leader;
+mod login_throttle;
+mod mailer;
+mod microsoft;
+mod permissions;
+mod player_stats;
+mod pubsub;
 mod sender;
+mod shutdown;
+mod storage;
+mod supervisor;
 mod tasks;
+mod totp;
 mod utils;
+mod validation;
+mod version_sync;
 
-use std::{fs::File, io::Read, sync::Arc};
+use std::{collections::HashMap, fs::File, io::Read, sync::Arc, time::Duration};
 
 use actix_cors::Cors;
+use actix_files::Files;
 use actix_web::{
     http::header,
     middleware,
     web::{self, Data},
-    App, HttpResponse, HttpServer, Responder,
+    App, HttpRequest, HttpResponse, HttpServer, Responder,
 };
 use docs::ApiDoc;
 use error::AppError;
+use events::EventBus;
+use jwt::{JwtConfig, JwtKeys};
+use leader::Leader;
+use login_throttle::LoginThrottleConfig;
+use mailer::MailConfig;
+use microsoft::{MicrosoftClient, MicrosoftConfig};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{ConnectOptions, Database};
-use sender::Broadcaster;
+use player_stats::PlayersRetentionConfig;
+use pubsub::{BackendKind, PubSubConfig};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use sender::{Broadcaster, SseConfig};
 use serde::Deserialize;
+use shutdown::{Shutdown, ShutdownConfig};
+use storage::{StorageConfig, StorageKind};
 use tasks::spawn;
+use utils::client_ip;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
+use version_sync::{VersionSync, VersionSyncConfig};
 
 #[derive(Deserialize, Clone)]
 struct Config {
@@ -33,8 +62,36 @@ struct Config {
     threads: usize,
     database_table: String,
     database_url: String,
+    #[allow(dead_code)]
     log: u32,
     json_token: String,
+    /// Take client addresses from `X-Forwarded-For`, only enable behind a trusted proxy.
+    /// Used by the SSE connection limits and login throttling
+    #[serde(default)]
+    trust_forwarded_for: bool,
+    #[serde(default)]
+    pubsub: PubSubConfig,
+    #[serde(default)]
+    sse: SseConfig,
+    #[serde(default)]
+    shutdown: ShutdownConfig,
+    #[serde(default)]
+    mail: MailConfig,
+    #[serde(default)]
+    login: LoginThrottleConfig,
+    #[serde(default)]
+    microsoft: MicrosoftConfig,
+    #[serde(default)]
+    jwt: JwtConfig,
+    #[serde(default)]
+    version_sync: VersionSyncConfig,
+    #[serde(default)]
+    storage: StorageConfig,
+    #[serde(default)]
+    players_retention: PlayersRetentionConfig,
+    /// Interval overrides per background task, in seconds
+    #[serde(default)]
+    tasks: HashMap<String, u64>,
 }
 
 fn load_config() -> Config {
@@ -44,6 +101,12 @@ fn load_config() -> Config {
     serde_json::from_str(&str).unwrap()
 }
 
+fn instance_id() -> String {
+    use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+    format!("{}-{:08x}", std::process::id(), OsRng.next_u32())
+}
+
 #[actix_web::main]
 async fn main() -> Result<(), AppError> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
@@ -56,16 +119,50 @@ async fn main() -> Result<(), AppError> {
 
     Migrator::up(&*conn, None).await.unwrap();
 
-    let broadcaster = Broadcaster::create();
+    let instance = instance_id();
+    log::info!("Starting instance {instance}");
+
+    let shutdown = Shutdown::create();
+    let retry = Duration::from_millis(config.shutdown.retry_ms);
+
+    let broadcaster = Broadcaster::create(config.sse.clone(), retry, Arc::clone(&shutdown));
+    let mailer = mailer::create(&config.mail)?;
+    let storage = storage::create(&config.storage)?;
+    let microsoft = Arc::new(MicrosoftClient::new(config.microsoft.clone()));
+    let version_sync = Arc::new(VersionSync::new(config.version_sync.clone()));
+    let keys = Arc::new(JwtKeys::load(&config.jwt, config.json_token.as_bytes())?);
+    let backend = pubsub::create(
+        &config.pubsub,
+        Arc::clone(&conn),
+        &instance,
+        Arc::clone(&shutdown),
+    )
+    .await?;
+    let events = EventBus::create(backend, Arc::clone(&broadcaster), Arc::clone(&shutdown));
+
+    // Only one of the instances sharing the database runs the tasks
+    let leader = match config.pubsub.backend {
+        BackendKind::Memory => Leader::always(),
+        BackendKind::Mysql => Leader::elect(Arc::clone(&conn), instance, Arc::clone(&shutdown)),
+    };
 
     // Spawn Tasks
-    let broadcaster_clone = Arc::clone(&broadcaster);
-    spawn(broadcaster_clone, Arc::clone(&conn));
+    let tasks = spawn(
+        Arc::clone(&events),
+        Arc::clone(&leader),
+        Arc::clone(&shutdown),
+        Arc::clone(&conn),
+        Arc::clone(&version_sync),
+        config.players_retention.clone(),
+        &config.tasks,
+    );
 
     let openapi = ApiDoc::openapi();
 
     let config_clone = config.clone();
-    HttpServer::new(move || {
+    let conn_clone = Arc::clone(&conn);
+    let broadcaster_clone = Arc::clone(&broadcaster);
+    let server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
             .allow_any_method()
@@ -73,11 +170,24 @@ async fn main() -> Result<(), AppError> {
 
         App::new()
             .wrap(cors)
-            .app_data(Data::new(Arc::clone(&conn)))
+            .app_data(Data::new(Arc::clone(&conn_clone)))
             .app_data(Data::new(config_clone.clone()))
-            .app_data(Data::new(Arc::clone(&broadcaster)))
+            .app_data(Data::new(Arc::clone(&broadcaster_clone)))
+            .app_data(Data::new(Arc::clone(&events)))
+            .app_data(Data::new(Arc::clone(&tasks)))
+            .app_data(Data::new(Arc::clone(&mailer)))
+            .app_data(Data::new(Arc::clone(&microsoft)))
+            .app_data(Data::new(Arc::clone(&keys)))
+            .app_data(Data::new(Arc::clone(&version_sync)))
+            .app_data(Data::new(Arc::clone(&storage)))
             .wrap(middleware::Logger::default().log_target("CraftList"))
             .configure(controllers::configure())
+            .configure(|service| {
+                // S3 buckets serve their files themselves
+                if config_clone.storage.backend == StorageKind::Local {
+                    service.service(Files::new("/uploads", &config_clone.storage.dir));
+                }
+            })
             .route("/events", web::get().to(sse_client))
             .route("/send", web::get().to(send))
             .route(
@@ -91,18 +201,47 @@ async fn main() -> Result<(), AppError> {
             .service(SwaggerUi::new("/docs/{_:.*}").url("/api-doc/openapi.json", openapi.clone()))
     })
     .workers(config.threads)
-    .bind((config.addr, config.port))?
-    .run()
-    .await?;
+    .shutdown_timeout(config.shutdown.timeout_secs)
+    // Signals are handled below, SSE clients have to be closed before the workers stop
+    .disable_signals()
+    .bind((config.addr.clone(), config.port))?
+    .run();
+
+    let handle = server.handle();
+    let shutdown_clone = Arc::clone(&shutdown);
+    actix_web::rt::spawn(async move {
+        shutdown::signal().await;
+        log::info!("Shutting down");
+
+        shutdown_clone.trigger();
+        broadcaster.shutdown();
+        handle.stop(true).await;
+    });
+
+    server.await?;
+
+    let deadline = Duration::from_secs(config.shutdown.timeout_secs);
+    if !shutdown.drain(deadline).await {
+        log::warn!("Background tasks did not finish within {deadline:?}");
+    }
+    leader.resign().await;
+    // The clone shares the pool with every other handle, closing it closes all of them
+    DatabaseConnection::clone(&conn).close().await?;
 
     Ok(())
 }
 
-pub async fn sse_client(broadcaster: web::Data<Arc<Broadcaster>>) -> impl Responder {
-    broadcaster.new_client().await
+async fn sse_client(
+    broadcaster: web::Data<Arc<Broadcaster>>,
+    config: web::Data<Config>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let ip = client_ip(&req, config.trust_forwarded_for);
+
+    Ok(broadcaster.new_client(ip)?)
 }
 
 pub async fn send(broadcaster: web::Data<Arc<Broadcaster>>) -> impl Responder {
-    broadcaster.broadcast("Hello").await;
+    broadcaster.broadcast("Hello");
     HttpResponse::Ok().finish()
 }
@@ -0,0 +1,346 @@
+use actix_web::error::{ErrorBadGateway, ErrorBadRequest};
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::error::AppError;
+
+/// Endpoints are configurable so a local mock identity provider can stand in for Microsoft.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct MicrosoftConfig {
+    /// Sign in with Microsoft is disabled while this is empty
+    pub client_id: String,
+    pub client_secret: String,
+    /// Page the user comes back to, it passes `code` and `state` on to `/auth/microsoft/callback`
+    pub redirect_uri: String,
+    pub scope: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub xbox_auth_url: String,
+    pub xsts_url: String,
+    pub minecraft_login_url: String,
+    pub minecraft_profile_url: String,
+}
+
+impl Default for MicrosoftConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            redirect_uri: "http://localhost:3000/microsoft/callback".to_owned(),
+            scope: "XboxLive.signin offline_access".to_owned(),
+            authorize_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize"
+                .to_owned(),
+            token_url: "https://login.microsoftonline.com/consumers/oauth2/v2.0/token".to_owned(),
+            xbox_auth_url: "https://user.auth.xboxlive.com/user/authenticate".to_owned(),
+            xsts_url: "https://xsts.auth.xboxlive.com/xsts/authorize".to_owned(),
+            minecraft_login_url: "https://api.minecraftservices.com/authentication/login_with_xbox"
+                .to_owned(),
+            minecraft_profile_url: "https://api.minecraftservices.com/minecraft/profile".to_owned(),
+        }
+    }
+}
+
+pub struct MinecraftProfile {
+    /// Dashed form
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct XboxResponse {
+    token: String,
+    display_claims: XboxClaims,
+}
+
+#[derive(Deserialize)]
+struct XboxClaims {
+    xui: Vec<XboxUser>,
+}
+
+#[derive(Deserialize)]
+struct XboxUser {
+    uhs: String,
+}
+
+#[derive(Deserialize)]
+struct ProfileResponse {
+    id: String,
+    name: String,
+}
+
+/// Microsoft account -> Xbox Live -> XSTS -> Minecraft services.
+pub struct MicrosoftClient {
+    http: Client,
+    config: MicrosoftConfig,
+}
+
+impl MicrosoftClient {
+    pub fn new(config: MicrosoftConfig) -> Self {
+        Self {
+            http: Client::new(),
+            config,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.config.client_id.is_empty()
+    }
+
+    pub fn authorize_url(&self, state: &str) -> Result<String, AppError> {
+        let url = Url::parse_with_params(
+            &self.config.authorize_url,
+            [
+                ("client_id", self.config.client_id.as_str()),
+                ("response_type", "code"),
+                ("redirect_uri", self.config.redirect_uri.as_str()),
+                ("scope", self.config.scope.as_str()),
+                ("state", state),
+            ],
+        )
+        .map_err(|e| ErrorBadGateway(format!("Invalid authorize url: {e}")))?;
+
+        Ok(url.into())
+    }
+
+    /// Follows the authorization code through to the Minecraft profile it owns.
+    pub async fn minecraft_profile(&self, code: &str) -> Result<MinecraftProfile, AppError> {
+        let token: TokenResponse = read(
+            self.http
+                .post(&self.config.token_url)
+                .form(&[
+                    ("client_id", self.config.client_id.as_str()),
+                    ("client_secret", self.config.client_secret.as_str()),
+                    ("grant_type", "authorization_code"),
+                    ("code", code),
+                    ("redirect_uri", self.config.redirect_uri.as_str()),
+                ])
+                .send()
+                .await,
+            "Microsoft rejected the authorization code",
+        )
+        .await?;
+
+        let xbox: XboxResponse = read(
+            self.http
+                .post(&self.config.xbox_auth_url)
+                .json(&json!({
+                    "Properties": {
+                        "AuthMethod": "RPS",
+                        "SiteName": "user.auth.xboxlive.com",
+                        "RpsTicket": format!("d={}", token.access_token)
+                    },
+                    "RelyingParty": "http://auth.xboxlive.com",
+                    "TokenType": "JWT"
+                }))
+                .send()
+                .await,
+            "Xbox Live sign in failed",
+        )
+        .await?;
+
+        let xsts: XboxResponse = read(
+            self.http
+                .post(&self.config.xsts_url)
+                .json(&json!({
+                    "Properties": {
+                        "SandboxId": "RETAIL",
+                        "UserTokens": [xbox.token]
+                    },
+                    "RelyingParty": "rp://api.minecraftservices.com/",
+                    "TokenType": "JWT"
+                }))
+                .send()
+                .await,
+            "This Microsoft account has no Xbox profile",
+        )
+        .await?;
+
+        let Some(user) = xsts.display_claims.xui.first() else {
+            return Err(ErrorBadGateway("Xbox Live returned no user").into());
+        };
+
+        let minecraft: TokenResponse = read(
+            self.http
+                .post(&self.config.minecraft_login_url)
+                .json(&json!({
+                    "identityToken": format!("XBL3.0 x={};{}", user.uhs, xsts.token)
+                }))
+                .send()
+                .await,
+            "Minecraft services sign in failed",
+        )
+        .await?;
+
+        let profile: ProfileResponse = read(
+            self.http
+                .get(&self.config.minecraft_profile_url)
+                .bearer_auth(minecraft.access_token)
+                .send()
+                .await,
+            "This Microsoft account does not own Minecraft",
+        )
+        .await?;
+
+        Ok(MinecraftProfile {
+            uuid: dashed_uuid(&profile.id),
+            name: profile.name,
+        })
+    }
+}
+
+// Client errors mean the account can not be used, anything else is the provider failing
+async fn read<T: DeserializeOwned>(
+    res: Result<reqwest::Response, reqwest::Error>,
+    rejected: &'static str,
+) -> Result<T, AppError> {
+    let res = res.map_err(|e| ErrorBadGateway(format!("Identity provider unreachable: {e}")))?;
+
+    if res.status().is_client_error() {
+        return Err(ErrorBadRequest(rejected).into());
+    }
+
+    if !res.status().is_success() {
+        return Err(ErrorBadGateway(format!("Identity provider returned {}", res.status())).into());
+    }
+
+    res.json()
+        .await
+        .map_err(|e| ErrorBadGateway(format!("Unexpected identity provider response: {e}")).into())
+}
+
+fn dashed_uuid(id: &str) -> String {
+    if id.len() != 32 {
+        return id.to_owned();
+    }
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &id[..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn expect(valid: bool, body: Value) -> HttpResponse {
+        match valid {
+            true => HttpResponse::Ok().json(body),
+            false => HttpResponse::BadRequest().finish(),
+        }
+    }
+
+    // Stands in for Microsoft, Xbox Live and Minecraft services, each step only accepts
+    // the token handed out by the one before
+    fn mock_idp(owns_minecraft: bool) -> MicrosoftConfig {
+        let server = HttpServer::new(move || {
+            App::new()
+                .route(
+                    "/token",
+                    web::post().to(|form: web::Form<HashMap<String, String>>| async move {
+                        expect(
+                            form.get("code").is_some_and(|v| v == "auth-code"),
+                            json!({ "access_token": "microsoft-token" }),
+                        )
+                    }),
+                )
+                .route(
+                    "/xbox",
+                    web::post().to(|body: web::Json<Value>| async move {
+                        expect(
+                            body["Properties"]["RpsTicket"] == "d=microsoft-token",
+                            json!({ "Token": "xbox-token", "DisplayClaims": { "xui": [{ "uhs": "hash" }] } }),
+                        )
+                    }),
+                )
+                .route(
+                    "/xsts",
+                    web::post().to(|body: web::Json<Value>| async move {
+                        expect(
+                            body["Properties"]["UserTokens"][0] == "xbox-token",
+                            json!({ "Token": "xsts-token", "DisplayClaims": { "xui": [{ "uhs": "hash" }] } }),
+                        )
+                    }),
+                )
+                .route(
+                    "/minecraft/login",
+                    web::post().to(|body: web::Json<Value>| async move {
+                        expect(
+                            body["identityToken"] == "XBL3.0 x=hash;xsts-token",
+                            json!({ "access_token": "minecraft-token" }),
+                        )
+                    }),
+                )
+                .route(
+                    "/minecraft/profile",
+                    web::get().to(move |req: HttpRequest| async move {
+                        if !owns_minecraft {
+                            return HttpResponse::NotFound().finish();
+                        }
+
+                        let authorized = req
+                            .headers()
+                            .get("authorization")
+                            .is_some_and(|v| v == "Bearer minecraft-token");
+                        expect(
+                            authorized,
+                            json!({ "id": "069a79f444e94726a5befca90e38aaf5", "name": "Notch" }),
+                        )
+                    }),
+                )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+
+        MicrosoftConfig {
+            client_id: "client".to_owned(),
+            token_url: format!("{url}/token"),
+            xbox_auth_url: format!("{url}/xbox"),
+            xsts_url: format!("{url}/xsts"),
+            minecraft_login_url: format!("{url}/minecraft/login"),
+            minecraft_profile_url: format!("{url}/minecraft/profile"),
+            ..MicrosoftConfig::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn follows_the_code_to_the_minecraft_profile() {
+        let client = MicrosoftClient::new(mock_idp(true));
+
+        let profile = client.minecraft_profile("auth-code").await.unwrap();
+        assert_eq!(profile.uuid, "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(profile.name, "Notch");
+
+        let rejected = client.minecraft_profile("other-code").await.err().unwrap();
+        assert_eq!(rejected.status_code(), 400);
+    }
+
+    #[actix_web::test]
+    async fn rejects_accounts_without_minecraft() {
+        let client = MicrosoftClient::new(mock_idp(false));
+
+        let error = client.minecraft_profile("auth-code").await.err().unwrap();
+        assert_eq!(error.status_code(), 400);
+        assert!(error.to_string().contains("does not own Minecraft"));
+    }
+}
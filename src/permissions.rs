@@ -0,0 +1,92 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entities::{sea_orm_active_enums::Role, user_roles};
+use crate::totp;
+
+/// What a role allows, carried in the access token so checks need no database.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Grant and revoke roles
+    ManageRoles,
+    /// Background tasks and connection metrics
+    ViewSystem,
+    /// Hide and restore servers and reviews
+    Moderate,
+    /// Categories and versions
+    ManageCatalog,
+    ManageAds,
+}
+
+pub fn role_permissions(role: Role) -> &'static [Permission] {
+    match role {
+        Role::Admin => &[
+            Permission::ManageRoles,
+            Permission::ViewSystem,
+            Permission::Moderate,
+            Permission::ManageCatalog,
+            Permission::ManageAds,
+        ],
+        Role::Moderator => &[Permission::Moderate],
+        Role::ContentEditor => &[Permission::ManageCatalog],
+        Role::AdsManager => &[Permission::ManageAds],
+    }
+}
+
+pub async fn find_roles<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<Vec<Role>, DbErr> {
+    let mut roles: Vec<Role> = user_roles::Entity::find()
+        .select_only()
+        .column(user_roles::Column::Role)
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    roles.sort();
+    Ok(roles)
+}
+
+/// Roles and permissions put in a new access token.
+pub struct Grants {
+    pub roles: Vec<Role>,
+    pub permissions: Vec<Permission>,
+    /// Staff without two-factor authentication, their permissions are withheld until it is enabled
+    pub totp_enrollment_required: bool,
+}
+
+pub async fn grants<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<Grants, DbErr> {
+    let roles = find_roles(db, user_id).await?;
+
+    if roles.is_empty() {
+        return Ok(Grants {
+            roles,
+            permissions: Vec::new(),
+            totp_enrollment_required: false,
+        });
+    }
+
+    if totp::find_enabled(db, user_id).await?.is_none() {
+        return Ok(Grants {
+            roles,
+            permissions: Vec::new(),
+            totp_enrollment_required: true,
+        });
+    }
+
+    let mut permissions: Vec<Permission> = roles
+        .iter()
+        .flat_map(|role| role_permissions(*role).iter().copied())
+        .collect();
+    permissions.sort();
+    permissions.dedup();
+
+    Ok(Grants {
+        roles,
+        permissions,
+        totp_enrollment_required: false,
+    })
+}
@@ -0,0 +1,659 @@
+use actix_web::error::ErrorBadRequest;
+use chrono::{DateTime, Duration, NaiveDateTime, Timelike, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait,
+    FromQueryResult, QuerySelect, Statement, Value,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::entities::{players_graph, players_graph_daily, players_graph_hourly};
+use crate::error::AppError;
+
+/// Longest range a single request can cover, in buckets.
+const MAX_BUCKETS: i64 = 2000;
+/// Pings run every 5 minutes, totals add up one sample of each server per slot.
+const SLOT_SECS: i64 = 5 * 60;
+/// Rollups catching up, like on the first run, insert at most this much per statement.
+const ROLLUP_CHUNK: Duration = Duration::days(1);
+
+// Every table as rows of server, slot start in seconds and the rollup columns
+const RAW_SAMPLES_SQL: &str =
+    "SELECT server_id, TIMESTAMPDIFF(SECOND, '1970-01-01', date) DIV 300 * 300 AS slot,
+    1 AS samples, online, IF(online, players_online, NULL) AS min_players,
+    IF(online, players_online, NULL) AS max_players, IF(online, players_online, 0) AS total_players
+    FROM players_graph WHERE date >= ? AND date < ?";
+const HOURLY_SAMPLES_SQL: &str =
+    "SELECT server_id, TIMESTAMPDIFF(SECOND, '1970-01-01', date) AS slot,
+    samples, online, min_players, max_players, total_players
+    FROM players_graph_hourly WHERE date >= ? AND date < ?";
+const DAILY_SAMPLES_SQL: &str =
+    "SELECT server_id, TIMESTAMPDIFF(SECOND, '1970-01-01', date) AS slot,
+    samples, online, min_players, max_players, total_players
+    FROM players_graph_daily WHERE date >= ? AND date < ?";
+
+const HOURLY_ROLLUP_SQL: &str = "INSERT INTO players_graph_hourly
+    (server_id, date, samples, online, min_players, max_players, total_players)
+    SELECT server_id, DATE_FORMAT(date, '%Y-%m-%d %H:00:00') AS hour, COUNT(*), SUM(online),
+    MIN(IF(online, players_online, NULL)), MAX(IF(online, players_online, NULL)),
+    SUM(IF(online, players_online, 0))
+    FROM players_graph WHERE date >= ? AND date < ?
+    GROUP BY server_id, hour";
+const DAILY_ROLLUP_SQL: &str = "INSERT INTO players_graph_daily
+    (server_id, date, samples, online, min_players, max_players, total_players)
+    SELECT server_id, DATE(date) AS day, SUM(samples), SUM(online),
+    MIN(min_players), MAX(max_players), SUM(total_players)
+    FROM players_graph_hourly WHERE date >= ? AND date < ?
+    GROUP BY server_id, day";
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct PlayersRetentionConfig {
+    /// Raw samples are kept this long, older ones only remain in the hourly and daily tables
+    pub raw_days: u64,
+    /// Hourly rollups are kept this long, daily ones are never deleted
+    pub hourly_days: u64,
+    /// Rows removed per delete, keeps each statement and its locks short
+    pub batch_size: u64,
+}
+
+impl Default for PlayersRetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_days: 7,
+            hourly_days: 90,
+            batch_size: 5000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default)]
+pub enum Bucket {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[default]
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "1d")]
+    Day,
+}
+
+impl Bucket {
+    pub fn secs(self) -> i64 {
+        match self {
+            Bucket::FiveMinutes => SLOT_SECS,
+            Bucket::Hour => 60 * 60,
+            Bucket::Day => 24 * 60 * 60,
+        }
+    }
+
+    /// Range covered when the request has no `from`.
+    fn default_span(self) -> Duration {
+        match self {
+            Bucket::FiveMinutes => Duration::days(1),
+            Bucket::Hour => Duration::days(7),
+            Bucket::Day => Duration::days(90),
+        }
+    }
+
+    /// How far back the finest table this bucket reads is kept, `None` for the daily rollups.
+    fn retention(self, config: &PlayersRetentionConfig) -> Option<(u64, &'static str)> {
+        match self {
+            Bucket::FiveMinutes => Some((config.raw_days, "1h")),
+            Bucket::Hour => Some((config.hourly_days, "1d")),
+            Bucket::Day => None,
+        }
+    }
+
+    /// Tables read for this bucket, coarsest first, each one only up to where it was rolled up.
+    fn sources(self) -> &'static [Source] {
+        match self {
+            Bucket::FiveMinutes => &[Source::Raw],
+            Bucket::Hour => &[Source::Hourly, Source::Raw],
+            Bucket::Day => &[Source::Daily, Source::Hourly, Source::Raw],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Source {
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl Source {
+    fn sql(self) -> &'static str {
+        match self {
+            Source::Raw => RAW_SAMPLES_SQL,
+            Source::Hourly => HOURLY_SAMPLES_SQL,
+            Source::Daily => DAILY_SAMPLES_SQL,
+        }
+    }
+
+    /// End of the rolled up data, `None` for raw samples and empty rollups.
+    async fn rolled_until<C: ConnectionTrait>(
+        self,
+        db: &C,
+    ) -> Result<Option<NaiveDateTime>, DbErr> {
+        let latest = match self {
+            Source::Raw => return Ok(None),
+            Source::Hourly => latest_hour(db).await?,
+            Source::Daily => latest_day(db).await?,
+        };
+
+        Ok(latest.map(|v| match self {
+            Source::Daily => v + Duration::days(1),
+            _ => v + Duration::hours(1),
+        }))
+    }
+}
+
+async fn latest_hour<C: ConnectionTrait>(db: &C) -> Result<Option<NaiveDateTime>, DbErr> {
+    let latest = players_graph_hourly::Entity::find()
+        .select_only()
+        .column_as(players_graph_hourly::Column::Date.max(), "date")
+        .into_tuple::<Option<NaiveDateTime>>()
+        .one(db)
+        .await?;
+
+    Ok(latest.flatten())
+}
+
+async fn earliest_sample<C: ConnectionTrait>(db: &C) -> Result<Option<NaiveDateTime>, DbErr> {
+    let earliest = players_graph::Entity::find()
+        .select_only()
+        .column_as(players_graph::Column::Date.min(), "date")
+        .into_tuple::<Option<NaiveDateTime>>()
+        .one(db)
+        .await?;
+
+    Ok(earliest.flatten())
+}
+
+async fn earliest_hour<C: ConnectionTrait>(db: &C) -> Result<Option<NaiveDateTime>, DbErr> {
+    let earliest = players_graph_hourly::Entity::find()
+        .select_only()
+        .column_as(players_graph_hourly::Column::Date.min(), "date")
+        .into_tuple::<Option<NaiveDateTime>>()
+        .one(db)
+        .await?;
+
+    Ok(earliest.flatten())
+}
+
+async fn latest_day<C: ConnectionTrait>(db: &C) -> Result<Option<NaiveDateTime>, DbErr> {
+    let latest = players_graph_daily::Entity::find()
+        .select_only()
+        .column_as(players_graph_daily::Column::Date.max(), "date")
+        .into_tuple::<Option<NaiveDateTime>>()
+        .one(db)
+        .await?;
+
+    Ok(latest.flatten())
+}
+
+/// Clock of every sample, range and rollup, UTC whatever the time zone of the database session.
+pub fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn start_of_hour(date: NaiveDateTime) -> NaiveDateTime {
+    date.with_minute(0)
+        .and_then(|v| v.with_second(0))
+        .and_then(|v| v.with_nanosecond(0))
+        .unwrap_or(date)
+}
+
+fn start_of_day(date: NaiveDateTime) -> NaiveDateTime {
+    date.date().and_time(Default::default())
+}
+
+fn timestamp(secs: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(secs, 0)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Start of the range, defaults to a day, week or 90 days before `to` depending on the bucket
+    from: Option<NaiveDateTime>,
+    /// End of the range, defaults to now
+    to: Option<NaiveDateTime>,
+    /// Width of each bucket, `5m` only covers the raw samples and `1h` the hourly rollups
+    #[serde(default)]
+    bucket: Bucket,
+}
+
+impl HistoryQuery {
+    /// Range with defaults filled in, 400 when it is empty, holds too many buckets or starts
+    /// before the retention of the table the bucket reads.
+    pub fn range(&self, retention: &PlayersRetentionConfig) -> Result<Range, AppError> {
+        self.range_at(retention, now())
+    }
+
+    fn range_at(
+        &self,
+        retention: &PlayersRetentionConfig,
+        now: NaiveDateTime,
+    ) -> Result<Range, AppError> {
+        let to = self.to.unwrap_or(now);
+        let from = self.from.unwrap_or(to - self.bucket.default_span());
+
+        if from >= to {
+            return Err(ErrorBadRequest("The range has to end after it starts").into());
+        }
+        if (to - from).num_seconds() / self.bucket.secs() > MAX_BUCKETS {
+            return Err(ErrorBadRequest(format!(
+                "The range can hold at most {MAX_BUCKETS} buckets, use a larger bucket"
+            ))
+            .into());
+        }
+        if let Some((days, coarser)) = self.bucket.retention(retention) {
+            if from < now - Duration::days(days as i64) {
+                return Err(ErrorBadRequest(format!(
+                    "This bucket only covers the last {days} days, use {coarser} for older ranges"
+                ))
+                .into());
+            }
+        }
+
+        Ok(Range {
+            from,
+            to,
+            bucket: self.bucket,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Range {
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    bucket: Bucket,
+}
+
+impl Range {
+    /// Rows of every table the bucket reads, with the values to bind.
+    async fn samples(
+        &self,
+        db: &DatabaseConnection,
+        server_id: Option<i32>,
+    ) -> Result<(String, Vec<Value>), DbErr> {
+        let mut parts = Vec::new();
+        let mut values = Vec::new();
+        let mut start = self.from;
+
+        for source in self.bucket.sources() {
+            let end = match source {
+                Source::Raw => self.to,
+                _ => match source.rolled_until(db).await? {
+                    Some(until) => until.min(self.to),
+                    None => continue,
+                },
+            };
+            if end <= start && !matches!(source, Source::Raw) {
+                continue;
+            }
+
+            let mut sql = source.sql().to_owned();
+            values.extend([start.into(), end.into()]);
+            if let Some(server_id) = server_id {
+                sql.push_str(" AND server_id = ?");
+                values.push(server_id.into());
+            }
+            parts.push(sql);
+            start = start.max(end);
+        }
+
+        Ok((parts.join(" UNION ALL "), values))
+    }
+}
+
+/// Players during one bucket, buckets without samples are left out.
+#[derive(Serialize, ToSchema)]
+pub struct PlayersBucket {
+    start: NaiveDateTime,
+    min: i64,
+    avg: f64,
+    max: i64,
+}
+
+/// Most players at once, dated to the start of the sample or rollup it was seen in.
+#[derive(Serialize, ToSchema)]
+pub struct PlayersPeak {
+    players: i64,
+    date: NaiveDateTime,
+}
+
+/// Players of one server, counted only from pings it answered.
+#[derive(Serialize, ToSchema)]
+pub struct PlayersHistory {
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    bucket: Bucket,
+    peak: Option<PlayersPeak>,
+    /// Percentage of pings the server answered, null without any
+    uptime: Option<f64>,
+    buckets: Vec<PlayersBucket>,
+}
+
+/// Players of all listed servers together.
+#[derive(Serialize, ToSchema)]
+pub struct TotalPlayers {
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    bucket: Bucket,
+    /// Highest total of one slot, slots older than the raw samples hold the average of their
+    /// hour or day, so older peaks can be lower than the players that were really online
+    peak: Option<PlayersPeak>,
+    buckets: Vec<PlayersBucket>,
+}
+
+#[derive(FromQueryResult)]
+struct BucketRow {
+    bucket: i64,
+    min: i64,
+    avg: f64,
+    max: i64,
+}
+
+#[derive(FromQueryResult)]
+struct UptimeRow {
+    samples: i64,
+    online: i64,
+}
+
+#[derive(FromQueryResult)]
+struct SlotRow {
+    slot: i64,
+    total: i64,
+}
+
+impl From<SlotRow> for PlayersPeak {
+    fn from(row: SlotRow) -> Self {
+        Self {
+            players: row.total,
+            date: timestamp(row.slot),
+        }
+    }
+}
+
+fn statement(sql: String, values: Vec<Value>) -> Statement {
+    Statement::from_sql_and_values(DbBackend::MySql, sql, values)
+}
+
+fn to_buckets(rows: Vec<BucketRow>, bucket: Bucket) -> Vec<PlayersBucket> {
+    rows.into_iter()
+        .map(|row| PlayersBucket {
+            start: timestamp(row.bucket * bucket.secs()),
+            min: row.min,
+            avg: (row.avg * 100.0).round() / 100.0,
+            max: row.max,
+        })
+        .collect()
+}
+
+pub async fn server_history(
+    db: &DatabaseConnection,
+    server_id: i32,
+    range: Range,
+) -> Result<PlayersHistory, AppError> {
+    let Range { from, to, bucket } = range;
+    let (samples, values) = range.samples(db, Some(server_id)).await?;
+
+    let rows = BucketRow::find_by_statement(statement(
+        format!(
+            "SELECT slot DIV ? AS bucket, MIN(min_players) AS min,
+            CAST(SUM(total_players) / SUM(online) AS DOUBLE) AS avg, MAX(max_players) AS max
+            FROM ({samples}) samples
+            GROUP BY bucket HAVING SUM(online) > 0 ORDER BY bucket"
+        ),
+        [vec![bucket.secs().into()], values.clone()].concat(),
+    ))
+    .all(db)
+    .await?;
+
+    let peak = SlotRow::find_by_statement(statement(
+        format!(
+            "SELECT slot, max_players AS total FROM ({samples}) samples
+            WHERE max_players IS NOT NULL ORDER BY max_players DESC, slot LIMIT 1"
+        ),
+        values.clone(),
+    ))
+    .one(db)
+    .await?
+    .map(PlayersPeak::from);
+
+    let uptime = UptimeRow::find_by_statement(statement(
+        format!(
+            "SELECT CAST(COALESCE(SUM(samples), 0) AS SIGNED) AS samples,
+            CAST(COALESCE(SUM(online), 0) AS SIGNED) AS online FROM ({samples}) samples"
+        ),
+        values,
+    ))
+    .one(db)
+    .await?
+    .filter(|v| v.samples > 0)
+    .map(|v| (v.online * 10000 / v.samples) as f64 / 100.0);
+
+    Ok(PlayersHistory {
+        from,
+        to,
+        bucket,
+        peak,
+        uptime,
+        buckets: to_buckets(rows, bucket),
+    })
+}
+
+pub async fn total_history(
+    db: &DatabaseConnection,
+    range: Range,
+) -> Result<TotalPlayers, AppError> {
+    let Range { from, to, bucket } = range;
+    let (samples, values) = range.samples(db, None).await?;
+
+    // Average players of each listed server per slot, added up across servers
+    let slots = format!(
+        "SELECT slot, CAST(ROUND(SUM(players)) AS SIGNED) AS total FROM (
+            SELECT samples.server_id, slot, SUM(total_players) / SUM(online) AS players
+            FROM ({samples}) samples
+            JOIN servers s ON s.id = samples.server_id
+            WHERE s.hidden_at IS NULL
+            GROUP BY samples.server_id, slot HAVING SUM(online) > 0
+        ) per_server GROUP BY slot"
+    );
+
+    let rows = BucketRow::find_by_statement(statement(
+        format!(
+            "SELECT slot DIV ? AS bucket, MIN(total) AS min,
+            CAST(AVG(total) AS DOUBLE) AS avg, MAX(total) AS max
+            FROM ({slots}) slots
+            GROUP BY bucket ORDER BY bucket"
+        ),
+        [vec![bucket.secs().into()], values.clone()].concat(),
+    ))
+    .all(db)
+    .await?;
+
+    let peak = SlotRow::find_by_statement(statement(
+        format!("SELECT slot, total FROM ({slots}) slots ORDER BY total DESC, slot LIMIT 1"),
+        values,
+    ))
+    .one(db)
+    .await?
+    .map(PlayersPeak::from);
+
+    Ok(TotalPlayers {
+        from,
+        to,
+        bucket,
+        peak,
+        buckets: to_buckets(rows, bucket),
+    })
+}
+
+async fn delete_in_batches(
+    db: &DatabaseConnection,
+    table: &str,
+    before: NaiveDateTime,
+    batch_size: u64,
+) -> Result<u64, DbErr> {
+    let mut removed = 0;
+    loop {
+        let res = db
+            .execute(statement(
+                format!("DELETE FROM {table} WHERE date < ? LIMIT ?"),
+                vec![before.into(), batch_size.into()],
+            ))
+            .await?;
+        removed += res.rows_affected();
+        if res.rows_affected() < batch_size {
+            return Ok(removed);
+        }
+    }
+}
+
+/// Runs a rollup statement over `from..to` a chunk at a time, so a long backlog does not become
+/// one huge insert.
+async fn rollup_in_chunks(
+    db: &DatabaseConnection,
+    sql: &str,
+    mut from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<(), DbErr> {
+    while from < to {
+        let end = (from + ROLLUP_CHUNK).min(to);
+        db.execute(statement(sql.to_owned(), vec![from.into(), end.into()]))
+            .await?;
+        from = end;
+    }
+
+    Ok(())
+}
+
+/// Rolls finished hours into `players_graph_hourly` and finished days into `players_graph_daily`,
+/// then deletes what fell out of the retention windows. Returns how many rows were removed.
+pub async fn rollup(
+    db: &DatabaseConnection,
+    config: &PlayersRetentionConfig,
+) -> Result<u64, DbErr> {
+    let now = now();
+    let this_hour = start_of_hour(now);
+    let today = start_of_day(now);
+
+    // Finished hours and days never change, each run continues after the last one it rolled
+    let hours_from = match latest_hour(db).await? {
+        Some(latest) => Some(latest + Duration::hours(1)),
+        None => earliest_sample(db).await?.map(start_of_hour),
+    };
+    if let Some(from) = hours_from {
+        rollup_in_chunks(db, HOURLY_ROLLUP_SQL, from, this_hour).await?;
+    }
+
+    let days_from = match latest_day(db).await? {
+        Some(latest) => Some(latest + Duration::days(1)),
+        None => earliest_hour(db).await?.map(start_of_day),
+    };
+    if let Some(from) = days_from {
+        rollup_in_chunks(db, DAILY_ROLLUP_SQL, from, today).await?;
+    }
+
+    // Never past what was just rolled up, whatever the windows are set to
+    let batch_size = config.batch_size.max(1);
+    let raw_before = (now - Duration::days(config.raw_days as i64)).min(this_hour);
+    let hourly_before = (now - Duration::days(config.hourly_days as i64)).min(today);
+
+    let raw = delete_in_batches(db, "players_graph", raw_before, batch_size).await?;
+    let hourly = delete_in_batches(db, "players_graph_hourly", hourly_before, batch_size).await?;
+
+    Ok(raw + hourly)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn query(from: Option<&str>, to: Option<&str>, bucket: Bucket) -> HistoryQuery {
+        HistoryQuery {
+            from: from.map(at),
+            to: to.map(at),
+            bucket,
+        }
+    }
+
+    #[test]
+    fn range_defaults_to_a_span_per_bucket() {
+        let config = PlayersRetentionConfig::default();
+        let now = at("2026-10-19 12:34:56");
+
+        for (bucket, span) in [
+            (Bucket::FiveMinutes, Duration::days(1)),
+            (Bucket::Hour, Duration::days(7)),
+            (Bucket::Day, Duration::days(90)),
+        ] {
+            let range = query(None, None, bucket).range_at(&config, now).unwrap();
+            assert_eq!(range.to, now);
+            assert_eq!(range.from, now - span);
+        }
+    }
+
+    #[test]
+    fn range_rejects_empty_and_oversized_ranges() {
+        let config = PlayersRetentionConfig::default();
+        let now = at("2026-10-19 12:00:00");
+
+        let empty = query(
+            Some("2026-10-19 10:00:00"),
+            Some("2026-10-19 10:00:00"),
+            Bucket::Hour,
+        );
+        assert!(empty.range_at(&config, now).is_err());
+
+        // 2000 five minute buckets are a little less than 7 days
+        let full = query(Some("2026-10-12 12:00:00"), None, Bucket::FiveMinutes);
+        assert!(full.range_at(&config, now).is_err());
+        let fits = query(Some("2026-10-13 12:00:00"), None, Bucket::FiveMinutes);
+        assert!(fits.range_at(&config, now).is_ok());
+    }
+
+    #[test]
+    fn range_stays_within_the_retention_of_the_bucket() {
+        let config = PlayersRetentionConfig {
+            raw_days: 7,
+            hourly_days: 30,
+            batch_size: 5000,
+        };
+        let now = at("2026-10-19 12:00:00");
+
+        let raw = query(Some("2026-10-12 11:00:00"), None, Bucket::FiveMinutes);
+        assert!(raw.range_at(&config, now).is_err());
+
+        let hourly = query(
+            Some("2026-09-18 12:00:00"),
+            Some("2026-09-20 12:00:00"),
+            Bucket::Hour,
+        );
+        assert!(hourly.range_at(&config, now).is_err());
+        let recent = query(Some("2026-09-20 12:00:00"), None, Bucket::Hour);
+        assert!(recent.range_at(&config, now).is_ok());
+
+        let daily = query(Some("2025-01-01 00:00:00"), None, Bucket::Day);
+        assert!(daily.range_at(&config, now).is_ok());
+    }
+
+    #[test]
+    fn slots_are_truncated_to_hours_and_days() {
+        let date = at("2026-10-19 12:34:56");
+
+        assert_eq!(start_of_hour(date), at("2026-10-19 12:00:00"));
+        assert_eq!(start_of_day(date), at("2026-10-19 00:00:00"));
+        assert_eq!(timestamp(3600), at("1970-01-01 01:00:00"));
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use super::{Message, PubSubBackend, PublishFuture};
+
+const CHANNEL_CAPACITY: usize = 256;
+
+pub struct MemoryBackend {
+    sender: broadcast::Sender<Message>,
+}
+
+impl MemoryBackend {
+    pub fn create() -> Arc<Self> {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Arc::new(Self { sender })
+    }
+}
+
+impl PubSubBackend for MemoryBackend {
+    fn publish(&self, message: Message) -> PublishFuture<'_> {
+        let _ = self.sender.send(message);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<Message> {
+        self.sender.subscribe()
+    }
+}
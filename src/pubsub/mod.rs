@@ -0,0 +1,77 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::error::AppError;
+use crate::events::DomainEvent;
+use crate::shutdown::Shutdown;
+
+pub mod memory;
+pub mod mysql;
+
+pub use memory::MemoryBackend;
+pub use mysql::MySqlBackend;
+
+pub type PublishFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+/// Everything that has to reach every api instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Message {
+    /// A write happened, consumed by the background tasks on the leader
+    Event(DomainEvent),
+    /// Ready to send SSE payload, delivered to the clients of every instance
+    Broadcast(String),
+}
+
+pub trait PubSubBackend: Send + Sync {
+    /// Delivers the message to every subscriber, including the ones of this instance.
+    fn publish(&self, message: Message) -> PublishFuture<'_>;
+
+    fn subscribe(&self) -> broadcast::Receiver<Message>;
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Single instance, nothing leaves the process
+    #[default]
+    Memory,
+    /// Messages go through the `pubsub_messages` table
+    Mysql,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct PubSubConfig {
+    pub backend: BackendKind,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for PubSubConfig {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::Memory,
+            poll_interval_ms: 500,
+        }
+    }
+}
+
+pub async fn create(
+    config: &PubSubConfig,
+    conn: Arc<DatabaseConnection>,
+    instance: &str,
+    shutdown: Arc<Shutdown>,
+) -> Result<Arc<dyn PubSubBackend>, AppError> {
+    Ok(match config.backend {
+        BackendKind::Memory => MemoryBackend::create(),
+        BackendKind::Mysql => {
+            let poll_interval = Duration::from_millis(config.poll_interval_ms);
+            MySqlBackend::create(conn, instance.to_owned(), poll_interval, shutdown).await?
+        }
+    })
+}
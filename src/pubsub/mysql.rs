@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt::time::interval;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use tokio::sync::broadcast;
+
+use crate::entities::pubsub_messages;
+use crate::error::AppError;
+use crate::shutdown::Shutdown;
+
+use super::{Message, PubSubBackend, PublishFuture};
+
+const CHANNEL_CAPACITY: usize = 256;
+// Rows are only needed until every instance polled them
+const RETENTION: Duration = Duration::from_secs(60);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+// Ids are taken at insert but rows show up at commit, so a lower id can appear after a higher one
+const LOOKBACK: Duration = Duration::from_secs(10);
+
+/// Rows a poll still has to deliver, each one exactly once.
+struct Cursor {
+    last_id: i64,
+    /// Rows within the lookback, with when they were published
+    seen: HashMap<i64, NaiveDateTime>,
+}
+
+impl Cursor {
+    fn new(last_id: i64) -> Self {
+        Self {
+            last_id,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Rows newer than the last one seen, plus recent ones that may have committed late.
+    fn condition(&self, cutoff: NaiveDateTime) -> Condition {
+        Condition::any()
+            .add(pubsub_messages::Column::Id.gt(self.last_id))
+            .add(pubsub_messages::Column::CreatedAt.gte(cutoff))
+    }
+
+    /// False when the row was delivered by an earlier poll.
+    fn accept(&mut self, row: &pubsub_messages::Model) -> bool {
+        self.last_id = self.last_id.max(row.id);
+        self.seen.insert(row.id, row.created_at).is_none()
+    }
+
+    /// Forgets rows the next poll can no longer return.
+    fn forget(&mut self, cutoff: NaiveDateTime) {
+        self.seen.retain(|_, created_at| *created_at >= cutoff);
+    }
+}
+
+/// Shares messages between instances through the `pubsub_messages` table.
+///
+/// Every instance polls for rows newer than the last one it has seen, looking a
+/// few seconds back for rows that committed out of order, so any database the
+/// instances already share works as the transport.
+pub struct MySqlBackend {
+    conn: Arc<DatabaseConnection>,
+    instance: String,
+    sender: broadcast::Sender<Message>,
+}
+
+impl MySqlBackend {
+    pub async fn create(
+        conn: Arc<DatabaseConnection>,
+        instance: String,
+        poll_interval: Duration,
+        shutdown: Arc<Shutdown>,
+    ) -> Result<Arc<Self>, AppError> {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        // Start from the newest row, older messages were meant for someone else
+        let last_id: Option<i64> = pubsub_messages::Entity::find()
+            .select_only()
+            .column_as(pubsub_messages::Column::Id.max(), "id")
+            .into_tuple()
+            .one(conn.as_ref())
+            .await?
+            .flatten();
+
+        let this = Arc::new(Self {
+            conn,
+            instance,
+            sender,
+        });
+        MySqlBackend::spawn_poll(
+            Arc::clone(&this),
+            last_id.unwrap_or(0),
+            poll_interval,
+            Arc::clone(&shutdown),
+        );
+        MySqlBackend::spawn_prune(Arc::clone(&this), shutdown);
+        Ok(this)
+    }
+
+    fn spawn_poll(this: Arc<Self>, last_id: i64, poll_interval: Duration, shutdown: Arc<Shutdown>) {
+        actix_web::rt::spawn(async move {
+            let mut interval = interval(poll_interval);
+            let mut cursor = Cursor::new(last_id);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.triggered() => return,
+                }
+
+                let cutoff = Utc::now().naive_utc() - LOOKBACK;
+                let rows = pubsub_messages::Entity::find()
+                    .filter(cursor.condition(cutoff))
+                    .order_by_asc(pubsub_messages::Column::Id)
+                    .all(this.conn.as_ref())
+                    .await;
+
+                let rows = match rows {
+                    Ok(v) => v,
+                    Err(e) => {
+                        log::error!("Failed to poll pubsub messages: {e}");
+                        continue;
+                    }
+                };
+
+                for row in rows {
+                    if !cursor.accept(&row) {
+                        continue;
+                    }
+
+                    // Already delivered locally in `publish`
+                    if row.instance == this.instance {
+                        continue;
+                    }
+
+                    match serde_json::from_str::<Message>(&row.payload) {
+                        Ok(message) => {
+                            let _ = this.sender.send(message);
+                        }
+                        Err(e) => log::error!("Invalid pubsub message {}: {e}", row.id),
+                    }
+                }
+                cursor.forget(cutoff);
+            }
+        });
+    }
+
+    fn spawn_prune(this: Arc<Self>, shutdown: Arc<Shutdown>) {
+        actix_web::rt::spawn(async move {
+            let mut interval = interval(PRUNE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.triggered() => return,
+                }
+
+                let cutoff = Utc::now().naive_utc() - RETENTION;
+                if let Err(e) = pubsub_messages::Entity::delete_many()
+                    .filter(pubsub_messages::Column::CreatedAt.lt(cutoff))
+                    .exec(this.conn.as_ref())
+                    .await
+                {
+                    log::error!("Failed to prune pubsub messages: {e}");
+                }
+            }
+        });
+    }
+}
+
+impl PubSubBackend for MySqlBackend {
+    fn publish(&self, message: Message) -> PublishFuture<'_> {
+        Box::pin(async move {
+            let payload = serde_json::to_string(&message)?;
+            let _ = self.sender.send(message);
+
+            pubsub_messages::ActiveModel {
+                instance: Set(self.instance.clone()),
+                payload: Set(payload),
+                created_at: Set(Utc::now().naive_utc()),
+                ..Default::default()
+            }
+            .insert(self.conn.as_ref())
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<Message> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ConnectOptions, ConnectionTrait, Database, DbBackend, Schema};
+    use tokio::sync::broadcast::error::TryRecvError;
+    use tokio::time::{sleep, timeout};
+
+    use super::*;
+
+    const POLL: Duration = Duration::from_millis(10);
+
+    // An in-memory SQLite database stands in for the one the instances share
+    async fn stand_in() -> Arc<DatabaseConnection> {
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1);
+        let conn = Database::connect(options).await.unwrap();
+
+        let table =
+            Schema::new(DbBackend::Sqlite).create_table_from_entity(pubsub_messages::Entity);
+        conn.execute(conn.get_database_backend().build(&table))
+            .await
+            .unwrap();
+        Arc::new(conn)
+    }
+
+    async fn insert(conn: &DatabaseConnection, id: i64, payload: &str) {
+        let message = serde_json::to_string(&Message::Broadcast(payload.to_owned())).unwrap();
+        pubsub_messages::ActiveModel {
+            id: Set(id),
+            instance: Set("other".to_owned()),
+            payload: Set(message),
+            created_at: Set(Utc::now().naive_utc()),
+        }
+        .insert(conn)
+        .await
+        .unwrap();
+    }
+
+    async fn next(receiver: &mut broadcast::Receiver<Message>) -> String {
+        match timeout(Duration::from_secs(2), receiver.recv()).await {
+            Ok(Ok(Message::Broadcast(payload))) => payload,
+            other => panic!("expected a broadcast, got {other:?}"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn delivers_rows_committed_out_of_order_once() {
+        let conn = stand_in().await;
+        let backend = MySqlBackend::create(
+            Arc::clone(&conn),
+            "this".to_owned(),
+            POLL,
+            Shutdown::create(),
+        )
+        .await
+        .unwrap();
+        let mut receiver = backend.subscribe();
+
+        insert(&conn, 2, "second").await;
+        assert_eq!(next(&mut receiver).await, "second");
+
+        // Got its id first but committed after the poll above
+        insert(&conn, 1, "first").await;
+        assert_eq!(next(&mut receiver).await, "first");
+
+        sleep(POLL * 10).await;
+        assert!(matches!(receiver.try_recv(), Err(TryRecvError::Empty)));
+    }
+
+    #[actix_web::test]
+    async fn reaches_other_instances_once() {
+        let conn = stand_in().await;
+        let this = MySqlBackend::create(
+            Arc::clone(&conn),
+            "this".to_owned(),
+            POLL,
+            Shutdown::create(),
+        )
+        .await
+        .unwrap();
+        let other = MySqlBackend::create(conn, "other".to_owned(), POLL, Shutdown::create())
+            .await
+            .unwrap();
+        let mut local = this.subscribe();
+        let mut remote = other.subscribe();
+
+        this.publish(Message::Broadcast("hello".to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(next(&mut local).await, "hello");
+        assert_eq!(next(&mut remote).await, "hello");
+
+        sleep(POLL * 10).await;
+        assert!(matches!(local.try_recv(), Err(TryRecvError::Empty)));
+        assert!(matches!(remote.try_recv(), Err(TryRecvError::Empty)));
+    }
+}
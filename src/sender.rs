@@ -1,73 +1,218 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+use actix_web::error::{ErrorServiceUnavailable, ErrorTooManyRequests};
+use actix_web::http::StatusCode;
 use actix_web::rt::time::interval;
-use actix_web_lab::sse::{self, ChannelStream, Sse};
+use actix_web_lab::sse::{self, ChannelStream, Sse, TrySendError};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::client_update::{UpdateEventType, UpdateResponseBody};
+use crate::shutdown::Shutdown;
+
+/// What happens to a client whose queue is full.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SlowClientPolicy {
+    /// The client misses the message but stays connected
+    #[default]
+    Drop,
+    /// The client is disconnected and has to reconnect
+    Disconnect,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct SseConfig {
+    /// Messages queued per client before the slow client policy applies
+    pub queue_size: usize,
+    pub max_clients: usize,
+    pub max_clients_per_ip: usize,
+    pub slow_client_policy: SlowClientPolicy,
+}
+
+impl Default for SseConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 10,
+            max_clients: 10_000,
+            max_clients_per_ip: 10,
+            slow_client_policy: SlowClientPolicy::Drop,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BroadcasterMetrics {
+    connected_clients: usize,
+    total_connections: u64,
+    rejected_connections: u64,
+    messages_sent: u64,
+    dropped_messages: u64,
+    slow_disconnects: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    total_connections: AtomicU64,
+    rejected_connections: AtomicU64,
+    messages_sent: AtomicU64,
+    dropped_messages: AtomicU64,
+    slow_disconnects: AtomicU64,
+}
 
 // Client
+#[derive(Debug, Clone)]
+struct Client {
+    sender: sse::Sender,
+    ip: Option<IpAddr>,
+}
+
 #[derive(Debug, Clone, Default)]
 struct BroadcasterInner {
-    clients: Vec<sse::Sender>,
+    clients: Vec<Client>,
+    /// Set under the lock, so no client is added after `shutdown` cleared the list
+    closed: bool,
 }
 
 pub struct Broadcaster {
     inner: Mutex<BroadcasterInner>,
+    config: SseConfig,
+    counters: Counters,
+    /// Sent as the `retry` field, browsers wait this long before reconnecting
+    retry: Duration,
 }
 
 impl Broadcaster {
-    pub fn create() -> Arc<Self> {
+    pub fn create(config: SseConfig, retry: Duration, shutdown: Arc<Shutdown>) -> Arc<Self> {
         let this = Arc::new(Self {
             inner: Mutex::new(BroadcasterInner::default()),
+            config,
+            counters: Counters::default(),
+            retry,
         });
-        Broadcaster::spawn_ping(Arc::clone(&this));
+        Broadcaster::spawn_ping(Arc::clone(&this), shutdown);
         this
     }
 
-    fn spawn_ping(this: Arc<Self>) {
+    fn spawn_ping(this: Arc<Self>, shutdown: Arc<Shutdown>) {
         actix_web::rt::spawn(async move {
             let mut interval = interval(Duration::from_secs(10));
 
             loop {
-                interval.tick().await;
-                this.remove_stale_clients().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.triggered() => return,
+                }
+                this.send_all(sse::Event::Comment("ping".into()));
             }
         });
     }
 
-    async fn remove_stale_clients(&self) {
-        let clients = self.inner.lock().clients.clone();
+    pub fn new_client(&self, ip: Option<IpAddr>) -> Result<Sse<ChannelStream>, actix_web::Error> {
+        let mut inner = self.inner.lock();
 
-        let mut ok_clients = Vec::new();
+        if inner.closed {
+            return Err(ErrorServiceUnavailable("Server is shutting down"));
+        }
 
-        for client in clients {
-            if client
-                .send(sse::Event::Comment("ping".into()))
-                .await
-                .is_ok()
-            {
-                ok_clients.push(client.clone());
-            }
+        if inner.clients.len() >= self.config.max_clients {
+            self.counters
+                .rejected_connections
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(ErrorServiceUnavailable("Too many connected clients"));
         }
 
-        self.inner.lock().clients = ok_clients;
+        if ip.is_some()
+            && inner.clients.iter().filter(|v| v.ip == ip).count() >= self.config.max_clients_per_ip
+        {
+            self.counters
+                .rejected_connections
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(ErrorTooManyRequests(
+                "Too many connections from this address",
+            ));
+        }
+
+        let (tx, rx) = sse::channel(self.config.queue_size.max(1));
+
+        // The queue is empty, this can not fail
+        let _ = tx.try_send(sse::Data::new("connected"));
+        inner.clients.push(Client { sender: tx, ip });
+        self.counters
+            .total_connections
+            .fetch_add(1, Ordering::Relaxed);
+
+        // EventSource only takes the reconnect delay from this field, it is the first frame sent
+        Ok(rx.with_retry_duration(self.retry))
     }
 
-    pub async fn new_client(&self) -> Sse<ChannelStream> {
-        let (tx, rx) = sse::channel(10);
+    /// Queues the message for every client without waiting on any of them.
+    pub fn broadcast(&self, msg: &str) {
+        self.send_all(sse::Data::new(msg).into());
+    }
+
+    fn send_all(&self, event: sse::Event) {
+        let mut inner = self.inner.lock();
+
+        inner
+            .clients
+            .retain(|client| match client.sender.try_send(event.clone()) {
+                Ok(()) => {
+                    self.counters.messages_sent.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    self.counters
+                        .dropped_messages
+                        .fetch_add(1, Ordering::Relaxed);
 
-        tx.send(sse::Data::new("connected")).await.unwrap();
-        self.inner.lock().clients.push(tx);
-        rx
+                    match self.config.slow_client_policy {
+                        SlowClientPolicy::Drop => true,
+                        SlowClientPolicy::Disconnect => {
+                            self.counters
+                                .slow_disconnects
+                                .fetch_add(1, Ordering::Relaxed);
+                            false
+                        }
+                    }
+                }
+                // Client went away
+                Err(_) => false,
+            });
     }
 
-    pub async fn broadcast(&self, msg: &str) {
-        let clients = self.inner.lock().clients.clone();
+    /// Refuses new clients, tells the connected ones and closes their streams.
+    /// They reconnect after the `retry` they got when connecting.
+    pub fn shutdown(&self) {
+        let body = UpdateResponseBody::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is shutting down",
+            None,
+            UpdateEventType::Shutdown,
+        );
+        if let Ok(msg) = serde_json::to_string(&body) {
+            self.broadcast(&msg);
+        }
 
-        let send_futures = clients
-            .iter()
-            .map(|client| client.send(sse::Data::new(msg)));
+        // Streams end once the queued messages are flushed
+        let mut inner = self.inner.lock();
+        inner.closed = true;
+        inner.clients.clear();
+    }
 
-        let _ = futures::future::join_all(send_futures).await;
+    pub fn metrics(&self) -> BroadcasterMetrics {
+        BroadcasterMetrics {
+            connected_clients: self.inner.lock().clients.len(),
+            total_connections: self.counters.total_connections.load(Ordering::Relaxed),
+            rejected_connections: self.counters.rejected_connections.load(Ordering::Relaxed),
+            messages_sent: self.counters.messages_sent.load(Ordering::Relaxed),
+            dropped_messages: self.counters.dropped_messages.load(Ordering::Relaxed),
+            slow_disconnects: self.counters.slow_disconnects.load(Ordering::Relaxed),
+        }
     }
 }
@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt::time::timeout;
+use serde::Deserialize;
+use tokio::sync::{watch, Notify};
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// How long requests and running tasks get to finish
+    pub timeout_secs: u64,
+    /// Reconnect delay suggested to SSE clients
+    pub retry_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            retry_ms: 5000,
+        }
+    }
+}
+
+/// Tells long running work that the process is stopping and keeps track of
+/// what still has to finish before the database can be closed.
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+    /// Set before `sender`, checked by `track` after counting itself in
+    stopping: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks a unit of work that should not be cut off, released on drop.
+pub struct WorkGuard<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for WorkGuard<'_> {
+    fn drop(&mut self) {
+        if self.shutdown.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn create() -> Arc<Self> {
+        let (sender, _) = watch::channel(false);
+        Arc::new(Self {
+            sender,
+            stopping: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+        })
+    }
+
+    pub fn trigger(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.sender.send_replace(true);
+    }
+
+    /// Resolves once shutdown has been triggered.
+    pub async fn triggered(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|v| *v).await;
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// `None` once shutdown was triggered, so nothing starts after `drain` saw no work left.
+    pub fn track(&self) -> Option<WorkGuard<'_>> {
+        // Counted first, a concurrent `drain` either sees the work or `track` sees the trigger
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = WorkGuard { shutdown: self };
+        match self.is_triggered() {
+            true => None,
+            false => Some(guard),
+        }
+    }
+
+    /// Waits for tracked work to finish, returns `false` if the deadline passed first.
+    pub async fn drain(&self, deadline: Duration) -> bool {
+        timeout(deadline, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// Resolves on `SIGINT` or `SIGTERM`.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+        tokio::select! {
+            _ = actix_web::rt::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = actix_web::rt::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{Storage, StorageConfig, StorageFuture};
+use crate::error::AppError;
+
+/// Files on disk next to the api, for a single instance or a shared volume.
+pub struct LocalStorage {
+    dir: PathBuf,
+    public_url: String,
+}
+
+impl LocalStorage {
+    pub fn create(config: &StorageConfig) -> Result<Arc<Self>, AppError> {
+        std::fs::create_dir_all(&config.dir)?;
+
+        Ok(Arc::new(Self {
+            dir: PathBuf::from(&config.dir),
+            public_url: config
+                .public_url
+                .clone()
+                .unwrap_or_else(|| "/uploads".to_owned()),
+        }))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, key: String, body: Vec<u8>, _: &'static str) -> StorageFuture<'_> {
+        Box::pin(async move {
+            let path = self.dir.join(&key);
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, body).await?;
+
+            Ok(())
+        })
+    }
+
+    fn delete(&self, key: String) -> StorageFuture<'_> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.dir.join(&key)).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{key}", self.public_url.trim_end_matches('/'))
+    }
+}
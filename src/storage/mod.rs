@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::error::AppError;
+
+pub mod local;
+pub mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+pub type StorageFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+/// Where uploaded files are kept, keys look like `servers/3/banner-1a2b.gif`.
+pub trait Storage: Send + Sync {
+    /// Stores the file under `key`, replacing what was there.
+    fn put(&self, key: String, body: Vec<u8>, content_type: &'static str) -> StorageFuture<'_>;
+
+    /// Removes the file, keys that do not exist are not an error.
+    fn delete(&self, key: String) -> StorageFuture<'_>;
+
+    /// Public address of the file.
+    fn url(&self, key: &str) -> String;
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Files are written to `dir` and served under `/uploads`
+    #[default]
+    Local,
+    /// Any S3 compatible bucket, MinIO works as a local stand-in
+    S3,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct S3Config {
+    /// Like `https://s3.eu-central-1.amazonaws.com` or `http://localhost:9000`
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Address the bucket as `endpoint/bucket` instead of `bucket.endpoint`, needed by most stand-ins
+    pub path_style: bool,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://s3.amazonaws.com".to_owned(),
+            region: "us-east-1".to_owned(),
+            bucket: String::new(),
+            access_key: String::new(),
+            secret_key: String::new(),
+            path_style: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageKind,
+    /// Address files are served from, like a CDN in front of the bucket. Defaults to
+    /// `/uploads` for local storage and to the bucket address for S3
+    pub public_url: Option<String>,
+    pub dir: String,
+    pub s3: S3Config,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageKind::Local,
+            public_url: None,
+            dir: "uploads".to_owned(),
+            s3: S3Config::default(),
+        }
+    }
+}
+
+pub fn create(config: &StorageConfig) -> Result<Arc<dyn Storage>, AppError> {
+    Ok(match config.backend {
+        StorageKind::Local => LocalStorage::create(config)?,
+        StorageKind::S3 => S3Storage::create(config)?,
+    })
+}
+
+/// Deletes the files without making the request wait on it, failures are only logged.
+pub fn discard(storage: Arc<dyn Storage>, keys: Vec<String>) {
+    if keys.is_empty() {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        for key in keys {
+            if let Err(e) = storage.delete(key.clone()).await {
+                log::error!("Failed to delete {key}: {e}");
+            }
+        }
+    });
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::rt::time::sleep;
+use chrono::{NaiveDateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+use crate::leader::Leader;
+use crate::shutdown::Shutdown;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+pub type WaitFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+// Not `Send`, `AppError` can hold an actix error. Tasks run on the actix arbiter anyway.
+pub type RunFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + 'a>>;
+
+pub trait TaskTrait: Send {
+    fn name(&self) -> &'static str;
+
+    /// Resolves once the task is due, either on its interval or earlier when woken up.
+    fn wait(&mut self) -> WaitFuture<'_>;
+
+    fn run(&mut self) -> RunFuture<'_>;
+}
+
+/// Builds a fresh task, used again after the previous one panicked.
+pub type TaskFactory = Box<dyn Fn() -> Box<dyn TaskTrait> + Send + Sync>;
+
+#[derive(Serialize, Clone, Default, ToSchema)]
+pub struct TaskStatus {
+    name: String,
+    interval_secs: u64,
+    running: bool,
+    run_count: u64,
+    error_count: u64,
+    restart_count: u64,
+    last_run: Option<NaiveDateTime>,
+    last_duration_ms: Option<u64>,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct TaskRegistry {
+    statuses: Mutex<BTreeMap<&'static str, TaskStatus>>,
+}
+
+impl TaskRegistry {
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.statuses.lock().values().cloned().collect()
+    }
+
+    fn register(&self, name: &'static str, interval: Duration) {
+        // Restarted tasks keep their counters
+        self.statuses
+            .lock()
+            .entry(name)
+            .or_insert_with(|| TaskStatus {
+                name: name.to_owned(),
+                interval_secs: interval.as_secs(),
+                ..Default::default()
+            });
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut TaskStatus)) {
+        if let Some(status) = self.statuses.lock().get_mut(name) {
+            f(status);
+        }
+    }
+}
+
+/// Runs the task until shutdown, restarting it with a growing delay whenever it panics.
+pub fn supervise(
+    factory: TaskFactory,
+    interval: Duration,
+    leader: Arc<Leader>,
+    registry: Arc<TaskRegistry>,
+    shutdown: Arc<Shutdown>,
+) {
+    actix_web::rt::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+
+        loop {
+            let task = factory();
+            let name = task.name();
+            registry.register(name, interval);
+
+            let started = Instant::now();
+            let handle = actix_web::rt::spawn(run_task(
+                task,
+                Arc::clone(&leader),
+                Arc::clone(&registry),
+                Arc::clone(&shutdown),
+            ));
+
+            let error = match handle.await {
+                Err(e) if e.is_panic() => e.to_string(),
+                // Stopped because of shutdown
+                _ => return,
+            };
+
+            // Only keep growing the delay while the task keeps crashing right away
+            if started.elapsed() > MAX_BACKOFF {
+                backoff = MIN_BACKOFF;
+            }
+
+            log::error!("Task {name} panicked: {error}, restarting in {backoff:?}");
+            registry.update(name, |status| {
+                status.running = false;
+                status.error_count += 1;
+                status.restart_count += 1;
+                status.last_error = Some(error);
+            });
+
+            tokio::select! {
+                _ = sleep(backoff) => {}
+                _ = shutdown.triggered() => return,
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+async fn run_task(
+    mut task: Box<dyn TaskTrait>,
+    leader: Arc<Leader>,
+    registry: Arc<TaskRegistry>,
+    shutdown: Arc<Shutdown>,
+) {
+    let name = task.name();
+    let mut backoff = MIN_BACKOFF;
+    let mut retry = false;
+
+    loop {
+        // A failed run is retried after the backoff instead of the full interval
+        if !retry {
+            tokio::select! {
+                _ = task.wait() => {}
+                _ = shutdown.triggered() => return,
+            }
+        }
+
+        if !leader.is_leader() {
+            retry = false;
+            continue;
+        }
+
+        // A run that already started gets to finish before the database is closed
+        let Some(guard) = shutdown.track() else {
+            return;
+        };
+        registry.update(name, |status| status.running = true);
+        let started = Instant::now();
+        let res = task.run().await;
+        let duration = started.elapsed();
+        drop(guard);
+
+        registry.update(name, |status| {
+            status.running = false;
+            status.run_count += 1;
+            status.last_run = Some(Utc::now().naive_utc());
+            status.last_duration_ms = Some(duration.as_millis() as u64);
+            if let Err(e) = &res {
+                status.error_count += 1;
+                status.last_error = Some(e.to_string());
+            }
+        });
+
+        retry = res.is_err();
+        match res {
+            Ok(()) => backoff = MIN_BACKOFF,
+            Err(e) => {
+                log::error!("Task {name} failed: {e}, retrying in {backoff:?}");
+                tokio::select! {
+                    _ = sleep(backoff) => {}
+                    _ = shutdown.triggered() => return,
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
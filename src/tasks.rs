@@ -7,59 +7,182 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use actix_web::http::StatusCode;
-use actix_web::rt::time::interval;
+use actix_web::rt::time::{interval, Interval};
 use futures::lock::Mutex;
-use sea_orm::DatabaseConnection;
+use futures::StreamExt;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set};
 use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::MissedTickBehavior;
 
 use crate::client_update::{players_graph, servers, UpdateResponseBody};
-use crate::entities;
+use crate::entities::{self, servers_info};
 use crate::error::AppError;
-use crate::sender::Broadcaster;
-use crate::utils::validate;
+use crate::events::{DomainEvent, EventBus, Topic};
+use crate::leader::Leader;
+use crate::login_throttle;
+use crate::player_stats::{self, PlayersRetentionConfig};
+use crate::shutdown::Shutdown;
+use crate::supervisor::{supervise, RunFuture, TaskFactory, TaskRegistry, TaskTrait, WaitFuture};
+use crate::utils::{purge_expired_refresh_tokens, purge_oauth_nonces, purge_user_tokens, validate};
+use crate::version_sync::VersionSync;
 
-const UPDATE_INTERVAL: Duration = Duration::from_secs(6);
+// Changes are pushed through the event bus, this only catches writes made outside the api
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const PING_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const VERSION_SYNC_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Servers pinged at the same time, each ping holds a socket until it answers or times out
+const PING_CONCURRENCY: usize = 32;
 type FetchReturn<'a> =
     Pin<Box<dyn Future<Output = Result<UpdateResponseBody, AppError>> + Send + 'a>>;
 type FetchFn = fn(&DatabaseConnection) -> FetchReturn;
-
-trait TaskTrait {
-    fn run(self: Box<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>>;
-}
+type CleanupReturn<'a> = Pin<Box<dyn Future<Output = Result<u64, DbErr>> + Send + 'a>>;
+type CleanupFn = fn(&DatabaseConnection) -> CleanupReturn;
+type Cache = Arc<Mutex<HashMap<&'static str, serde_json::Value>>>;
 
 macro_rules! add_task {
-    ($task_manager:expr, $fetch_fn:ident) => {
-        $task_manager.add_task::<entities::$fetch_fn::Model>(|conn| {
-            Box::pin(async move { $fetch_fn(conn).await })
-        });
+    ($task_manager:expr, $fetch_fn:ident, $topic:expr) => {
+        $task_manager.add_task::<entities::$fetch_fn::Model>(
+            stringify!($fetch_fn),
+            $topic,
+            |conn| Box::pin(async move { $fetch_fn(conn).await }),
+        );
     };
 }
 
-pub fn spawn(broadcaster: Arc<Broadcaster>, conn: Arc<DatabaseConnection>) {
-    let mut task_manager = TaskManager::new(broadcaster, conn);
-    add_task!(task_manager, players_graph);
-    add_task!(task_manager, servers);
-    task_manager.start();
+pub fn spawn(
+    events: Arc<EventBus>,
+    leader: Arc<Leader>,
+    shutdown: Arc<Shutdown>,
+    conn: Arc<DatabaseConnection>,
+    version_sync: Arc<VersionSync>,
+    retention: PlayersRetentionConfig,
+    intervals: &HashMap<String, u64>,
+) -> Arc<TaskRegistry> {
+    let mut task_manager = TaskManager::new(events, leader, shutdown, conn, intervals.clone());
+    add_task!(task_manager, players_graph, Topic::PlayersGraph);
+    add_task!(task_manager, servers, Topic::Servers);
+    task_manager.add_ping_task();
+    if version_sync.enabled() {
+        task_manager.add_version_sync_task(version_sync);
+    }
+    task_manager.add_players_rollup_task(retention);
+    task_manager.add_cleanup_task("purge_auth", |conn| {
+        Box::pin(purge_expired_refresh_tokens(conn))
+    });
+    task_manager.add_cleanup_task("purge_user_tokens", |conn| {
+        Box::pin(purge_user_tokens(conn))
+    });
+    task_manager.add_cleanup_task("purge_login_attempts", |conn| {
+        Box::pin(login_throttle::purge(conn))
+    });
+    task_manager.add_cleanup_task("purge_oauth_nonces", |conn| {
+        Box::pin(purge_oauth_nonces(conn))
+    });
+    task_manager.start()
 }
 
 pub struct TaskManager {
-    broadcaster: Arc<Broadcaster>,
+    events: Arc<EventBus>,
+    leader: Arc<Leader>,
+    shutdown: Arc<Shutdown>,
     conn: Arc<DatabaseConnection>,
-    cache: Arc<Mutex<HashMap<&'static str, serde_json::Value>>>,
-    tasks: Vec<Box<dyn TaskTrait>>,
+    cache: Cache,
+    /// Interval overrides from the config, in seconds
+    intervals: HashMap<String, u64>,
+    tasks: Vec<(TaskFactory, Duration)>,
 }
 
 impl TaskManager {
-    pub fn new(broadcaster: Arc<Broadcaster>, conn: Arc<DatabaseConnection>) -> Self {
+    pub fn new(
+        events: Arc<EventBus>,
+        leader: Arc<Leader>,
+        shutdown: Arc<Shutdown>,
+        conn: Arc<DatabaseConnection>,
+        intervals: HashMap<String, u64>,
+    ) -> Self {
         Self {
-            broadcaster,
+            events,
+            leader,
+            shutdown,
             conn,
             cache: Arc::new(Mutex::new(HashMap::new())),
+            intervals,
             tasks: Vec::new(),
         }
     }
 
-    pub fn add_task<T>(&mut self, fetch_fn: FetchFn)
+    fn interval(&self, name: &str, default: Duration) -> Duration {
+        self.intervals
+            .get(name)
+            .map(|secs| Duration::from_secs(*secs))
+            .unwrap_or(default)
+    }
+
+    pub fn add_ping_task(&mut self) {
+        let period = self.interval("ping", PING_INTERVAL);
+        let events = Arc::clone(&self.events);
+        let conn = Arc::clone(&self.conn);
+
+        let factory: TaskFactory = Box::new(move || {
+            Box::new(PingTask {
+                events: Arc::clone(&events),
+                conn: Arc::clone(&conn),
+                interval: new_interval(period),
+            })
+        });
+        self.tasks.push((factory, period));
+    }
+
+    pub fn add_version_sync_task(&mut self, sync: Arc<VersionSync>) {
+        let period = self.interval("version_sync", VERSION_SYNC_INTERVAL);
+        let events = Arc::clone(&self.events);
+        let conn = Arc::clone(&self.conn);
+
+        let factory: TaskFactory = Box::new(move || {
+            Box::new(VersionSyncTask {
+                sync: Arc::clone(&sync),
+                events: Arc::clone(&events),
+                conn: Arc::clone(&conn),
+                interval: new_interval(period),
+            })
+        });
+        self.tasks.push((factory, period));
+    }
+
+    pub fn add_players_rollup_task(&mut self, config: PlayersRetentionConfig) {
+        let period = self.interval("players_rollup", ROLLUP_INTERVAL);
+        let conn = Arc::clone(&self.conn);
+
+        let factory: TaskFactory = Box::new(move || {
+            Box::new(PlayersRollupTask {
+                conn: Arc::clone(&conn),
+                config: config.clone(),
+                interval: new_interval(period),
+            })
+        });
+        self.tasks.push((factory, period));
+    }
+
+    /// Periodically deletes rows that are no longer needed, `cleanup` returns how many.
+    pub fn add_cleanup_task(&mut self, name: &'static str, cleanup: CleanupFn) {
+        let period = self.interval(name, CLEANUP_INTERVAL);
+        let conn = Arc::clone(&self.conn);
+
+        let factory: TaskFactory = Box::new(move || {
+            Box::new(CleanupTask {
+                name,
+                conn: Arc::clone(&conn),
+                interval: new_interval(period),
+                cleanup,
+            })
+        });
+        self.tasks.push((factory, period));
+    }
+
+    pub fn add_task<T>(&mut self, name: &'static str, topic: Topic, fetch_fn: FetchFn)
     where
         T: 'static
             + for<'a> Deserialize<'a>
@@ -69,31 +192,61 @@ impl TaskManager {
             + std::clone::Clone
             + serde::Serialize,
     {
-        let task = Box::new(Task::<T> {
-            broadcaster: Arc::clone(&self.broadcaster),
-            conn: Arc::clone(&self.conn),
-            cache: Arc::clone(&self.cache),
-            cache_key: std::any::type_name::<T>(),
-            interval: interval(UPDATE_INTERVAL),
-            fetch_fn,
-            _marker: PhantomData,
+        let period = self.interval(name, RECONCILE_INTERVAL);
+        let bus = Arc::clone(&self.events);
+        let conn = Arc::clone(&self.conn);
+        let cache = Arc::clone(&self.cache);
+
+        let factory: TaskFactory = Box::new(move || {
+            Box::new(Task::<T> {
+                name,
+                bus: Arc::clone(&bus),
+                conn: Arc::clone(&conn),
+                cache: Arc::clone(&cache),
+                cache_key: std::any::type_name::<T>(),
+                interval: new_interval(period),
+                topic,
+                events: bus.subscribe(),
+                fetch_fn,
+                _marker: PhantomData,
+            })
         });
-        self.tasks.push(task);
+        self.tasks.push((factory, period));
     }
 
-    pub fn start(self) {
-        for task in self.tasks {
-            actix_web::rt::spawn(task.run());
+    pub fn start(self) -> Arc<TaskRegistry> {
+        let registry = Arc::new(TaskRegistry::default());
+
+        for (factory, period) in self.tasks {
+            supervise(
+                factory,
+                period,
+                Arc::clone(&self.leader),
+                Arc::clone(&registry),
+                Arc::clone(&self.shutdown),
+            );
         }
+
+        registry
     }
 }
 
+fn new_interval(period: Duration) -> Interval {
+    let mut interval = interval(period);
+    // A slow run should not be followed by a burst of catch up runs
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
+
 pub struct Task<T> {
-    broadcaster: Arc<Broadcaster>,
+    name: &'static str,
+    bus: Arc<EventBus>,
     conn: Arc<DatabaseConnection>,
-    cache: Arc<Mutex<HashMap<&'static str, serde_json::Value>>>,
+    cache: Cache,
     cache_key: &'static str,
-    interval: actix_web::rt::time::Interval,
+    interval: Interval,
+    topic: Topic,
+    events: broadcast::Receiver<DomainEvent>,
     fetch_fn: FetchFn,
     _marker: PhantomData<T>,
 }
@@ -107,83 +260,260 @@ impl<
             + serde::Serialize,
     > TaskTrait for Task<T>
 {
-    fn run(self: Box<Self>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
-        let conn = self.conn.clone();
-        let cache = self.cache.clone();
-        let cache_key = self.cache_key;
-        let fetch_fn = self.fetch_fn;
-        let broadcaster = self.broadcaster.clone();
-        let mut interval = self.interval;
+    fn name(&self) -> &'static str {
+        self.name
+    }
 
+    fn wait(&mut self) -> WaitFuture<'_> {
         Box::pin(async move {
-            add_to_cache(&conn, Arc::clone(&cache), cache_key, fetch_fn).await;
-
             loop {
-                interval.tick().await;
-
-                check_from_db::<T>(
-                    Arc::clone(&broadcaster),
-                    &conn,
-                    Arc::clone(&cache),
-                    cache_key,
-                    fetch_fn,
-                )
-                .await;
+                tokio::select! {
+                    _ = self.interval.tick() => break,
+                    event = self.events.recv() => match event {
+                        Ok(event) if event.topic == self.topic => {
+                            log::debug!("{:?} {} changed, refreshing", event.topic, event.id);
+                            break;
+                        }
+                        Ok(_) => continue,
+                        // Missed some events, compare against the db anyway
+                        Err(RecvError::Lagged(_)) => break,
+                        Err(RecvError::Closed) => {
+                            self.interval.tick().await;
+                            break;
+                        }
+                    },
+                }
             }
+
+            // A burst of writes only needs a single comparison
+            while self.events.try_recv().is_ok() {}
+        })
+    }
+
+    fn run(&mut self) -> RunFuture<'_> {
+        Box::pin(async move {
+            // Fresh cache, nothing to compare against yet
+            if !self.cache.lock().await.contains_key(self.cache_key) {
+                return add_to_cache(&self.conn, &self.cache, self.cache_key, self.fetch_fn).await;
+            }
+
+            check_from_db::<T>(
+                &self.bus,
+                &self.conn,
+                &self.cache,
+                self.cache_key,
+                self.fetch_fn,
+            )
+            .await
         })
     }
 }
 
-async fn add_to_cache<'a>(
+async fn add_to_cache(
     conn: &DatabaseConnection,
-    cache: Arc<Mutex<HashMap<&'a str, serde_json::Value>>>,
-    cache_key: &'a str,
-    fetch_fn: (impl Fn(&DatabaseConnection) -> FetchReturn + Send + '_),
-) {
-    let res = fetch_fn(conn).await.unwrap();
-    cache.lock().await.insert(cache_key, res.data.unwrap());
+    cache: &Cache,
+    cache_key: &'static str,
+    fetch_fn: FetchFn,
+) -> Result<(), AppError> {
+    let res = fetch_fn(conn).await?;
+    let data = res
+        .data
+        .unwrap_or_else(|| serde_json::Value::Array(Vec::new()));
+    cache.lock().await.insert(cache_key, data);
+    Ok(())
 }
 
-async fn check_from_db<'a, T>(
-    broadcaster: Arc<Broadcaster>,
+async fn check_from_db<T>(
+    bus: &EventBus,
     conn: &DatabaseConnection,
-    cache: Arc<Mutex<HashMap<&'a str, serde_json::Value>>>,
-    cache_key: &'a str,
-    fetch_fn: fn(&DatabaseConnection) -> FetchReturn,
-) where
+    cache: &Cache,
+    cache_key: &'static str,
+    fetch_fn: FetchFn,
+) -> Result<(), AppError>
+where
     T: for<'b> Deserialize<'b> + PartialEq + Debug + std::clone::Clone + serde::Serialize,
 {
-    let res = fetch_fn(conn)
-        .await
-        .unwrap_or_else(|e| UpdateResponseBody::err(&e));
-
-    if let Some(data) = res.data.clone() {
-        let data = validate::<Vec<T>>(data).unwrap();
-        let cached =
-            validate::<Vec<T>>(cache.lock().await.get(cache_key).unwrap().clone()).unwrap();
-
-        let matches = data.iter().zip(&cached).filter(|&(a, b)| a != b);
-        if matches.count() > 0 || data.len() != cached.len() {
-            cache
-                .lock()
-                .await
-                .insert(cache_key, res.data.clone().unwrap());
-            let result_vec: Vec<T> = data
-                .iter()
-                .filter(|&x| !cached.contains(x))
-                .chain(cached.iter().filter(|&x| !data.contains(x)))
-                .cloned()
-                .collect();
-            let new = UpdateResponseBody::new(
-                StatusCode::from_u16(res.code).unwrap(),
-                &res.message,
-                Some(serde_json::to_value(result_vec).unwrap()),
-                res.event,
-            );
-            broadcaster.broadcast(&sjts(&new)).await;
+    let res = match fetch_fn(conn).await {
+        Ok(v) => v,
+        Err(e) => {
+            bus.broadcast(sjts(&UpdateResponseBody::err(&e))).await;
+            return Err(e);
         }
-    } else {
-        broadcaster.broadcast(&sjts(&res)).await;
+    };
+
+    let Some(data) = res.data.clone() else {
+        bus.broadcast(sjts(&res)).await;
+        return Ok(());
+    };
+
+    let data = validate::<Vec<T>>(data)?;
+    let cached = match cache.lock().await.get(cache_key) {
+        Some(v) => validate::<Vec<T>>(v.clone())?,
+        None => Vec::new(),
+    };
+
+    let matches = data.iter().zip(&cached).filter(|&(a, b)| a != b);
+    if matches.count() > 0 || data.len() != cached.len() {
+        cache
+            .lock()
+            .await
+            .insert(cache_key, serde_json::to_value(&data)?);
+        let result_vec: Vec<T> = data
+            .iter()
+            .filter(|&x| !cached.contains(x))
+            .chain(cached.iter().filter(|&x| !data.contains(x)))
+            .cloned()
+            .collect();
+        let new = UpdateResponseBody::new(
+            StatusCode::from_u16(res.code).unwrap_or(StatusCode::OK),
+            &res.message,
+            Some(serde_json::to_value(result_vec)?),
+            res.event,
+        );
+        bus.broadcast(sjts(&new)).await;
+    }
+
+    Ok(())
+}
+
+pub struct PingTask {
+    events: Arc<EventBus>,
+    conn: Arc<DatabaseConnection>,
+    interval: Interval,
+}
+
+impl TaskTrait for PingTask {
+    fn name(&self) -> &'static str {
+        "ping"
+    }
+
+    fn wait(&mut self) -> WaitFuture<'_> {
+        Box::pin(async move {
+            self.interval.tick().await;
+        })
+    }
+
+    fn run(&mut self) -> RunFuture<'_> {
+        Box::pin(ping_servers(&self.conn, &self.events))
+    }
+}
+
+async fn ping_servers(conn: &DatabaseConnection, events: &EventBus) -> Result<(), AppError> {
+    let servers = servers_info::Entity::find().all(conn).await?;
+
+    let pings = servers.iter().map(|server| async move {
+        let res = craftping::ping(server.address.clone(), server.port).await;
+        (server.server_id, res)
+    });
+    let results: Vec<_> = futures::stream::iter(pings)
+        .buffer_unordered(PING_CONCURRENCY)
+        .collect()
+        .await;
+
+    for (server_id, res) in results {
+        let (players_online, online) = match res {
+            Ok(v) => (v.players_online as i32, 1),
+            Err(_) => (0, 0),
+        };
+
+        entities::players_graph::ActiveModel {
+            server_id: Set(server_id),
+            players_online: Set(players_online),
+            online: Set(online),
+            date: Set(Some(player_stats::now())),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        events
+            .publish(DomainEvent::new(Topic::PlayersGraph, server_id))
+            .await;
+    }
+
+    Ok(())
+}
+
+pub struct VersionSyncTask {
+    sync: Arc<VersionSync>,
+    events: Arc<EventBus>,
+    conn: Arc<DatabaseConnection>,
+    interval: Interval,
+}
+
+impl TaskTrait for VersionSyncTask {
+    fn name(&self) -> &'static str {
+        "version_sync"
+    }
+
+    fn wait(&mut self) -> WaitFuture<'_> {
+        Box::pin(async move {
+            self.interval.tick().await;
+        })
+    }
+
+    fn run(&mut self) -> RunFuture<'_> {
+        Box::pin(async move {
+            self.sync.run(&self.conn, &self.events).await?;
+            Ok(())
+        })
+    }
+}
+
+pub struct CleanupTask {
+    name: &'static str,
+    conn: Arc<DatabaseConnection>,
+    interval: Interval,
+    cleanup: CleanupFn,
+}
+
+impl TaskTrait for CleanupTask {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn wait(&mut self) -> WaitFuture<'_> {
+        Box::pin(async move {
+            self.interval.tick().await;
+        })
+    }
+
+    fn run(&mut self) -> RunFuture<'_> {
+        Box::pin(async move {
+            let removed = (self.cleanup)(&self.conn).await?;
+            if removed > 0 {
+                log::info!("Task {} removed {removed} rows", self.name);
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct PlayersRollupTask {
+    conn: Arc<DatabaseConnection>,
+    config: PlayersRetentionConfig,
+    interval: Interval,
+}
+
+impl TaskTrait for PlayersRollupTask {
+    fn name(&self) -> &'static str {
+        "players_rollup"
+    }
+
+    fn wait(&mut self) -> WaitFuture<'_> {
+        Box::pin(async move {
+            self.interval.tick().await;
+        })
+    }
+
+    fn run(&mut self) -> RunFuture<'_> {
+        Box::pin(async move {
+            let removed = player_stats::rollup(&self.conn, &self.config).await?;
+            if removed > 0 {
+                log::info!("Task players_rollup removed {removed} rows");
+            }
+            Ok(())
+        })
     }
 }
 
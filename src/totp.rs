@@ -0,0 +1,239 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::{
+    entities::{recovery_codes, user_totp},
+    error::AppError,
+    utils::random_hex,
+};
+
+const ISSUER: &str = "CraftList";
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+const RECOVERY_CODES: usize = 10;
+
+/// Fresh base32 secret, 160 bits as recommended for SHA-1.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+fn totp(secret: &str, account: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(secret.to_owned())
+        .to_bytes()
+        .map_err(|e| AppError::Totp(format!("{e:?}")))?;
+
+    // `:` separates issuer and account in the uri
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        1,
+        STEP,
+        secret,
+        Some(ISSUER.to_owned()),
+        account.replace(':', "_"),
+    )
+    .map_err(|e| AppError::Totp(e.to_string()))
+}
+
+pub fn otpauth_uri(secret: &str, account: &str) -> Result<String, AppError> {
+    Ok(totp(secret, account)?.get_url())
+}
+
+/// Time step the code was generated for, `None` if it is wrong or was already used.
+pub fn verify_code(model: &user_totp::Model, code: &str) -> Result<Option<i64>, AppError> {
+    let totp = totp(&model.secret, "")?;
+    let current = Utc::now().timestamp() / STEP as i64;
+
+    // One step of clock drift in either direction
+    let step = (current - 1..=current + 1).find(|step| {
+        *step > model.last_used_step.unwrap_or(i64::MIN)
+            && totp.generate(*step as u64 * STEP) == code
+    });
+
+    Ok(step)
+}
+
+/// Remembers the step so the same code can not be replayed, `false` if another request was first.
+pub async fn mark_used<C: ConnectionTrait>(db: &C, user_id: i32, step: i64) -> Result<bool, DbErr> {
+    let res = user_totp::Entity::update_many()
+        .col_expr(user_totp::Column::LastUsedStep, Expr::value(step))
+        .filter(user_totp::Column::UserId.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(user_totp::Column::LastUsedStep.is_null())
+                .add(user_totp::Column::LastUsedStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected == 1)
+}
+
+pub async fn find_enabled<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> Result<Option<user_totp::Model>, DbErr> {
+    user_totp::Entity::find_by_id(user_id)
+        .filter(user_totp::Column::EnabledAt.is_not_null())
+        .one(db)
+        .await
+}
+
+/// Checks a TOTP code or, failing that, an unused recovery code.
+pub async fn verify_second_factor<C: ConnectionTrait>(
+    db: &C,
+    model: &user_totp::Model,
+    code: &str,
+) -> Result<bool, AppError> {
+    let code = code.trim();
+
+    if code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(match verify_code(model, code)? {
+            Some(step) => mark_used(db, model.user_id, step).await?,
+            None => false,
+        });
+    }
+
+    Ok(use_recovery_code(db, model.user_id, code).await?)
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    Sha256::digest(normalized.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+async fn use_recovery_code<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    code: &str,
+) -> Result<bool, DbErr> {
+    let res = recovery_codes::Entity::update_many()
+        .col_expr(
+            recovery_codes::Column::UsedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(recovery_codes::Column::UserId.eq(user_id))
+        .filter(recovery_codes::Column::CodeHash.eq(hash_recovery_code(code)))
+        .filter(recovery_codes::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected == 1)
+}
+
+/// Replaces every recovery code of the user, the plain codes are only ever shown here.
+pub async fn regenerate_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> Result<Vec<String>, DbErr> {
+    recovery_codes::Entity::delete_many()
+        .filter(recovery_codes::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODES)
+        .map(|_| {
+            let code = random_hex(5);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect();
+
+    recovery_codes::Entity::insert_many(codes.iter().map(|code| recovery_codes::ActiveModel {
+        user_id: Set(user_id),
+        code_hash: Set(hash_recovery_code(code)),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(secret: &str, last_used_step: Option<i64>) -> user_totp::Model {
+        user_totp::Model {
+            user_id: 1,
+            secret: secret.to_owned(),
+            enabled_at: None,
+            last_used_step,
+            created_at: None,
+        }
+    }
+
+    fn code_at(secret: &str, step: i64) -> String {
+        totp(secret, "").unwrap().generate(step as u64 * STEP)
+    }
+
+    #[test]
+    fn secrets_hold_160_bits() {
+        let secret = generate_secret();
+        let bytes = Secret::Encoded(secret.clone()).to_bytes().unwrap();
+
+        assert_eq!(bytes.len(), 20);
+        assert_ne!(secret, generate_secret());
+    }
+
+    #[test]
+    fn codes_verify_once_within_the_drift() {
+        let secret = generate_secret();
+        let current = Utc::now().timestamp() / STEP as i64;
+
+        let step = verify_code(&model(&secret, None), &code_at(&secret, current))
+            .unwrap()
+            .unwrap();
+        assert!((current..=current + 1).contains(&step));
+
+        let previous = code_at(&secret, current - 1);
+        assert!(verify_code(&model(&secret, None), &previous)
+            .unwrap()
+            .is_some());
+
+        // Replays and codes of older steps are refused
+        let code = code_at(&secret, step);
+        assert!(verify_code(&model(&secret, Some(step)), &code)
+            .unwrap()
+            .is_none());
+        let stale = code_at(&secret, current - 3);
+        assert!(verify_code(&model(&secret, None), &stale)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn uri_keeps_the_issuer_apart_from_the_account() {
+        let uri = otpauth_uri(&generate_secret(), "steve:alex@example.com").unwrap();
+
+        assert!(uri.starts_with("otpauth://totp/CraftList:steve_alex"));
+        assert!(uri.contains("issuer=CraftList"));
+    }
+
+    #[test]
+    fn recovery_codes_ignore_case_and_separators() {
+        assert_eq!(
+            hash_recovery_code("ab12c-3de45"),
+            hash_recovery_code(" AB12C 3DE45 ")
+        );
+        assert_ne!(
+            hash_recovery_code("ab12c-3de45"),
+            hash_recovery_code("ab12c-3de46")
+        );
+    }
+}
@@ -1,22 +1,38 @@
 use actix_web::{
-    body::MessageBody,
-    dev::{ServiceRequest, ServiceResponse},
-    error::{ErrorBadRequest, ErrorUnauthorized},
+    dev::Payload,
+    error::{ErrorBadRequest, ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized},
     http::header,
-    web, HttpRequest,
+    web, FromRequest, HttpRequest,
 };
-use actix_web_lab::middleware::Next;
-use chrono::{Duration, NaiveDateTime, Utc};
+use argon2::{
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        SaltString,
+    },
+    Argon2, PasswordHasher,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DbErr, EntityTrait, QueryFilter, Set, SqlErr, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::future::{ready, Ready};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 use crate::{
-    entities::{auth, sea_orm_active_enums::Role, users},
+    entities::{
+        auth, oauth_nonces,
+        sea_orm_active_enums::{Purpose, Role},
+        user_tokens,
+    },
     error::AppError,
-    Config,
+    jwt::JwtKeys,
+    permissions::{Grants, Permission},
 };
 
 pub fn validate<T>(body: Value) -> Result<T, AppError>
@@ -33,99 +49,530 @@ where
     Ok(req)
 }
 
+/// Argon2 hash of the password with a fresh salt, ready to be stored.
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    match Argon2::default().hash_password(password.as_bytes(), &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(e) => Err(ErrorInternalServerError(format!("Failed to hash password: {e}")).into()),
+    }
+}
+
+const ACCESS_TOKEN_LIFETIME: Duration = Duration::minutes(10);
+const REFRESH_TOKEN_LIFETIME: Duration = Duration::days(1);
+/// Rotation keeps a session alive for at most this long after the login
+const SESSION_MAX_LIFETIME: Duration = Duration::days(30);
+const MFA_TOKEN_LIFETIME: Duration = Duration::minutes(5);
+const OAUTH_STATE_LIFETIME: Duration = Duration::minutes(10);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i32,
     exp: i64,
+    // Two refresh tokens issued within the same second would otherwise be identical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
 }
 
-fn create_token(user_id: i32, expiration: NaiveDateTime, secret_key: &[u8]) -> String {
+fn create_token(
+    user_id: i32,
+    expiration: NaiveDateTime,
+    jti: Option<String>,
+    keys: &JwtKeys,
+) -> String {
     let claims = Claims {
         sub: user_id,
         exp: expiration.and_utc().timestamp(),
+        jti,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret_key),
-    )
-    .expect("token creation failed")
+    keys.encode(&claims).expect("token creation failed")
 }
 
-pub fn create_access_token(user_id: i32, secret_key: &[u8]) -> String {
+/// Random hex string made of `bytes` random bytes.
+pub fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    buf.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccessClaims {
+    sub: i32,
+    /// For other services, this one only checks permissions
+    roles: Vec<Role>,
+    permissions: Vec<Permission>,
+    iss: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+}
+
+pub fn create_access_token(user_id: i32, grants: &Grants, keys: &JwtKeys) -> String {
     let now = Utc::now().naive_utc();
     let expiration = now
-        .checked_add_signed(Duration::minutes(10))
+        .checked_add_signed(ACCESS_TOKEN_LIFETIME)
         .expect("valid timestamp");
 
-    create_token(user_id, expiration, secret_key)
+    let claims = AccessClaims {
+        sub: user_id,
+        roles: grants.roles.clone(),
+        permissions: grants.permissions.clone(),
+        iss: keys.issuer.clone(),
+        aud: keys.audience.clone(),
+        iat: now.and_utc().timestamp(),
+        exp: expiration.and_utc().timestamp(),
+    };
+
+    keys.encode(&claims).expect("token creation failed")
+}
+
+// Refresh tokens carry no audience, so they are rejected here and the other way around
+fn validate_access_token(
+    access_token: &str,
+    keys: &JwtKeys,
+) -> Result<AccessClaims, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::default();
+    validation.leeway = 0;
+    validation.set_issuer(&[&keys.issuer]);
+    validation.set_audience(&[&keys.audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    keys.decode(access_token, validation)
 }
 
+/// Starts a new session, tokens rotated from it share its family.
 pub async fn create_refresh_token(
     db: &DatabaseConnection,
     user_id: i32,
-    secret_key: &[u8],
-) -> String {
+    keys: &JwtKeys,
+) -> Result<String, AppError> {
     let now = Utc::now().naive_utc();
-    let expiration = now
-        .checked_add_signed(Duration::days(1))
+    let (token, _) = insert_refresh_token(db, user_id, random_hex(16), now, keys).await?;
+    Ok(token)
+}
+
+/// Expiry of a token issued at `now`, never past the end of its session.
+fn refresh_token_expiration(now: NaiveDateTime, started_at: NaiveDateTime) -> NaiveDateTime {
+    let token_end = now
+        .checked_add_signed(REFRESH_TOKEN_LIFETIME)
+        .expect("valid timestamp");
+    let session_end = started_at
+        .checked_add_signed(SESSION_MAX_LIFETIME)
         .expect("valid timestamp");
 
-    let token = create_token(user_id, expiration, secret_key);
+    token_end.min(session_end)
+}
+
+// Lookups only need equality, a plain digest is enough for random signed tokens
+fn refresh_token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+async fn insert_refresh_token<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    family: String,
+    started_at: NaiveDateTime,
+    keys: &JwtKeys,
+) -> Result<(String, auth::Model), DbErr> {
+    let now = Utc::now().naive_utc();
+    let expiration = refresh_token_expiration(now, started_at);
 
-    let new_token = auth::ActiveModel {
-        token: Set(token.clone()),
+    let token = create_token(user_id, expiration, Some(random_hex(16)), keys);
+
+    let model = auth::ActiveModel {
+        token_hash: Set(refresh_token_hash(&token)),
         user_id: Set(user_id),
+        family: Set(family),
+        session_started_at: Set(started_at),
         created_at: Set(Some(now)),
         expires_at: Set(expiration),
         ..Default::default()
-    };
+    }
+    .insert(db)
+    .await?;
 
-    new_token.insert(db).await.unwrap();
+    Ok((token, model))
+}
 
-    token
+/// Exchanges a refresh token for a new one of the same family. A token that
+/// was already rotated means it leaked, so the whole family gets revoked.
+pub async fn rotate_refresh_token(
+    db: &DatabaseConnection,
+    refresh_token: &str,
+    keys: &JwtKeys,
+) -> Result<(i32, String), AppError> {
+    let stored = find_refresh_token(db, refresh_token, keys).await?;
+    let now = Utc::now().naive_utc();
+
+    if stored.revoked_at.is_some() {
+        return Err(refresh_token_reused(db, &stored).await);
+    }
+
+    // The last token of a session expires with it, this only guards a clock skew
+    if refresh_token_expiration(now, stored.session_started_at) <= now {
+        return Err(ErrorUnauthorized("Session expired").into());
+    }
+
+    let txn = db.begin().await?;
+
+    // Of two requests racing with the same token only one gets to rotate it
+    let revoked = auth::Entity::update_many()
+        .col_expr(auth::Column::RevokedAt, Expr::value(now))
+        .filter(auth::Column::Id.eq(stored.id))
+        .filter(auth::Column::RevokedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    if revoked.rows_affected == 0 {
+        txn.rollback().await?;
+        return Err(refresh_token_reused(db, &stored).await);
+    }
+
+    let (token, replacement) = insert_refresh_token(
+        &txn,
+        stored.user_id,
+        stored.family.clone(),
+        stored.session_started_at,
+        keys,
+    )
+    .await?;
+
+    auth::Entity::update_many()
+        .col_expr(auth::Column::ReplacedBy, Expr::value(replacement.id))
+        .filter(auth::Column::Id.eq(stored.id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok((stored.user_id, token))
 }
 
-pub async fn validate_refresh_token(
+async fn refresh_token_reused(db: &DatabaseConnection, stored: &auth::Model) -> AppError {
+    log::warn!(
+        "Refresh token reuse for user {}, revoking session {}",
+        stored.user_id,
+        stored.family
+    );
+
+    match revoke_refresh_family(db, &stored.family).await {
+        Ok(_) => ErrorUnauthorized("Refresh token reuse detected").into(),
+        Err(e) => e.into(),
+    }
+}
+
+/// Looks up a refresh token that is signed, not expired and known to the database.
+/// Revoked tokens are returned too, callers decide what reuse means for them.
+pub async fn find_refresh_token(
     db: &DatabaseConnection,
     refresh_token: &str,
+    keys: &JwtKeys,
+) -> Result<auth::Model, AppError> {
+    let Ok(claims) = validate_refresh_token(refresh_token, keys).await else {
+        return Err(ErrorUnauthorized("Invalid token").into());
+    };
+
+    let stored = auth::Entity::find()
+        .filter(auth::Column::TokenHash.eq(refresh_token_hash(refresh_token)))
+        .filter(auth::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .one(db)
+        .await?;
+
+    match stored {
+        Some(v) if v.user_id == claims.sub => Ok(v),
+        _ => Err(ErrorUnauthorized("Invalid token").into()),
+    }
+}
+
+/// Ends a session, every token of the family stops working.
+pub async fn revoke_refresh_family(db: &DatabaseConnection, family: &str) -> Result<u64, DbErr> {
+    let res = auth::Entity::update_many()
+        .col_expr(auth::Column::RevokedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(auth::Column::Family.eq(family))
+        .filter(auth::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+/// Ends every session of the user.
+pub async fn revoke_user_refresh_tokens(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<u64, DbErr> {
+    let res = auth::Entity::update_many()
+        .col_expr(auth::Column::RevokedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(auth::Column::UserId.eq(user_id))
+        .filter(auth::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+/// Deletes refresh tokens past their expiry, revoked or not. They can not be
+/// presented again anyway, so there is nothing left to detect reuse of.
+pub async fn purge_expired_refresh_tokens(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let res = auth::Entity::delete_many()
+        .filter(auth::Column::ExpiresAt.lte(Utc::now().naive_utc()))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+#[derive(Serialize, Deserialize)]
+struct UserTokenClaims {
+    sub: i32,
+    exp: i64,
+    purpose: Purpose,
+    jti: String,
+}
+
+// Separate key, so a mailed token never passes as an access token
+fn user_token_key(secret_key: &[u8]) -> Vec<u8> {
+    [secret_key, b".user-tokens"].concat()
+}
+
+/// Signed token that can be used once for `purpose`, meant to be sent by mail.
+pub async fn create_user_token(
+    db: &DatabaseConnection,
+    user_id: i32,
+    purpose: Purpose,
+    lifetime: Duration,
     secret_key: &[u8],
-) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(
-        refresh_token,
-        &DecodingKey::from_secret(secret_key),
+) -> Result<String, AppError> {
+    let now = Utc::now().naive_utc();
+    let expiration = now.checked_add_signed(lifetime).expect("valid timestamp");
+    let jti = random_hex(16);
+
+    user_tokens::ActiveModel {
+        user_id: Set(user_id),
+        purpose: Set(purpose),
+        jti: Set(jti.clone()),
+        created_at: Set(Some(now)),
+        expires_at: Set(expiration),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let claims = UserTokenClaims {
+        sub: user_id,
+        exp: expiration.and_utc().timestamp(),
+        purpose,
+        jti,
+    };
+
+    Ok(encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&user_token_key(secret_key)),
+    )?)
+}
+
+/// Makes every unused token of the user for `purpose` unusable, so only a newer one works.
+pub async fn revoke_user_tokens<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    purpose: Purpose,
+) -> Result<u64, DbErr> {
+    let res = user_tokens::Entity::update_many()
+        .col_expr(
+            user_tokens::Column::UsedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .filter(user_tokens::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+/// Marks the token as used and returns its user, fails if it was used before.
+pub async fn consume_user_token<C: ConnectionTrait>(
+    db: &C,
+    token: &str,
+    purpose: Purpose,
+    secret_key: &[u8],
+) -> Result<i32, AppError> {
+    let invalid = || AppError::from(ErrorBadRequest("Invalid or expired token"));
+
+    let claims = decode::<UserTokenClaims>(
+        token,
+        &DecodingKey::from_secret(&user_token_key(secret_key)),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|_| invalid())?
+    .claims;
+
+    if claims.purpose != purpose {
+        return Err(invalid());
+    }
+
+    let now = Utc::now().naive_utc();
+    let res = user_tokens::Entity::update_many()
+        .col_expr(user_tokens::Column::UsedAt, Expr::value(now))
+        .filter(user_tokens::Column::Jti.eq(claims.jti))
+        .filter(user_tokens::Column::UserId.eq(claims.sub))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .filter(user_tokens::Column::UsedAt.is_null())
+        .filter(user_tokens::Column::ExpiresAt.gt(now))
+        .exec(db)
+        .await?;
+
+    if res.rows_affected == 0 {
+        return Err(invalid());
+    }
+
+    Ok(claims.sub)
+}
+
+#[derive(Serialize, Deserialize)]
+struct MfaClaims {
+    sub: i32,
+    exp: i64,
+}
+
+fn mfa_key(secret_key: &[u8]) -> Vec<u8> {
+    [secret_key, b".mfa"].concat()
+}
+
+/// Proves the password step of a login, exchanged for tokens together with a TOTP code.
+pub fn create_mfa_token(user_id: i32, secret_key: &[u8]) -> Result<String, AppError> {
+    let expiration = Utc::now().naive_utc() + MFA_TOKEN_LIFETIME;
+    let claims = MfaClaims {
+        sub: user_id,
+        exp: expiration.and_utc().timestamp(),
+    };
+
+    Ok(encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&mfa_key(secret_key)),
+    )?)
+}
+
+pub fn validate_mfa_token(token: &str, secret_key: &[u8]) -> Result<i32, AppError> {
+    decode::<MfaClaims>(
+        token,
+        &DecodingKey::from_secret(&mfa_key(secret_key)),
         &Validation::new(Algorithm::HS256),
+    )
+    .map(|v| v.claims.sub)
+    .map_err(|_| ErrorUnauthorized("Login expired, start again").into())
+}
+
+#[derive(Serialize, Deserialize)]
+struct OAuthStateClaims {
+    exp: i64,
+    nonce: String,
+    /// Account the Minecraft profile gets linked to, logging in when empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link_user: Option<i32>,
+}
+
+fn oauth_state_key(secret_key: &[u8]) -> Vec<u8> {
+    [secret_key, b".oauth-state"].concat()
+}
+
+/// Signed `state` for the provider and the nonce the browser has to present with it.
+pub fn create_oauth_state(
+    link_user: Option<i32>,
+    secret_key: &[u8],
+) -> Result<(String, String), AppError> {
+    let nonce = random_hex(16);
+    let claims = OAuthStateClaims {
+        exp: (Utc::now().naive_utc() + OAUTH_STATE_LIFETIME)
+            .and_utc()
+            .timestamp(),
+        nonce: nonce.clone(),
+        link_user,
+    };
+
+    let state = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&oauth_state_key(secret_key)),
     )?;
-    let claims = token_data.claims;
 
-    if claims.exp < Utc::now().timestamp() {
-        return Err(jsonwebtoken::errors::ErrorKind::ExpiredSignature.into());
+    Ok((state, nonce))
+}
+
+/// Returns the account to link, if any. Fails unless the nonce matches the one set for this
+/// browser and the state was not used before.
+pub async fn consume_oauth_state(
+    db: &DatabaseConnection,
+    state: &str,
+    nonce: Option<&str>,
+    secret_key: &[u8],
+) -> Result<Option<i32>, AppError> {
+    let claims = decode::<OAuthStateClaims>(
+        state,
+        &DecodingKey::from_secret(&oauth_state_key(secret_key)),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|_| ErrorBadRequest("Sign in expired, start again"))?
+    .claims;
+
+    if nonce != Some(claims.nonce.as_str()) {
+        return Err(ErrorBadRequest("Sign in was started in another browser").into());
     }
 
-    let stored_token = auth::Entity::find()
-        .filter(auth::Column::Token.eq(refresh_token))
-        .filter(auth::Column::ExpiresAt.gt(Utc::now().naive_utc()))
-        .one(db)
-        .await
-        .unwrap();
+    // The primary key lets only one of two requests with the same state through
+    let used = oauth_nonces::ActiveModel {
+        nonce: Set(claims.nonce),
+        expires_at: Set(DateTime::from_timestamp(claims.exp, 0)
+            .unwrap_or_default()
+            .naive_utc()),
+    }
+    .insert(db)
+    .await;
 
-    match stored_token {
-        Some(_) => Ok(claims),
-        None => Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into()),
+    match used {
+        Ok(_) => Ok(claims.link_user),
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            Err(ErrorBadRequest("Sign in was already used, start again").into())
+        }
+        Err(e) => Err(e.into()),
     }
 }
 
-pub async fn validate_token(
+/// Deletes used OAuth nonces whose state expired, they can not be presented again.
+pub async fn purge_oauth_nonces(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let res = oauth_nonces::Entity::delete_many()
+        .filter(oauth_nonces::Column::ExpiresAt.lte(Utc::now().naive_utc()))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+/// Deletes mailed tokens that were used or can no longer be.
+pub async fn purge_user_tokens(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let res = user_tokens::Entity::delete_many()
+        .filter(
+            Condition::any()
+                .add(user_tokens::Column::UsedAt.is_not_null())
+                .add(user_tokens::Column::ExpiresAt.lte(Utc::now().naive_utc())),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
+
+async fn validate_refresh_token(
     access_token: &str,
-    secret_key: &[u8],
+    keys: &JwtKeys,
 ) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(
-        access_token,
-        &DecodingKey::from_secret(secret_key),
-        &Validation::new(Algorithm::HS256),
-    )?;
-    let claims = token_data.claims;
+    let claims: Claims = keys.decode(access_token, Validation::default())?;
 
     if claims.exp < Utc::now().timestamp() {
         return Err(jsonwebtoken::errors::ErrorKind::ExpiredSignature.into());
@@ -134,82 +581,107 @@ pub async fn validate_token(
     Ok(claims)
 }
 
-pub async fn auth_middleware(
-    config: web::Data<Config>,
-    req: ServiceRequest,
-    next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
-    let token = req
+const BEARER: &str = "Bearer ";
+
+// Only the token itself, the scheme is matched case insensitively
+fn bearer_token(req: &HttpRequest) -> Result<&str, actix_web::Error> {
+    let value = req
         .headers()
-        .get("Authentication")
+        .get(header::AUTHORIZATION)
         .and_then(|value| value.to_str().ok())
-        .map(str::to_owned);
+        .ok_or(ErrorUnauthorized("Missing JWT token"))?;
 
-    let Some(token_v) = token else {
-        return Err(ErrorUnauthorized("Missing JWT token"));
-    };
+    match value.get(..BEARER.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(BEARER) => Ok(value[BEARER.len()..].trim()),
+        _ => Err(ErrorUnauthorized("Expected a Bearer token")),
+    }
+}
 
-    match validate_token(&token_v, config.json_token.as_bytes()).await {
-        Ok(_) => next.call(req).await,
-        Err(_) => Err(ErrorUnauthorized("Invalid token")),
+/// User of a valid `Authorization: Bearer` access token. Permissions are the
+/// ones at the time the token was issued, changes apply on the next refresh.
+pub struct AuthUser {
+    pub id: i32,
+    pub permissions: Vec<Permission>,
+}
+
+impl FromRequest for AuthUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(AuthUser::from_http(req))
     }
 }
 
-pub async fn admin_auth_middleware(
-    db: web::Data<Arc<DatabaseConnection>>,
-    config: web::Data<Config>,
-    req: ServiceRequest,
-    next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
-    let Ok(claims) = validate_token(req.get_token()?, config.json_token.as_bytes()).await else {
-        return Err(ErrorUnauthorized("Invalid token"));
-    };
+impl AuthUser {
+    fn from_http(req: &HttpRequest) -> Result<Self, actix_web::Error> {
+        let keys = req
+            .app_data::<web::Data<Arc<JwtKeys>>>()
+            .ok_or(ErrorInternalServerError("Missing signing keys"))?;
 
-    let user = users::Entity::find()
-        .filter(users::Column::Id.eq(claims.sub))
-        .filter(users::Column::Role.eq(Role::Admin))
-        .one(db.get_ref().as_ref())
-        .await
-        .unwrap();
+        let claims = validate_access_token(bearer_token(req)?, keys)
+            .map_err(|_| ErrorUnauthorized("Invalid token"))?;
 
-    if user.is_some() {
-        return next.call(req).await;
+        Ok(AuthUser {
+            id: claims.sub,
+            permissions: claims.permissions,
+        })
     }
 
-    Err(ErrorUnauthorized(
-        "You are not authorized to access this resource",
-    ))
+    pub fn require(&self, permission: Permission) -> Result<(), actix_web::Error> {
+        match self.permissions.contains(&permission) {
+            true => Ok(()),
+            false => Err(ErrorForbidden(
+                "You are not authorized to access this resource",
+            )),
+        }
+    }
 }
 
-pub trait RequestUtils {
-    fn get_user_id(&self) -> Result<i32, AppError>;
-    fn get_token(&self) -> Result<&str, actix_web::Error>;
+/// Address of the client, taken from `X-Forwarded-For` only when the proxy is trusted.
+pub fn client_ip(req: &HttpRequest, trust_forwarded_for: bool) -> Option<IpAddr> {
+    if trust_forwarded_for {
+        req.connection_info()
+            .realip_remote_addr()
+            .and_then(parse_ip)
+    } else {
+        req.peer_addr().map(|v| v.ip())
+    }
 }
 
-macro_rules! impl_request_utils {
-    ($type:ty) => {
-        impl RequestUtils for $type {
-            fn get_user_id(&self) -> Result<i32, AppError> {
-                let config = self.app_data::<Config>().unwrap();
+// `realip_remote_addr` falls back to the peer address, which includes the port
+fn parse_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse()
+        .ok()
+        .or_else(|| addr.parse::<SocketAddr>().ok().map(|v| v.ip()))
+}
 
-                let token_data = decode::<Claims>(
-                    self.get_token()?,
-                    &DecodingKey::from_secret(config.json_token.as_bytes()),
-                    &Validation::new(Algorithm::HS256),
-                )?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                Ok(token_data.claims.sub)
-            }
+    #[test]
+    fn rotation_keeps_the_session_limit() {
+        let started_at = Utc::now().naive_utc();
 
-            fn get_token(&self) -> Result<&str, actix_web::Error> {
-                self.headers()
-                    .get(header::AUTHORIZATION)
-                    .and_then(|value| value.to_str().ok())
-                    .ok_or(ErrorBadRequest("Missing JWT token"))
-            }
-        }
-    };
-}
+        let fresh = refresh_token_expiration(started_at, started_at);
+        assert_eq!(fresh, started_at + REFRESH_TOKEN_LIFETIME);
+
+        let late = started_at + SESSION_MAX_LIFETIME - Duration::hours(2);
+        let expiration = refresh_token_expiration(late, started_at);
+        assert_eq!(expiration, started_at + SESSION_MAX_LIFETIME);
 
-impl_request_utils!(HttpRequest);
-impl_request_utils!(ServiceRequest);
+        let after = started_at + SESSION_MAX_LIFETIME + Duration::hours(1);
+        assert!(refresh_token_expiration(after, started_at) <= after);
+    }
+
+    #[test]
+    fn refresh_token_hashes_fit_the_column() {
+        let token = "a".repeat(1024);
+
+        let hash = refresh_token_hash(&token);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, refresh_token_hash(&token));
+        assert_ne!(hash, refresh_token_hash("b"));
+    }
+}
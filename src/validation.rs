@@ -0,0 +1,239 @@
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use validator::{Validate, ValidationError};
+
+use crate::error::AppError;
+
+/// Json body that passed its `Validate` rules, otherwise the request is
+/// answered with 422 and every invalid field.
+pub struct ValidJson<T>(pub T);
+
+impl<T> Deref for ValidJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for ValidJson<T> {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, AppError>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+
+        Box::pin(async move {
+            let data = json.await?.into_inner();
+            data.validate()?;
+            Ok(ValidJson(data))
+        })
+    }
+}
+
+fn error(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// 3 to 16 letters, digits or underscores, same as Minecraft names.
+pub fn username(value: &str) -> Result<(), ValidationError> {
+    if !(3..=16).contains(&value.len()) {
+        return Err(error("length", "Must be between 3 and 16 characters long"));
+    }
+
+    if !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(error(
+            "characters",
+            "Can only contain letters, digits and underscores",
+        ));
+    }
+
+    Ok(())
+}
+
+/// At least 8 characters with a letter and a digit.
+pub fn password(value: &str) -> Result<(), ValidationError> {
+    if value.chars().count() < 8 {
+        return Err(error("length", "Must be at least 8 characters long"));
+    }
+
+    if value.chars().count() > 128 {
+        return Err(error("length", "Must be at most 128 characters long"));
+    }
+
+    if !value.chars().any(char::is_alphabetic) || !value.chars().any(|c| c.is_ascii_digit()) {
+        return Err(error("strength", "Must contain a letter and a digit"));
+    }
+
+    Ok(())
+}
+
+/// Not empty once surrounding whitespace is removed.
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("blank", "Can not be empty"));
+    }
+
+    Ok(())
+}
+
+/// Category names as sent by clients, each one not blank and at most 64 characters.
+pub fn category_names(values: &[String]) -> Result<(), ValidationError> {
+    for value in values {
+        not_blank(value)?;
+
+        if value.chars().count() > 64 {
+            return Err(error(
+                "length",
+                "Each one must be at most 64 characters long",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowercase letters, digits and single hyphens between them, usable in urls.
+pub fn slug(value: &str) -> Result<(), ValidationError> {
+    if !(1..=64).contains(&value.len()) {
+        return Err(error("length", "Must be between 1 and 64 characters long"));
+    }
+
+    let valid = value.split('-').all(|part| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+
+    if !valid {
+        return Err(error(
+            "slug",
+            "Can only contain lowercase letters and digits separated by single hyphens",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Hex colour like `#55ff55`.
+pub fn colour(value: &str) -> Result<(), ValidationError> {
+    let valid = value.len() == 7
+        && value.starts_with('#')
+        && value[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if !valid {
+        return Err(error("colour", "Must be a hex colour like #55ff55"));
+    }
+
+    Ok(())
+}
+
+/// Domain name or IP address, without a port.
+pub fn host(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<std::net::IpAddr>().is_ok() {
+        return Ok(());
+    }
+
+    let valid = !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if !valid {
+        return Err(error("host", "Must be a domain name or an IP address"));
+    }
+
+    Ok(())
+}
+
+/// Language code like `en` or `pt-BR`.
+pub fn language(value: &str) -> Result<(), ValidationError> {
+    let (language, region) = match value.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (value, None),
+    };
+
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && region.is_none_or(|v| v.len() == 2 && v.chars().all(|c| c.is_ascii_uppercase()));
+
+    if !valid {
+        return Err(error(
+            "language",
+            "Must be a language code like en or pt-BR",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Two letter country code like `DE`.
+pub fn country(value: &str) -> Result<(), ValidationError> {
+    if value.len() != 2 || !value.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(error(
+            "country",
+            "Must be a two letter country code like DE",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Absolute http or https url.
+pub fn http_url(value: &str) -> Result<(), ValidationError> {
+    let valid = value.len() <= 255
+        && reqwest::Url::parse(value)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+
+    if !valid {
+        return Err(error("url", "Must be an http or https url"));
+    }
+
+    Ok(())
+}
+
+/// Invite link on discord.gg or discord.com.
+pub fn discord_invite(value: &str) -> Result<(), ValidationError> {
+    http_url(value)?;
+
+    let valid = ["https://discord.gg/", "https://discord.com/invite/"]
+        .iter()
+        .any(|prefix| {
+            value.strip_prefix(prefix).is_some_and(|code| {
+                !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        });
+
+    if !valid {
+        return Err(error(
+            "discord",
+            "Must be a discord.gg or discord.com/invite link",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category_names_checks_every_item() {
+        let names = |values: &[&str]| values.iter().map(|v| (*v).to_owned()).collect::<Vec<_>>();
+
+        assert!(category_names(&names(&["Survival", "PvP"])).is_ok());
+        assert!(category_names(&names(&["Survival", "  "])).is_err());
+        assert!(category_names(&names(&[&"a".repeat(65)])).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use actix_web::error::{ErrorBadGateway, ErrorConflict};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use sea_orm::{
+    sea_query::{Alias, CaseStatement, Expr},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    TransactionTrait,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    controllers::servers::ServerRef,
+    entities::{sea_orm_active_enums::ReleaseType, servers, servers_info, versions},
+    error::AppError,
+    events::{DomainEvent, EventBus, Topic},
+};
+
+/// Each source is an http(s) url or the path of a local JSON file.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct VersionSyncConfig {
+    /// Runs the periodic sync, the admin endpoint works either way
+    pub enabled: bool,
+    /// Version manifest in the format Mojang publishes
+    pub manifest: String,
+    /// Protocol numbers per version name, versions without one are not created
+    pub protocols: String,
+}
+
+impl Default for VersionSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manifest: "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json".to_owned(),
+            protocols: "https://raw.githubusercontent.com/PrismarineJS/minecraft-data/master/data/pc/common/protocolVersions.json".to_owned(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    /// Newest first
+    versions: Vec<ManifestVersion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestVersion {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    release_time: DateTime<Utc>,
+}
+
+/// Either `{"1.20.4": 765}` or the minecraft-data list of `{"minecraftVersion", "version"}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Protocols {
+    Map(HashMap<String, i32>),
+    List(Vec<ProtocolEntry>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProtocolEntry {
+    minecraft_version: String,
+    version: i32,
+}
+
+impl Protocols {
+    fn into_map(self) -> HashMap<String, i32> {
+        match self {
+            Protocols::Map(map) => map,
+            // Listed newest first, the first entry of a name wins
+            Protocols::List(list) => {
+                let mut map = HashMap::new();
+                for entry in list {
+                    map.entry(entry.minecraft_version).or_insert(entry.version);
+                }
+                map
+            }
+        }
+    }
+}
+
+fn release_type(kind: &str) -> Option<ReleaseType> {
+    match kind {
+        "release" => Some(ReleaseType::Release),
+        "snapshot" => Some(ReleaseType::Snapshot),
+        "old_beta" => Some(ReleaseType::Beta),
+        "old_alpha" => Some(ReleaseType::Alpha),
+        _ => None,
+    }
+}
+
+/// Answer when a change would turn version ranges of servers around.
+pub const INVERTED_RANGES: &str =
+    "Servers would support a minimum version newer than their maximum";
+
+/// Numbers `ordinal` from 1 again. Versions with a release date are sorted by it,
+/// the ones without keep their place between them. Returns the servers whose minimum
+/// version ends up newer than their maximum, callers roll back when there are any.
+#[must_use = "servers with an inverted version range have to be reported"]
+pub async fn reorder<C: ConnectionTrait>(db: &C) -> Result<Vec<ServerRef>, DbErr> {
+    let rows = versions::Entity::find()
+        .order_by_asc(versions::Column::Ordinal)
+        .order_by_asc(versions::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut dated: Vec<&versions::Model> =
+        rows.iter().filter(|v| v.released_at.is_some()).collect();
+    dated.sort_by_key(|v| v.released_at);
+    let mut dated = dated.into_iter();
+
+    let mut moved = Vec::new();
+    let mut ordinals = CaseStatement::new();
+    for (i, slot) in rows.iter().enumerate() {
+        let version = match slot.released_at {
+            Some(_) => dated.next().unwrap_or(slot),
+            None => slot,
+        };
+
+        let ordinal = i as i32 + 1;
+        if version.ordinal != ordinal {
+            moved.push(version.id);
+            ordinals = ordinals.case(versions::Column::Id.eq(version.id), ordinal);
+        }
+    }
+
+    // One statement however many versions moved
+    if !moved.is_empty() {
+        versions::Entity::update_many()
+            .col_expr(
+                versions::Column::Ordinal,
+                ordinals
+                    .finally(Expr::col(versions::Column::Ordinal))
+                    .into(),
+            )
+            .filter(versions::Column::Id.is_in(moved))
+            .exec(db)
+            .await?;
+    }
+
+    inverted_ranges(db).await
+}
+
+/// Servers whose minimum version is now newer than their maximum.
+pub async fn inverted_ranges<C: ConnectionTrait>(db: &C) -> Result<Vec<ServerRef>, DbErr> {
+    servers::Entity::find()
+        .join(JoinType::InnerJoin, servers::Relation::ServersInfo.def())
+        .join_as(
+            JoinType::InnerJoin,
+            servers_info::Relation::Versions1.def(),
+            Alias::new("v1"),
+        )
+        .join_as(
+            JoinType::InnerJoin,
+            servers_info::Relation::Versions2.def(),
+            Alias::new("v2"),
+        )
+        .filter(
+            Expr::col((Alias::new("v1"), versions::Column::Ordinal))
+                .gt(Expr::col((Alias::new("v2"), versions::Column::Ordinal))),
+        )
+        .select_only()
+        .column(servers::Column::Id)
+        .column(servers::Column::Name)
+        .into_model()
+        .all(db)
+        .await
+}
+
+#[derive(Serialize, ToSchema, Default)]
+pub struct SyncReport {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    /// Versions of the manifest without a known protocol number
+    skipped: usize,
+}
+
+pub struct VersionSync {
+    http: Client,
+    config: VersionSyncConfig,
+}
+
+impl VersionSync {
+    pub fn new(config: VersionSyncConfig) -> Self {
+        Self {
+            http: Client::new(),
+            config,
+        }
+    }
+
+    /// Whether the periodic sync should run.
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    async fn load<T: DeserializeOwned>(&self, source: &str) -> Result<T, AppError> {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            let res = self
+                .http
+                .get(source)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(|e| ErrorBadGateway(format!("Could not fetch {source}: {e}")))?;
+
+            return Ok(res
+                .json()
+                .await
+                .map_err(|e| ErrorBadGateway(format!("Invalid JSON from {source}: {e}")))?);
+        }
+
+        Ok(serde_json::from_slice(&tokio::fs::read(source).await?)?)
+    }
+
+    /// Creates the missing versions and refreshes the release info of known ones, by name.
+    pub async fn run(
+        &self,
+        db: &DatabaseConnection,
+        events: &EventBus,
+    ) -> Result<SyncReport, AppError> {
+        let manifest: Manifest = self.load(&self.config.manifest).await?;
+        let protocols = match self.config.protocols.is_empty() {
+            true => HashMap::new(),
+            false => self
+                .load::<Protocols>(&self.config.protocols)
+                .await?
+                .into_map(),
+        };
+
+        let mut existing: HashMap<String, versions::Model> = versions::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|v| (v.name.clone(), v))
+            .collect();
+
+        let mut report = SyncReport::default();
+        let mut changed = Vec::new();
+        let txn = db.begin().await?;
+
+        // Oldest first, so new rows get ids in release order
+        for entry in manifest.versions.into_iter().rev() {
+            let kind = release_type(&entry.kind);
+            let released_at = Some(entry.release_time.naive_utc());
+            let protocol = protocols.get(&entry.id).copied();
+
+            let Some(version) = existing.remove(&entry.id) else {
+                let Some(protocol) = protocol else {
+                    report.skipped += 1;
+                    continue;
+                };
+
+                let model = versions::ActiveModel {
+                    name: Set(entry.id),
+                    protocol: Set(protocol),
+                    release_type: Set(kind),
+                    released_at: Set(released_at),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+                changed.push(model.id);
+                report.created += 1;
+                continue;
+            };
+
+            let protocol = protocol.unwrap_or(version.protocol);
+            if version.release_type == kind
+                && version.released_at == released_at
+                && version.protocol == protocol
+            {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let id = version.id;
+            let mut model = version.into_active_model();
+            model.protocol = Set(protocol);
+            model.release_type = Set(kind);
+            model.released_at = Set(released_at);
+            model.update(&txn).await?;
+            changed.push(id);
+            report.updated += 1;
+        }
+
+        if !changed.is_empty() {
+            let inverted = reorder(&txn).await?;
+            if !inverted.is_empty() {
+                txn.rollback().await?;
+                let names: Vec<&str> = inverted.iter().map(|v| v.name.as_str()).collect();
+                return Err(
+                    ErrorConflict(format!("{INVERTED_RANGES}: {}", names.join(", "))).into(),
+                );
+            }
+        }
+
+        txn.commit().await?;
+
+        for id in changed {
+            events.publish(DomainEvent::new(Topic::Versions, id)).await;
+        }
+
+        log::info!(
+            "Version sync created {}, updated {}, skipped {}",
+            report.created,
+            report.updated,
+            report.skipped
+        );
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use sea_orm::{ConnectOptions, Database, DbBackend, Schema};
+
+    use super::*;
+    use crate::entities::users;
+
+    // An in-memory SQLite database stands in for MySQL
+    async fn stand_in() -> DatabaseConnection {
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1);
+        let conn = Database::connect(options).await.unwrap();
+
+        let schema = Schema::new(DbBackend::Sqlite);
+        for table in [
+            schema.create_table_from_entity(versions::Entity),
+            schema.create_table_from_entity(users::Entity),
+            schema.create_table_from_entity(servers::Entity),
+            schema.create_table_from_entity(servers_info::Entity),
+        ] {
+            conn.execute(conn.get_database_backend().build(&table))
+                .await
+                .unwrap();
+        }
+        conn
+    }
+
+    async fn version(db: &DatabaseConnection, name: &str, ordinal: i32, year: i32) -> i32 {
+        versions::ActiveModel {
+            name: Set(name.to_owned()),
+            protocol: Set(ordinal),
+            ordinal: Set(ordinal),
+            released_at: Set(
+                NaiveDate::from_ymd_opt(year, 1, 1).and_then(|v| v.and_hms_opt(0, 0, 0))
+            ),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+        .id
+    }
+
+    async fn ordinals(db: &DatabaseConnection) -> Vec<(String, i32)> {
+        versions::Entity::find()
+            .order_by_asc(versions::Column::Ordinal)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.name, v.ordinal))
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn reorder_sorts_by_release_date() {
+        let db = stand_in().await;
+        version(&db, "1.20", 1, 2023).await;
+        version(&db, "1.8", 2, 2014).await;
+        version(&db, "1.12", 3, 2017).await;
+
+        assert!(reorder(&db).await.unwrap().is_empty());
+        assert_eq!(
+            ordinals(&db).await,
+            [
+                ("1.8".to_owned(), 1),
+                ("1.12".to_owned(), 2),
+                ("1.20".to_owned(), 3)
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn reorder_reports_inverted_ranges() {
+        let db = stand_in().await;
+        let newer = version(&db, "1.20", 1, 2023).await;
+        let older = version(&db, "1.8", 2, 2014).await;
+
+        let owner = users::ActiveModel {
+            email: Set("steve@example.com".to_owned()),
+            username: Set("steve".to_owned()),
+            password: Set(String::new()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let server = servers::ActiveModel {
+            name: Set("Hypixel".to_owned()),
+            description: Set(String::new()),
+            user_id: Set(owner.id),
+            is_premium: Set(0),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        servers_info::ActiveModel {
+            server_id: Set(server.id),
+            address: Set("mc.hypixel.net".to_owned()),
+            port: Set(25565),
+            min_version: Set(newer),
+            max_version: Set(older),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let inverted = reorder(&db).await.unwrap();
+        assert_eq!(inverted.len(), 1);
+        assert_eq!(inverted[0].name, "Hypixel");
+    }
+}